@@ -0,0 +1,74 @@
+//! Dialog Graph
+//!
+//! `fight_arena-dialog-graph [--dot | --mermaid] FILE`
+//!
+//! Draws a dialog file as a graph, written on the standard output:
+//!
+//! - `--mermaid` (by default): to paste in a markdown file, as in the README
+//! - `--dot`: to render with Graphviz, `... --dot FILE | dot -Tsvg > dialog.svg`
+
+use std::{env, fs, process::ExitCode};
+
+use fight_arena::ui::dialog_system::parse_dialog;
+
+const USAGE: &str = "Usage: fight_arena-dialog-graph [--dot | --mermaid] FILE
+
+Draws the dialog FILE (`*.dialog.md`) as a graph, in Mermaid (by default) or in Graphviz DOT.";
+
+enum Format {
+    Dot,
+    Mermaid,
+}
+
+fn main() -> ExitCode {
+    let mut format = Format::Mermaid;
+    let mut file = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "--dot" => format = Format::Dot,
+            "--mermaid" => format = Format::Mermaid,
+            _ if file.is_none() => file = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let file = match file {
+        Some(file) => file,
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let content = match fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(error) => {
+            eprintln!("{}: error: {}", file, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match parse_dialog(&content) {
+        Ok(graph) => {
+            match format {
+                Format::Dot => print!("{}", graph.to_dot()),
+                Format::Mermaid => print!("{}", graph.to_mermaid()),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!(
+                "{}:{}:{}: error: {} (found `{}`)",
+                file, error.line, error.column, error.message, error.token
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Dialog Linter
+//!
+//! `fight_arena-dialog-lint [PATH]...`
+//!
+//! Parses every dialog file (`*.dialog.md`) found in the given files or folders
+//! (`assets/dialogs` by default) and reports:
+//!
+//! - the errors refused by `parse_dialog` (with their line and column)
+//! - the mistakes found by `lint_dialog`
+//!
+//! Exits with a non-zero status if any dialog has an error,
+//! to catch them before running the game.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use fight_arena::ui::{dialog_lint::lint_dialog, dialog_system::parse_dialog};
+
+const DIALOGS_FOLDER: &str = "assets/dialogs";
+const DIALOG_EXTENSION: &str = ".dialog.md";
+
+const USAGE: &str = "Usage: fight_arena-dialog-lint [PATH]...
+
+Checks every dialog file (`*.dialog.md`) in the given files or folders.
+By default, checks the folder `assets/dialogs`.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let paths: Vec<PathBuf> = if args.is_empty() {
+        vec![PathBuf::from(DIALOGS_FOLDER)]
+    } else {
+        args.iter().map(PathBuf::from).collect()
+    };
+
+    let mut files = Vec::new();
+    for path in paths.iter() {
+        if let Err(error) = find_dialog_files(path, &mut files) {
+            eprintln!("{}: error: {}", path.display(), error);
+            return ExitCode::FAILURE;
+        }
+    }
+    files.sort();
+
+    let errors: usize = files.iter().map(|file| lint_file(file)).sum();
+
+    println!(
+        "{} dialog file(s) checked: {} error(s)",
+        files.len(),
+        errors
+    );
+    if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// A file given explicitly is always checked,
+/// a folder is searched (recursively) for dialog files.
+fn find_dialog_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        // raises the error if the file does not exist
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?.path();
+        if entry.is_dir() {
+            find_dialog_files(&entry, files)?;
+        } else if entry.to_string_lossy().ends_with(DIALOG_EXTENSION) {
+            files.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// # Return
+///
+/// The number of errors found in this dialog file
+fn lint_file(file: &Path) -> usize {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(error) => {
+            eprintln!("{}: error: {}", file.display(), error);
+            return 1;
+        }
+    };
+
+    match parse_dialog(&content) {
+        Err(error) => {
+            eprintln!(
+                "{}:{}:{}: error: {} (found `{}`)",
+                file.display(),
+                error.line,
+                error.column,
+                error.message,
+                error.token
+            );
+            1
+        }
+        Ok(graph) => {
+            let lints = lint_dialog(&graph);
+            for lint in lints.iter() {
+                eprintln!("{}: error: {}", file.display(), lint);
+            }
+            lints.len()
+        }
+    }
+}
@@ -34,16 +34,25 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::Velocity;
 use std::time::Duration;
 
+pub mod stat_bars;
 pub mod stats;
+pub mod stuff;
 
 use crate::{
     // combat::stats::*,
-    // combat::stats::{show_hp, show_mana}
-    constants::character::npc::movement::EVASION_TIMER,
+    combat::{
+        stats::{HP, MANA},
+        stuff::Inventory,
+    },
+    constants::character::{npc::movement::EVASION_TIMER, KARMA_MAX, KARMA_MIN},
 
     npc::NPC,
     player::Player,
-    ui::dialog_panel::CloseDialogPanelEvent,
+    ui::{
+        dialog_panel::CloseDialogPanelEvent,
+        dialog_system::{ChoiceEffect, ChoiceEffectEvent, ThrowableEvent},
+        dialog_trigger::TriggerAppExt,
+    },
 };
 
 /// Just help to create a ordered system in the app builder
@@ -68,14 +77,45 @@ impl Plugin for CombatPlugin {
         app.add_event::<SpawnCombatFoesEvent>()
             .add_event::<CombatEvent>()
             .add_event::<CombatExitEvent>()
+            .add_event::<FightEvent>()
+            .add_event::<KarmaChangedEvent>()
+            .init_resource::<stat_bars::StatBarsSettings>()
+            // `-> FightEvent` in a dialog
+            .register_trigger(ThrowableEvent::FIGHT_EVENT, |world, interlocutor, _| {
+                world.send_event(FightEvent { foe: interlocutor });
+            })
+            // `-> Karma(+5)` in a dialog
+            .register_trigger(ThrowableEvent::KARMA, |world, _, trigger| {
+                if let ThrowableEvent::Karma(delta) = trigger {
+                    change_player_karma(world, *delta);
+                }
+            })
+            // `-> GiveItem(potion, 2)` in a dialog
+            .register_trigger(ThrowableEvent::GIVE_ITEM, |world, _, trigger| {
+                if let ThrowableEvent::GiveItem { item, amount } = trigger {
+                    give_item_to_player(world, item, *amount);
+                }
+            })
             .add_systems(
                 Update,
                 (
                     spawn_party_members.before(CombatState::Initiation),
                     enter_combat.in_set(CombatState::Initiation),
+                    start_fight.after(CombatState::Initiation),
+                    apply_choice_effects,
                     exit_combat
                         .in_set(CombatState::Evasion)
+                        .after(start_fight)
                         .before(CombatState::Observation),
+                    end_fight.after(CombatState::Evasion),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    stat_bars::spawn_stat_bars,
+                    stat_bars::update_stat_bars,
+                    stat_bars::show_stat_bars_in_combat,
                 ),
             )
             .add_systems(
@@ -108,6 +148,8 @@ pub struct CombatEvent {
 ///     - combat was stoped by the player ('o')
 ///   - ui::dialog_panel::update_dialog_panel
 ///     - End of the dialog
+///   - combat::mod::end_fight
+///     - every foe of the fight is beaten
 /// Read in
 ///   - combat::exit_combat
 ///     - Add a FairPlayTimer to all enemies involved in the fight
@@ -117,16 +159,70 @@ pub struct CombatEvent {
 #[derive(Event)]
 pub struct CombatExitEvent;
 
+/// Happens when:
+///   - ui::dialog_trigger::throw_trigger_event
+///     - a dialog node with `-> FightEvent` is left
+/// Read in
+///   - combat::mod::start_fight
+///     - enter the fight phase against the foe
+///   - combat::mod::exit_combat
+///     - the end of the talk does not end the fight
+#[derive(Event)]
+pub struct FightEvent {
+    pub foe: Entity,
+}
+
 fn observation() {
     // println!("Now it's your turn...")
 }
 
+/// Happens when:
+///   - combat::apply_choice_effects
+///     - a choice with `| karma: -10;` is picked in a dialog
+///   - combat::change_player_karma
+///     - a dialog node with `-> Karma(+5)` is left
+/// Read in
+///   - any system depending on the player's karma
+#[derive(Event, Debug)]
+pub struct KarmaChangedEvent {
+    pub previous: i32,
+    pub current: i32,
+}
+
 #[derive(Component)]
 pub struct Karma(pub i32);
 
+impl Karma {
+    /// Adds the `delta`, kept between KARMA_MIN and KARMA_MAX
+    ///
+    /// # Return
+    ///
+    /// The KarmaChangedEvent to send, None if the karma is already at its bound
+    pub fn change(&mut self, delta: i32) -> Option<KarmaChangedEvent> {
+        let previous = self.0;
+        self.0 = self.0.saturating_add(delta).clamp(KARMA_MIN, KARMA_MAX);
+
+        if previous == self.0 {
+            None
+        } else {
+            Some(KarmaChangedEvent {
+                previous,
+                current: self.0,
+            })
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct InCombat;
 
+/// A foe the talk turned into a fight (`-> FightEvent`).
+///
+/// The end of the talk does not end the fight: the foe, the player and their party stay InCombat
+/// until the foe is beaten (see `end_fight`).
+#[derive(Component)]
+pub struct Fighting;
+
 #[derive(Clone, Copy, Component)]
 pub struct Leader;
 
@@ -246,6 +342,107 @@ pub fn enter_combat(
     }
 }
 
+/// The talk is over: enter the fight phase against the interlocutor
+pub fn start_fight(
+    mut commands: Commands,
+
+    mut fight_event: EventReader<FightEvent>,
+
+    allies_query: Query<Entity, Or<(With<Player>, With<Recruted>)>>,
+    foes_query: Query<&Name, With<NPC>>,
+) {
+    for FightEvent { foe } in fight_event.iter() {
+        match foes_query.get(*foe) {
+            Ok(name) => {
+                info!("Fight against {}", name);
+                // the dialog can be opened without any CombatEvent ('o'),
+                // and the end of the talk may have already let the foe go
+                commands
+                    .entity(*foe)
+                    .insert((InCombat, Fighting))
+                    .remove::<FairPlayTimer>();
+                for ally in allies_query.iter() {
+                    commands.entity(ally).insert(InCombat);
+                }
+            }
+            Err(e) => warn!("The foe of the FightEvent is not a npc: {:?}", e),
+        }
+    }
+}
+
+/// Adds the `delta` to the player's karma, kept between KARMA_MIN and KARMA_MAX
+pub fn change_player_karma(world: &mut World, delta: i32) {
+    let mut player_query = world.query_filtered::<&mut Karma, With<Player>>();
+    let karma_changed = match player_query.get_single_mut(world) {
+        Ok(mut karma) => {
+            let karma_changed = karma.change(delta);
+            info!("Karma {:+}: {}", delta, karma.0);
+            karma_changed
+        }
+        Err(e) => {
+            warn!("The player has no karma: {:?}", e);
+            None
+        }
+    };
+
+    if let Some(karma_changed) = karma_changed {
+        world.send_event(karma_changed);
+    }
+}
+
+/// Applies the effects of the choice picked in a dialog to the player,
+/// each stat is kept within its bounds.
+pub fn apply_choice_effects(
+    mut choice_effect_event: EventReader<ChoiceEffectEvent>,
+
+    mut player_query: Query<(&mut Karma, &mut HP, &mut MANA), With<Player>>,
+    mut karma_changed_event: EventWriter<KarmaChangedEvent>,
+) {
+    for ChoiceEffectEvent { effects } in choice_effect_event.iter() {
+        let (mut karma, mut hp, mut mana) = match player_query.get_single_mut() {
+            Ok(stats) => stats,
+            Err(e) => {
+                warn!("The player has no stats: {:?}", e);
+                continue;
+            }
+        };
+
+        for effect in effects.iter() {
+            match effect {
+                ChoiceEffect::Karma(delta) => {
+                    if let Some(karma_changed) = karma.change(*delta) {
+                        karma_changed_event.send(karma_changed);
+                    }
+                    info!("Karma {:+}: {}", delta, karma.0);
+                }
+                ChoiceEffect::Hp(delta) => {
+                    hp.current_hp = hp.current_hp.saturating_add(*delta).clamp(0, hp.max_hp);
+                    info!("HP {:+}: {}/{}", delta, hp.current_hp, hp.max_hp);
+                }
+                ChoiceEffect::Mana(delta) => {
+                    mana.current_mana = mana
+                        .current_mana
+                        .saturating_add(*delta)
+                        .clamp(0, mana.max_mana);
+                    info!("Mana {:+}: {}/{}", delta, mana.current_mana, mana.max_mana);
+                }
+            }
+        }
+    }
+}
+
+/// The player receives the `amount` of the `item`
+pub fn give_item_to_player(world: &mut World, item: &str, amount: u32) {
+    let mut player_query = world.query_filtered::<&mut Inventory, With<Player>>();
+    match player_query.get_single_mut(world) {
+        Ok(mut inventory) => {
+            inventory.add(item, amount);
+            info!("{} x{} received: {}", item, amount, inventory.count(item));
+        }
+        Err(e) => warn!("The player has no inventory: {:?}", e),
+    }
+}
+
 /// For each entity in combat, freeze their movement
 pub fn freeze_in_combat(mut characters_query: Query<(Entity, &mut Velocity), With<InCombat>>) {
     // TOTEST: QUESTION: Maybe be not for the member of the company
@@ -268,13 +465,39 @@ pub fn spawn_party_members(
     }
 }
 
+/// The beaten foes (without hp left) stop fighting,
+/// the combat is exited with the last one.
+pub fn end_fight(
+    mut commands: Commands,
+
+    fighting_query: Query<(Entity, &HP, &Name), With<Fighting>>,
+
+    mut ev_combat_exit: EventWriter<CombatExitEvent>,
+) {
+    let mut beaten = 0;
+    for (foe, hp, name) in fighting_query.iter() {
+        if hp.current_hp <= 0 {
+            info!("{} is beaten", name);
+            commands.entity(foe).remove::<Fighting>();
+            beaten += 1;
+        }
+    }
+
+    if beaten > 0 && beaten == fighting_query.iter().len() {
+        ev_combat_exit.send(CombatExitEvent);
+    }
+}
+
 /// exit Combat by pressing 'o'
 ///
 /// apply to all npc involved in a interaction the IdleBehavior
+///
+/// A fight started by the talk (`Fighting`) goes on: its foes and the allies stay InCombat.
 pub fn exit_combat(
     mut commands: Commands,
 
     mut ev_combat_exit: EventReader<CombatExitEvent>,
+    mut fight_event: EventReader<FightEvent>,
 
     allies_query: Query<
         (Entity, &Name),
@@ -285,19 +508,30 @@ pub fn exit_combat(
     >,
 
     foes_query: Query<(Entity, &Name), (With<NPC>, With<InCombat>, Without<Recruted>)>,
+    fighting_query: Query<(), With<Fighting>>,
 
     mut close_dialog_panel_event: EventWriter<CloseDialogPanelEvent>,
 ) {
+    // the foes of a fight started within this frame are not marked `Fighting` yet
+    let new_fights: Vec<Entity> = fight_event.iter().map(|FightEvent { foe }| *foe).collect();
+    let is_fighting = |foe: Entity| new_fights.contains(&foe) || fighting_query.contains(foe);
+
     for _ev in ev_combat_exit.iter() {
         info!("DEBUG: Combat Exit");
 
-        for (allie, _name) in allies_query.iter() {
-            commands.entity(allie).remove::<InCombat>();
+        let fight_goes_on = !new_fights.is_empty() || !fighting_query.is_empty();
+        if !fight_goes_on {
+            for (allie, _name) in allies_query.iter() {
+                commands.entity(allie).remove::<InCombat>();
+            }
         }
 
         // foes AND being an enemy
         // With InCombat and Without Recruted mean that these entities are enemies.
         for (foes, _name) in foes_query.iter() {
+            if is_fighting(foes) {
+                continue;
+            }
             commands.entity(foes).insert(FairPlayTimer {
                 timer: Timer::new(Duration::from_secs(EVASION_TIMER), TimerMode::Once),
             });
@@ -320,3 +554,73 @@ pub fn exit_combat(
         // }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<FightEvent>()
+            .add_event::<CombatExitEvent>()
+            .add_event::<CloseDialogPanelEvent>()
+            .add_systems(
+                Update,
+                (
+                    start_fight,
+                    exit_combat.after(start_fight),
+                    end_fight.after(exit_combat),
+                ),
+            );
+        app
+    }
+
+    #[test]
+    fn test_fight_outlasts_the_talk() {
+        let mut app = app();
+        let player = app
+            .world
+            .spawn((Player, InCombat, Name::new("Player")))
+            .id();
+        let foe = app
+            .world
+            .spawn((NPC, InCombat, HP::default(), Name::new("Olf")))
+            .id();
+
+        // the dialog ends with `-> FightEvent`: both events are sent within the same frame
+        app.world.send_event(FightEvent { foe });
+        app.world.send_event(CombatExitEvent);
+        app.update();
+        app.update();
+
+        assert!(app.world.get::<InCombat>(foe).is_some());
+        assert!(app.world.get::<FairPlayTimer>(foe).is_none());
+        assert!(app.world.get::<InCombat>(player).is_some());
+
+        // the foe is beaten
+        app.world.get_mut::<HP>(foe).unwrap().current_hp = 0;
+        app.update();
+        app.update();
+
+        assert!(app.world.get::<InCombat>(foe).is_none());
+        assert!(app.world.get::<Fighting>(foe).is_none());
+        assert!(app.world.get::<InCombat>(player).is_none());
+    }
+
+    #[test]
+    fn test_talk_without_fight_exits_combat() {
+        let mut app = app();
+        let player = app
+            .world
+            .spawn((Player, InCombat, Name::new("Player")))
+            .id();
+        let foe = app.world.spawn((NPC, InCombat, Name::new("Olf"))).id();
+
+        app.world.send_event(CombatExitEvent);
+        app.update();
+
+        assert!(app.world.get::<InCombat>(foe).is_none());
+        assert!(app.world.get::<FairPlayTimer>(foe).is_some());
+        assert!(app.world.get::<InCombat>(player).is_none());
+    }
+}
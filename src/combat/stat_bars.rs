@@ -0,0 +1,218 @@
+//! Stat Bars
+//!
+//! Every entity with `HP` or `MANA` carries its bars above its head, as child sprites:
+//!
+//! - the HP bar is green for the MC's team (`Team(TEAM_MC)`), red for the foes
+//! - the mana bar is blue
+//! - a bar is filled again as soon as its stat (or the team) changes
+//! - outside a combat (`InCombat`), the bars are hidden
+//!   unless `StatBarsSettings::only_in_combat` is turned off
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    combat::{
+        stats::{HP, MANA},
+        InCombat, Team,
+    },
+    constants::combat::{stat_bars::*, team::TEAM_MC},
+};
+
+/// The stat displayed by a bar, child of the character
+#[derive(Component, Reflect, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum StatBar {
+    Hp,
+    Mana,
+}
+
+/// The part of a StatBar filled according to its stat
+#[derive(Component)]
+pub struct StatBarFill;
+
+#[derive(Resource, Reflect)]
+pub struct StatBarsSettings {
+    /// Hides the bars of the characters not fighting
+    pub only_in_combat: bool,
+}
+
+impl Default for StatBarsSettings {
+    fn default() -> Self {
+        StatBarsSettings {
+            only_in_combat: true,
+        }
+    }
+}
+
+/// # Return
+///
+/// The part of a bar filled, between 0 and 1
+pub fn fill_ratio(current: i32, max: i32) -> f32 {
+    if max > 0 {
+        (current as f32 / max as f32).clamp(0., 1.)
+    } else {
+        0.
+    }
+}
+
+/// The color of the HP bar: allies and foes of the MC
+fn hp_bar_color(team: Option<&Team>) -> Color {
+    if team.is_some_and(|team| team.0 == TEAM_MC) {
+        ALLY_HP_BAR_COLOR
+    } else {
+        FOE_HP_BAR_COLOR
+    }
+}
+
+/// # Return
+///
+/// How much a bar is filled, and its color,
+/// or None if the character does not have this stat
+fn stat_bar_fill(
+    stat_bar: StatBar,
+    hp: Option<&HP>,
+    mana: Option<&MANA>,
+    team: Option<&Team>,
+) -> Option<(f32, Color)> {
+    match stat_bar {
+        StatBar::Hp => hp.map(|hp| (fill_ratio(hp.current_hp, hp.max_hp), hp_bar_color(team))),
+        StatBar::Mana => {
+            mana.map(|mana| (fill_ratio(mana.current_mana, mana.max_mana), MANA_BAR_COLOR))
+        }
+    }
+}
+
+/// Spawns the bar of each stat added to a character:
+/// a character given `MANA` later keeps its single HP bar.
+pub fn spawn_stat_bars(
+    mut commands: Commands,
+    hp_query: Query<(Entity, &HP, Option<&Team>), Added<HP>>,
+    mana_query: Query<(Entity, &MANA), Added<MANA>>,
+) {
+    for (character, hp, team) in hp_query.iter() {
+        commands.entity(character).with_children(|parent| {
+            spawn_stat_bar(
+                parent,
+                StatBar::Hp,
+                HP_BAR_Y_OFFSET,
+                fill_ratio(hp.current_hp, hp.max_hp),
+                hp_bar_color(team),
+            );
+        });
+    }
+
+    for (character, mana) in mana_query.iter() {
+        commands.entity(character).with_children(|parent| {
+            spawn_stat_bar(
+                parent,
+                StatBar::Mana,
+                MANA_BAR_Y_OFFSET,
+                fill_ratio(mana.current_mana, mana.max_mana),
+                MANA_BAR_COLOR,
+            );
+        });
+    }
+}
+
+fn spawn_stat_bar(
+    parent: &mut ChildBuilder,
+    stat_bar: StatBar,
+    y_offset: f32,
+    ratio: f32,
+    color: Color,
+) {
+    parent
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: STAT_BAR_BACKGROUND_COLOR,
+                    custom_size: Some(Vec2::new(STAT_BAR_WIDTH, STAT_BAR_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0., y_offset, STAT_BAR_Z),
+                // shown by show_stat_bars_in_combat
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            stat_bar,
+            Name::new(format!("{:?} Bar", stat_bar)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(STAT_BAR_WIDTH * ratio, STAT_BAR_HEIGHT)),
+                        // filled from the left end of the bar
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(-STAT_BAR_WIDTH / 2., 0., 0.1),
+                    ..default()
+                },
+                StatBarFill,
+            ));
+        });
+}
+
+/// Fills the bars of the characters whose stats or team have changed.
+pub fn update_stat_bars(
+    character_query: Query<
+        (Option<&HP>, Option<&MANA>, Option<&Team>, &Children),
+        Or<(Changed<HP>, Changed<MANA>, Changed<Team>)>,
+    >,
+    bar_query: Query<(&StatBar, &Children)>,
+    mut fill_query: Query<&mut Sprite, With<StatBarFill>>,
+) {
+    for (hp, mana, team, children) in character_query.iter() {
+        for (stat_bar, bar_children) in bar_query.iter_many(children) {
+            let (ratio, color) = match stat_bar_fill(*stat_bar, hp, mana, team) {
+                Some(fill) => fill,
+                None => continue,
+            };
+
+            let mut fills = fill_query.iter_many_mut(bar_children);
+            while let Some(mut sprite) = fills.fetch_next() {
+                sprite.custom_size = Some(Vec2::new(STAT_BAR_WIDTH * ratio, STAT_BAR_HEIGHT));
+                sprite.color = color;
+            }
+        }
+    }
+}
+
+/// Shows the bars of the fighters, or of everyone if not `only_in_combat`.
+pub fn show_stat_bars_in_combat(
+    settings: Res<StatBarsSettings>,
+    fighter_query: Query<(), With<InCombat>>,
+    mut bar_query: Query<(&Parent, &mut Visibility), With<StatBar>>,
+) {
+    for (character, mut visibility) in bar_query.iter_mut() {
+        let shown = !settings.only_in_combat || fighter_query.contains(character.get());
+        visibility.set_if_neq(if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_ratio() {
+        assert_eq!(fill_ratio(25, 50), 0.5);
+        assert_eq!(fill_ratio(50, 50), 1.);
+        // out of bounds
+        assert_eq!(fill_ratio(-5, 50), 0.);
+        assert_eq!(fill_ratio(60, 50), 1.);
+        assert_eq!(fill_ratio(10, 0), 0.);
+    }
+
+    #[test]
+    fn test_hp_bar_color_of_allies_and_foes() {
+        assert_eq!(hp_bar_color(Some(&Team(TEAM_MC))), ALLY_HP_BAR_COLOR);
+        assert_eq!(hp_bar_color(Some(&Team(TEAM_MC + 1))), FOE_HP_BAR_COLOR);
+        assert_eq!(hp_bar_color(None), FOE_HP_BAR_COLOR);
+    }
+}
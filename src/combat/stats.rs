@@ -40,23 +40,6 @@ impl Default for HP {
     }
 }
 
-// TODO: a hp bar close to the entity
-pub fn _show_hp(
-    player_query: Query<&HP, With<Player>>,
-    npc_query: Query<(&HP, &Name), With<NPC>>,
-){
-    let player_hp = player_query.single();
-    for (npc_hp, npc_name) in npc_query.iter() {
-        println!(
-            "{}'s HP: {}/{},", 
-            npc_name, npc_hp.current_hp, npc_hp.max_hp);
-    }
-
-   println!(
-    "player's HP: {}/{}",
-    player_hp.current_hp, player_hp.max_hp);
-}
-
 /// ----------MANA----------
 /// Start of the Game: 0-100mana -> End of the Game: 10 000mana
 /// Can be modified by level, item, buff, debuff, technics.
@@ -76,23 +59,6 @@ impl Default for MANA {
     }
 }
 
-// TODO: a mana bar close to the entity
-pub fn _show_mana(
-    player_query: Query<&MANA, With<Player>>,
-    npc_query: Query<(&MANA, &Name), With<NPC>>,
-){
-    let player_mana = player_query.single();
-    for (npc_mana, npc_name) in npc_query.iter() {
-        println!(
-            "{}'s MANA: {}/{},", 
-            npc_name, npc_mana.current_mana, npc_mana.max_mana);
-    }
-
-   println!(
-    "player's MANA: {}/{}",
-    player_mana.current_mana, player_mana.max_mana);
-}
-
 /// ----------Attack----------
 /// Start of the Game: 10-20 -> End of the Game: ~
 /// Can be modified by level, item, buff, debuff, technics.
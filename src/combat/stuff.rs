@@ -0,0 +1,21 @@
+//! Implement all Stuff: the items carried by an entity
+
+use bevy::{prelude::*, utils::HashMap};
+
+/// All the items carried by an entity, and their amount.
+///
+/// An item is only known by its name (`potion`, `coin`, etc).
+#[derive(Default, Component, Reflect)]
+pub struct Inventory {
+    items: HashMap<String, u32>,
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: &str, amount: u32) {
+        *self.items.entry(item.to_string()).or_default() += amount;
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.items.get(item).copied().unwrap_or_default()
+    }
+}
@@ -89,65 +89,14 @@ pub mod character {
         pub mod dialog {
             // Flibittygibbit
 
-            // TODO: feature - Read at dialog_file instead of CST
-            // CST = path to the file
-
-            pub const RANDOM_DIALOG: &str = "# Fabien\n
-- Enfant, j'ai eu un poney
-- Mais j'ai toujours voulu un agneau\n";
-            pub const OLF_DIALOG: &str = "# Olf
-
-- Il faut absolument sauver les Fabien du Chien Geant
-
-## Morgan
-
-- ... | None
-
-### Olf
-
-- Il me faut donc obtenir le trone
-
-#### Morgan
-
-- ... | None
-- et de l'$ | None
-
-##### Olf
-
-- Et de l'$
-- C'est essentiel
-
-##### Olf
-
-- C'est essentiel\n";
-            pub const FABIEN_DIALOG: &str = "# Fabien
-
-- Hello
-
-## Fabien
-
-- /<3
-
-### Morgan
-
-- Hey | None
-- No Hello | None
-- Want to share a flat ? | None
-
-#### Fabien
-
-- :)
-
-#### Fabien
-
-- :O
-
-#### Fabien
-
-- Sure\n";
-
-            pub const MORGAN_DIALOG: &str =
-                "# Morgan\n\n- Bonjour Florian. /\nComment vas/-tu ? /\nJ'ai faim.\n";
+            // Paths of the dialog files, relative to the `assets` folder,
+            // without their locale: `dialogs/olf` is read from `dialogs/olf.en.dialog.md`
+            // See `ui::dialog_locale::localized_path`
+
+            pub const RANDOM_DIALOG: &str = "dialogs/random";
+            pub const OLF_DIALOG: &str = "dialogs/olf";
+            pub const FABIEN_DIALOG: &str = "dialogs/fabien";
+            pub const MORGAN_DIALOG: &str = "dialogs/morgan";
         }
 
         pub mod movement {
@@ -164,6 +113,9 @@ pub mod character {
 
     pub mod player {
 
+        /// The name of the Main Character in the dialog files
+        pub const MC_NAME: &str = "Morgan";
+
         pub const PLAYER_STARTING_ANIM: usize = 4;
 
         pub const PLAYER_SCALE: f32 = super::CHAR_SCALE;
@@ -185,18 +137,72 @@ pub mod combat {
         pub const TEAM_OLF: i32 = 1;
         pub const TEAM_FABICURION: i32 = 2;
     }
+
+    pub mod stat_bars {
+        use bevy::prelude::Color;
+
+        /// The size of a full bar, before the scale of the character
+        pub const STAT_BAR_WIDTH: f32 = 20.;
+        pub const STAT_BAR_HEIGHT: f32 = 2.;
+        /// The height of the bars above the center of the character
+        pub const HP_BAR_Y_OFFSET: f32 = 15.;
+        pub const MANA_BAR_Y_OFFSET: f32 = 12.;
+        /// Drawn over the character
+        pub const STAT_BAR_Z: f32 = 1.;
+
+        pub const STAT_BAR_BACKGROUND_COLOR: Color = Color::rgba(0.1, 0.1, 0.1, 0.8);
+        /// The HP of the MC's team
+        pub const ALLY_HP_BAR_COLOR: Color = Color::rgb(0.2, 0.7, 0.2);
+        pub const FOE_HP_BAR_COLOR: Color = Color::rgb(0.8, 0.15, 0.15);
+        pub const MANA_BAR_COLOR: Color = Color::rgb(0.2, 0.4, 0.9);
+    }
 }
 
 pub mod ui {
     pub mod dialogs {
         use bevy::prelude::Color;
 
+        /// Delay before a modified dialog file is reloaded.
+        pub const DIALOG_HOT_RELOAD_DELAY_MS: u64 = 200;
+
+        /// The locale of every dialog file: a missing translation falls back to it.
+        pub const DEFAULT_LOCALE: &str = "en";
+        /// The locales cycled through by pressing 'L'.
+        pub const LOCALES: [&str; 2] = ["en", "fr"];
+
+        /// The author of the lines said by nobody (`# Narator`): a node without character.
+        pub const NARATOR: &str = "Narator";
+
+        /// The authors of a dialog are also searched among the entities within this distance of the player.
+        pub const SPEAKER_RANGE: f32 = 100.;
+
         pub const DIALOG_PANEL_ANIMATION_OFFSET: f32 = -1000.;
         pub const DIALOG_BOX_UPDATE_DELTA_S: f32 = 0.05;
+        /// The font size of a `[b]` text, relative to the DialogBox's one
+        pub const DIALOG_BOX_BOLD_FONT_SCALE: f32 = 1.2;
+        /// The offset (in pixels) of a `[shake]` text
+        pub const DIALOG_BOX_SHAKE_AMPLITUDE: f32 = 2.;
+        /// How fast a `[shake]` text trembles (radians per second)
+        pub const DIALOG_BOX_SHAKE_FREQUENCY: f32 = 40.;
         pub const DIALOG_PANEL_ANIMATION_TIME_MS: u64 = 500;
         pub const SCROLL_SIZE: (f32, f32) = (490., 11700. / 45.);
         pub const SCROLL_ANIMATION_DELTA_S: f32 = 0.1;
         pub const SCROLL_ANIMATION_FRAMES_NUMBER: usize = 45;
+        /// The number of PlayerChoice buttons: the choices of a page
+        pub const CHOICES_PER_PAGE: usize = 3;
+        /// The offset (in pixels) from the top of each PlayerChoice button
+        pub const CHOICE_BUTTON_TOPS: [f32; CHOICES_PER_PAGE] = [450., 250., 50.];
+        /// The width (in pixels) of the bar of a timed choice, when full
+        pub const CHOICE_TIMER_BAR_WIDTH: f32 = 300.;
+        pub const CHOICE_TIMER_BAR_COLOR: Color = Color::rgb(0.6, 0.1, 0.1);
+        /// In Auto mode, the time (in seconds) any typed text stays
+        pub const AUTO_ADVANCE_BASE_DELAY_S: f32 = 1.;
+        /// In Auto mode, the time (in seconds) added to read each char of a text
+        pub const AUTO_ADVANCE_DELAY_PER_CHAR_S: f32 = 0.05;
+        /// In Fast-forward mode, the time (in seconds) a line already seen stays
+        pub const FAST_FORWARD_DELAY_S: f32 = 0.1;
+        /// Pixels scrolled in the history panel by a notch of the mouse wheel
+        pub const HISTORY_SCROLL_LINE_HEIGHT: f32 = 20.;
 
         pub const TRANSPARENT_BUTTON: Color = Color::rgba(0., 0., 0., 0.);
         // pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
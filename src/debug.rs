@@ -3,9 +3,19 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
 use crate::{
     collisions::{TesselatedCollider, TesselatedColliderConfig},
+    combat::{
+        stat_bars::{StatBar, StatBarsSettings},
+        stuff::Inventory,
+    },
     ui::{
+        dialog_advance::AdvanceMode,
+        dialog_focus::ChoiceFocus,
+        dialog_history::{DialogHistory, DialogLine},
+        dialog_locale::Locale,
         dialog_panel::DialogPanel,
         dialog_scroll::{PlayerScroll, UpperScroll},
+        dialog_speaker::{Portrait, Speaker},
+        dialog_system::{DialogCursor, DialogGraph, NodeId},
     },
 };
 
@@ -23,7 +33,17 @@ impl Plugin for DebugPlugin {
                 /*                                     UI                                     */
                 /* -------------------------------------------------------------------------- */
 
+                .register_type::<Locale>()
+                .register_type::<AdvanceMode>()
+                .register_type::<ChoiceFocus>()
+                .register_type::<DialogHistory>()
+                .register_type::<DialogLine>()
                 .register_type::<DialogPanel>()
+                .register_type::<DialogGraph>()
+                .register_type::<DialogCursor>()
+                .register_type::<NodeId>()
+                .register_type::<Speaker>()
+                .register_type::<Portrait>()
                 // .register_type::<DialogBox>()
                 .register_type::<UpperScroll>()
                 .register_type::<PlayerScroll>()
@@ -34,6 +54,14 @@ impl Plugin for DebugPlugin {
 
                 .register_type::<TesselatedCollider>()
                 .register_type::<TesselatedColliderConfig>()
+
+                /* -------------------------------------------------------------------------- */
+                /*                                   Combat                                   */
+                /* -------------------------------------------------------------------------- */
+
+                .register_type::<Inventory>()
+                .register_type::<StatBar>()
+                .register_type::<StatBarsSettings>()
                 ;
         }
     }
@@ -0,0 +1,36 @@
+//! Fight Arena: every plugin of the game
+//!
+//! Shared by its binaries:
+//!
+//! - `fight_arena`: the game itself (`main.rs`)
+//! - `fight_arena-dialog-lint`: checks the dialog files (`bin/dialog_lint.rs`)
+//! - `fight_arena-dialog-graph`: draws a dialog file (`bin/dialog_graph.rs`)
+
+#![allow(clippy::redundant_field_names)]
+use bevy::prelude::*;
+
+pub mod collisions;
+pub mod combat;
+pub mod constants;
+pub mod debug;
+pub mod locations;
+pub mod movement;
+pub mod npc;
+pub mod player;
+pub mod spritesheet;
+pub mod ui;
+pub mod world_flags;
+
+use constants::*;
+pub use spritesheet::FabienSheet;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum GameState {
+    /// TODO: feat Menu - put the default on Menu
+    Menu,
+    #[default]
+    Playing,
+    Interaction,
+    Combat,
+    Discussion,
+}
@@ -3,41 +3,27 @@
 //! Follow along with the tutorial serie by Logic Projects <3
 
 #![allow(clippy::redundant_field_names)]
-use bevy::{audio::Volume, prelude::*, render::texture::ImagePlugin, window::WindowResolution};
+use bevy::{
+    asset::ChangeWatcher, audio::Volume, prelude::*, render::texture::ImagePlugin,
+    window::WindowResolution,
+};
 use bevy_rapier2d::prelude::*;
 use bevy_tweening::TweeningPlugin;
+use std::time::Duration;
 
-pub mod collisions;
-mod combat;
-pub mod constants;
-mod debug;
-mod locations;
-mod movement;
-mod npc;
-pub mod player;
-mod spritesheet;
-pub mod ui;
-
-use collisions::RetroPhysicsPlugin;
-use combat::CombatPlugin;
-use constants::*;
-use debug::DebugPlugin;
-use locations::LocationsPlugin;
-use npc::NPCPlugin;
-use player::PlayerPlugin;
-use spritesheet::{FabienPlugin, FabienSheet};
-use ui::UiPlugin;
-
-#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
-pub enum GameState {
-    /// TODO: feat Menu - put the default on Menu
-    Menu,
-    #[default]
-    Playing,
-    Interaction,
-    Combat,
-    Discussion,
-}
+use fight_arena::{
+    collisions::RetroPhysicsPlugin,
+    combat::CombatPlugin,
+    constants::{ui::dialogs::DIALOG_HOT_RELOAD_DELAY_MS, *},
+    debug::DebugPlugin,
+    locations::LocationsPlugin,
+    npc::NPCPlugin,
+    player::PlayerPlugin,
+    spritesheet::FabienPlugin,
+    ui::UiPlugin,
+    world_flags::WorldFlagsPlugin,
+    GameState,
+};
 
 // #[rustfmt::skip]
 fn main() {
@@ -66,7 +52,14 @@ fn main() {
                     }),
                     ..default()
                 })
-                .set(ImagePlugin::default_nearest()),
+                .set(ImagePlugin::default_nearest())
+                // hot-reload the dialog files
+                .set(AssetPlugin {
+                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(
+                        DIALOG_HOT_RELOAD_DELAY_MS,
+                    )),
+                    ..default()
+                }),
             RapierDebugRenderPlugin {
                 mode: DebugRenderMode::all(),
                 ..default()
@@ -81,6 +74,7 @@ fn main() {
             NPCPlugin,
             PlayerPlugin,
             UiPlugin,
+            WorldFlagsPlugin,
         ))
         .add_systems(
             Startup,
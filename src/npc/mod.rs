@@ -6,7 +6,7 @@ use crate::{
     constants::{
         character::{
             npc::{
-                dialog::{FABIEN_DIALOG, OLF_DIALOG},
+                dialog::{FABIEN_DIALOG, OLF_DIALOG, RANDOM_DIALOG},
                 movement::{NPC_SPEED, NPC_SPEED_LEADER},
                 *,
             },
@@ -18,9 +18,18 @@ use crate::{
     npc::{
         aggression::DetectionSensor,
         // idle::IdleBehavior,
-        movement::{give_a_direction, DetectionBehavior, FollowupBehavior, JustWalkBehavior},
+        movement::{
+            give_a_direction, DetectionBehavior, FollowupBehavior, JustWalkBehavior,
+            PursuitBehavior,
+        },
+    },
+    ui::{
+        dialog_cast::{CastDialog, DialogCast, ResetRule},
+        dialog_locale::{localized_path, Locale},
+        dialog_system::{Dialog, DialogCursor, ThrowableEvent},
+        dialog_trigger::TriggerAppExt,
     },
-    ui::dialog_system::Dialog,
+    world_flags::{GameEvent, WorldFlags},
     FabienSheet,
 };
 
@@ -80,6 +89,14 @@ impl Plugin for NPCPlugin {
             .add_event::<aggression::StopChaseEvent>()
             .add_event::<aggression::DetectionModeEvent>()
             .add_event::<aggression::EngagePursuitEvent>()
+            // `-> HasFriend` in a dialog
+            .register_trigger(ThrowableEvent::HAS_FRIEND, recruit)
+            // `-> JoinTeam(TEAM_MC)` in a dialog
+            .register_trigger(ThrowableEvent::JOIN_TEAM, |world, npc, trigger| {
+                if let ThrowableEvent::JoinTeam(team) = trigger {
+                    join_team(world, npc, *team);
+                }
+            })
             .add_systems(Startup, (spawn_characters, spawn_aggresives_characters))
             .add_systems(
                 FixedUpdate,
@@ -107,6 +124,49 @@ impl Plugin for NPCPlugin {
     }
 }
 
+/// The interlocutor joins the MC's team and follows its leader.
+///
+/// Handler of the trigger `HasFriend`.
+pub fn recruit(world: &mut World, npc: Entity, _trigger: &ThrowableEvent) {
+    if join_team(world, npc, TEAM_MC) {
+        world
+            .resource_mut::<WorldFlags>()
+            .raise(GameEvent::HasFriend);
+    }
+}
+
+/// The npc leaves its group to follow the leader of the `team`.
+///
+/// Only the MC's team recruits.
+///
+/// # Return
+///
+/// false if the `npc` is not a npc
+pub fn join_team(world: &mut World, npc: Entity, team: i32) -> bool {
+    let mut npc_entity = match world.get_entity_mut(npc) {
+        Some(npc_entity) if npc_entity.contains::<NPC>() => npc_entity,
+        _ => {
+            warn!("The interlocutor joining the team {} is not a npc", team);
+            return false;
+        }
+    };
+
+    // carefull with more than one leader per team
+    npc_entity.remove::<(Leader, JustWalkBehavior, PursuitBehavior)>();
+    npc_entity.insert((Team(team), FollowupBehavior));
+    if team == TEAM_MC {
+        npc_entity.insert(Recruted);
+    } else {
+        npc_entity.remove::<Recruted>();
+    }
+
+    if let Some(name) = npc_entity.get::<Name>() {
+        info!("{} joins the team {}", name, team);
+    }
+
+    true
+}
+
 // Check in location/temple/mod.rs
 // the npc_z_position
 
@@ -124,7 +184,7 @@ fn spawn_characters(mut commands: Commands, fabien: Res<FabienSheet>) {
                 },
                 ..default()
             },
-            Name::new("NPC Admiral"),
+            Name::new("Admiral"),
             NPC,
             Team(TEAM_MC),
             Recruted,
@@ -169,7 +229,7 @@ fn spawn_characters(mut commands: Commands, fabien: Res<FabienSheet>) {
                 },
                 ..default()
             },
-            Name::new("NPC Hugo"),
+            Name::new("Hugo"),
             NPC,
             Team(TEAM_MC),
             Recruted,
@@ -202,7 +262,12 @@ fn spawn_characters(mut commands: Commands, fabien: Res<FabienSheet>) {
         });
 }
 
-fn spawn_aggresives_characters(mut commands: Commands, fabien: Res<FabienSheet>) {
+fn spawn_aggresives_characters(
+    mut commands: Commands,
+    fabien: Res<FabienSheet>,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+) {
     // let olf_dialog_tree = init_tree_flat(String::from(OLF_DIALOG));
 
     // OLF
@@ -218,7 +283,7 @@ fn spawn_aggresives_characters(mut commands: Commands, fabien: Res<FabienSheet>)
                 },
                 ..default()
             },
-            Name::new("NPC Olf"),
+            Name::new("Olf"),
             NPC,
             Leader,
             Team(TEAM_OLF),
@@ -244,7 +309,8 @@ fn spawn_aggresives_characters(mut commands: Commands, fabien: Res<FabienSheet>)
                 defense_spe: DefenseSpe::default(),
             },
             Dialog {
-                current_node: Some(String::from(OLF_DIALOG)),
+                source: asset_server.load(localized_path(OLF_DIALOG, &locale.0)),
+                cursor: DialogCursor::new(),
             },
             // 5 Fabicurion are hidden within Olf's silhouette
             GroupSize(5),
@@ -268,7 +334,7 @@ fn spawn_aggresives_characters(mut commands: Commands, fabien: Res<FabienSheet>)
 
     // Two FABICURION
     for i in 0..2 {
-        let name = "NPC Fabicurion nmb".replace("nmb", &i.to_string());
+        let name = "Fabicurion nmb".replace("nmb", &i.to_string());
 
         commands
             .spawn((
@@ -315,8 +381,22 @@ fn spawn_aggresives_characters(mut commands: Commands, fabien: Res<FabienSheet>)
                 GroupSize(2),
                 DetectionBehavior,
                 Dialog {
-                    current_node: Some(String::from(FABIEN_DIALOG)),
+                    source: asset_server.load(localized_path(FABIEN_DIALOG, &locale.0)),
+                    cursor: DialogCursor::new(),
                 },
+                // once introduced, they only talk about their childhood
+                DialogCast::new(vec![
+                    CastDialog::new(
+                        asset_server.load(localized_path(FABIEN_DIALOG, &locale.0)),
+                        0,
+                        ResetRule::Once,
+                    ),
+                    CastDialog::new(
+                        asset_server.load(localized_path(RANDOM_DIALOG, &locale.0)),
+                        -1,
+                        ResetRule::Repeatable,
+                    ),
+                ]),
             ))
             .with_children(|parent| {
                 parent.spawn((
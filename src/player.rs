@@ -3,7 +3,7 @@ use bevy_rapier2d::prelude::*;
 
 use crate::{
     // collisions::{TesselatedCollider, TesselatedColliderConfig},
-    combat::{stats::*, InCombat, Karma, Leader, Team},
+    combat::{stats::*, stuff::Inventory, InCombat, Karma, Leader, Team},
     constants::{
         character::{
             npc::dialog::MORGAN_DIALOG, player::*, CHAR_HITBOX_HEIGHT, CHAR_HITBOX_WIDTH,
@@ -12,7 +12,10 @@ use crate::{
         combat::team::TEAM_MC,
     },
     movement::*,
-    ui::dialog_system::Dialog,
+    ui::{
+        dialog_locale::{localized_path, Locale},
+        dialog_system::{Dialog, DialogCursor},
+    },
     FabienSheet,
 };
 
@@ -85,7 +88,12 @@ fn player_movement(
     }
 }
 
-fn spawn_player(mut commands: Commands, fabiens: Res<FabienSheet>) {
+fn spawn_player(
+    mut commands: Commands,
+    fabiens: Res<FabienSheet>,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+) {
     commands
         .spawn((
             SpriteSheetBundle {
@@ -110,9 +118,11 @@ fn spawn_player(mut commands: Commands, fabiens: Res<FabienSheet>) {
             Name::new("Player"),
             Player,
             Dialog {
-                current_node: Some(MORGAN_DIALOG.to_owned()),
+                source: asset_server.load(localized_path(MORGAN_DIALOG, &locale.0)),
+                cursor: DialogCursor::new(),
             },
             Karma(10),
+            Inventory::default(),
             // Combat
             Leader,
             Team(TEAM_MC),
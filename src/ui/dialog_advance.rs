@@ -0,0 +1,197 @@
+//! Dialog Advance Modes
+//!
+//! How the texts of the UpperScroll move on, toggled by the button of the panel:
+//!
+//! - Manual: only when `P` is pressed
+//! - Auto: once typed, each text stays for a reading delay proportional to its length
+//! - Fast-forward: the lines already seen (see `DialogHistory`) are shown entirely and skipped,
+//!   stops at the first new one
+//!
+//! In every mode, the player's choices wait for the player,
+//! and `P` still shows entirely a text still typing, then skips it.
+
+use bevy::prelude::*;
+use std::fmt;
+
+use crate::{
+    constants::ui::dialogs::{
+        AUTO_ADVANCE_BASE_DELAY_S, AUTO_ADVANCE_DELAY_PER_CHAR_S, FAST_FORWARD_DELAY_S,
+    },
+    ui::{
+        dialog_box::DialogBox,
+        dialog_history::DialogHistory,
+        dialog_markup::strip_markup,
+        dialog_panel::{is_said_by_mc, DialogPanel},
+        dialog_player::DialogDiveEvent,
+        dialog_scroll::UpperScroll,
+    },
+};
+
+#[derive(Resource, Reflect, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AdvanceMode {
+    #[default]
+    Manual,
+    Auto,
+    FastForward,
+}
+
+impl AdvanceMode {
+    /// The mode toggled to, by the button of the panel
+    pub fn next(&self) -> AdvanceMode {
+        match self {
+            AdvanceMode::Manual => AdvanceMode::Auto,
+            AdvanceMode::Auto => AdvanceMode::FastForward,
+            AdvanceMode::FastForward => AdvanceMode::Manual,
+        }
+    }
+}
+
+impl fmt::Display for AdvanceMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdvanceMode::Manual => write!(f, "Manual"),
+            AdvanceMode::Auto => write!(f, "Auto"),
+            AdvanceMode::FastForward => write!(f, "Fast-forward"),
+        }
+    }
+}
+
+/// The button of the panel toggling the AdvanceMode
+#[derive(Component)]
+pub struct AdvanceModeButton;
+
+/// # Return
+///
+/// The time (in seconds) a typed `text` stays in Auto mode,
+/// proportional to its length (without its markup)
+pub fn reading_delay(text: &str) -> f32 {
+    AUTO_ADVANCE_BASE_DELAY_S
+        + strip_markup(text).chars().count() as f32 * AUTO_ADVANCE_DELAY_PER_CHAR_S
+}
+
+/// Toggles the AdvanceMode when the AdvanceModeButton is pressed.
+pub fn toggle_advance_mode(
+    mut advance_mode: ResMut<AdvanceMode>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<AdvanceModeButton>)>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            *advance_mode = advance_mode.next();
+            info!("Advance mode: {}", *advance_mode);
+        }
+    }
+}
+
+/// Displays the AdvanceMode on its button.
+pub fn update_advance_mode_button(
+    advance_mode: Res<AdvanceMode>,
+
+    button_query: Query<(Ref<AdvanceModeButton>, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children) in button_query.iter() {
+        if !advance_mode.is_changed() && !button.is_added() {
+            continue;
+        }
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = advance_mode.to_string();
+        }
+    }
+}
+
+/// Moves on the texts of the UpperScroll, in Auto or Fast-forward mode.
+///
+/// `countdown`: the text waited for, and its remaining delay
+pub fn advance_dialog(
+    time: Res<Time>,
+    advance_mode: Res<AdvanceMode>,
+    history: Res<DialogHistory>,
+    mut countdown: Local<Option<(String, Timer)>>,
+
+    panel_query: Query<&DialogPanel>,
+    mut dialog_box_query: Query<(&mut DialogBox, &Children), With<UpperScroll>>,
+    mut text_query: Query<&mut Text>,
+
+    mut dialog_dive_event: EventWriter<DialogDiveEvent>,
+) {
+    let current = match panel_query.get_single() {
+        Ok(panel) => panel.cursor.current(&panel.dialog_tree).cloned(),
+        Err(_) => None,
+    };
+    let (mut dialog_box, children) = match (current.as_ref(), dialog_box_query.get_single_mut()) {
+        // the player's choices wait for the player
+        (Some(current), Ok(upper_box)) if !(current.is_choice() && is_said_by_mc(current)) => {
+            upper_box
+        }
+        _ => {
+            *countdown = None;
+            return;
+        }
+    };
+    let speaker = current.and_then(|current| current.character);
+
+    let delay = match *advance_mode {
+        AdvanceMode::Manual => {
+            *countdown = None;
+            return;
+        }
+        AdvanceMode::Auto if dialog_box.is_finished() => reading_delay(&dialog_box.text),
+        AdvanceMode::FastForward if history.has_seen(speaker.as_deref(), &dialog_box.text) => {
+            if !dialog_box.is_finished() {
+                if let Ok(mut text) = text_query.get_mut(children[0]) {
+                    dialog_box.complete(&mut text);
+                }
+            }
+            FAST_FORWARD_DELAY_S
+        }
+        // still typing, or a new line in Fast-forward
+        _ => {
+            *countdown = None;
+            return;
+        }
+    };
+
+    // a new text to wait for
+    if !matches!(&*countdown, Some((text, _)) if *text == dialog_box.text) {
+        *countdown = Some((
+            dialog_box.text.clone(),
+            Timer::from_seconds(delay, TimerMode::Once),
+        ));
+    }
+
+    let time_is_up = countdown
+        .as_mut()
+        .is_some_and(|(_, timer)| timer.tick(time.delta()).just_finished());
+    if time_is_up {
+        dialog_dive_event.send(DialogDiveEvent {
+            child_index: 0,
+            skip: true,
+            timed_out: false,
+        });
+        // the same text can follow
+        *countdown = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_delay_grows_with_the_text() {
+        assert_eq!(reading_delay(""), AUTO_ADVANCE_BASE_DELAY_S);
+        assert!(reading_delay("It's essential") > reading_delay("Hey"));
+        // the markup is not read
+        assert_eq!(reading_delay("[b]Hey[/b][wait=2]"), reading_delay("Hey"));
+    }
+
+    #[test]
+    fn test_toggle_cycles_through_every_mode() {
+        let mode = AdvanceMode::default();
+
+        assert_eq!(mode, AdvanceMode::Manual);
+        assert_eq!(mode.next(), AdvanceMode::Auto);
+        assert_eq!(mode.next().next(), AdvanceMode::FastForward);
+        assert_eq!(mode.next().next().next(), mode);
+    }
+}
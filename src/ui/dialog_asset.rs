@@ -0,0 +1,101 @@
+//! Dialog Files
+//!
+//! Every dialog is written in a `.dialog.md` file, under `assets/dialogs/`,
+//! following the `parse_dialog` convention.
+//! Each translation has its own file: `olf.en.dialog.md` (see `ui::dialog_locale`).
+//!
+//! - The DialogLoader parses these files into DialogAsset
+//! - Any entity holding a Dialog keeps a Handle to its file
+//!   - its cursor is checked when the file is modified on disk (hot-reload)
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+};
+
+use crate::ui::{
+    dialog_panel::DialogPanel,
+    dialog_system::{parse_dialog, Dialog, DialogGraph},
+};
+
+/// The parsed content of a dialog file.
+#[derive(Debug, TypeUuid, TypePath)]
+#[uuid = "8f435f14-5bac-44dd-88dd-394aed7e3202"]
+pub struct DialogAsset {
+    pub graph: DialogGraph,
+}
+
+/// Reads any `*.dialog.md` file into a DialogAsset.
+#[derive(Default)]
+pub struct DialogLoader;
+
+impl AssetLoader for DialogLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            // the parser only knows the `\n` end marker
+            let content = std::str::from_utf8(bytes)?.replace("\r\n", "\n");
+
+            // refuse the file (and keep the previous version if any)
+            // instead of crashing in the middle of a fight
+            let graph = parse_dialog(&content)?;
+
+            load_context.set_default_asset(LoadedAsset::new(DialogAsset { graph }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialog.md"]
+    }
+}
+
+/// Checks the Dialog of every entity linked with a (re)loaded dialog file.
+///
+/// # Note
+///
+/// When a file is modified on disk (hot-reload) or translated (`ui::dialog_locale`),
+/// the progress in this dialog is kept if the branch taken still exists.
+/// Otherwise, it restarts from its root.
+///
+/// An open DialogPanel is rebuilt with the new version.
+pub fn update_dialog_on_asset_event(
+    mut asset_events: EventReader<AssetEvent<DialogAsset>>,
+    dialog_assets: Res<Assets<DialogAsset>>,
+
+    mut dialog_query: Query<(Entity, &mut Dialog, &Name)>,
+    mut panel_query: Query<&mut DialogPanel>,
+) {
+    for event in asset_events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                let dialog_asset = match dialog_assets.get(handle) {
+                    Some(dialog_asset) => dialog_asset,
+                    None => continue,
+                };
+
+                for (entity, mut dialog, name) in dialog_query.iter_mut() {
+                    if dialog.source == *handle {
+                        info!("{}'s dialog (re)loaded", name);
+                        if !dialog.cursor.is_valid(&dialog_asset.graph) {
+                            warn!("{}'s dialog progress is lost, it restarts", name);
+                            dialog.cursor.restart();
+                        }
+
+                        for mut panel in panel_query.iter_mut() {
+                            if panel.main_interlocutor == entity {
+                                panel.rebuild(&dialog_asset.graph);
+                            }
+                        }
+                    }
+                }
+            }
+            AssetEvent::Removed { handle: _ } => {}
+        }
+    }
+}
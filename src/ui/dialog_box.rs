@@ -1,31 +1,124 @@
 //! Every Structs and methods only about Dialog Box
+//!
+//! The markup of the text (`[color=red]`, `[wait=0.5]`, ...) is read by `ui::dialog_markup`.
 
 use bevy::prelude::*;
 
 use crate::{
-    constants::ui::dialogs::DIALOG_BOX_UPDATE_DELTA_S,
-    ui::dialog_scroll::{PlayerChoice, UpperScroll},
+    constants::ui::dialogs::{
+        DIALOG_BOX_BOLD_FONT_SCALE, DIALOG_BOX_SHAKE_AMPLITUDE, DIALOG_BOX_SHAKE_FREQUENCY,
+        DIALOG_BOX_UPDATE_DELTA_S,
+    },
+    ui::{
+        dialog_markup::{parse_markup, MarkupStyle, RevealStep, RichText},
+        dialog_scroll::{PlayerChoice, UpperScroll},
+    },
 };
 
 /// Represents the entity containing the displayed text as first children.
 ///
-/// Used to animate the Text, letter by letter.
+/// Used to animate the Text, grapheme by grapheme:
+/// each section of the markup is a TextSection of the Text.
 #[derive(Debug, Component)]
 pub struct DialogBox {
     pub text: String,
+    rich_text: RichText,
+    /// The index of the next RevealStep
     progress: usize,
     finished: bool,
     update_timer: Timer,
+    /// The delay between two graphemes, at speed 1
+    update_time: f32,
+    speed: f32,
+    /// The section being revealed is a `[shake]` one
+    shaking: bool,
+    /// The sections of the Text are built from the markup
+    prepared: bool,
+    /// The style of the Text before any markup, taken when first prepared
+    base_style: Option<TextStyle>,
 }
 
 impl DialogBox {
     pub fn new(text: String, update_time: f32) -> Self {
+        // an invalid markup is displayed as is
+        let rich_text = parse_markup(&text).unwrap_or_else(|error| {
+            warn!("Invalid markup in {:?}: {}", text, error);
+            RichText::plain(&text)
+        });
+
         DialogBox {
             text,
+            rich_text,
             progress: 0,
             finished: false,
             update_timer: Timer::from_seconds(update_time, TimerMode::Once),
+            update_time,
+            speed: 1.,
+            shaking: false,
+            prepared: false,
+            base_style: None,
+        }
+    }
+
+    /// Replaces the sections of `text` by the empty sections of the markup,
+    /// styled from `base_style`.
+    fn prepare(&mut self, text: &mut Text) {
+        let base_style = self
+            .base_style
+            .get_or_insert_with(|| text.sections[0].style.clone())
+            .clone();
+
+        text.sections = self
+            .rich_text
+            .sections
+            .iter()
+            .map(|style| TextSection::new("", markup_text_style(style, &base_style)))
+            .collect();
+        // keeps a section to write in, even for an empty text
+        if text.sections.is_empty() {
+            text.sections.push(TextSection::new("", base_style));
+        }
+        self.prepared = true;
+    }
+
+    /// Applies the next steps of the reveal, until a grapheme is shown or a pause is met.
+    ///
+    /// # Return
+    ///
+    /// The delay before the next step (in seconds),
+    /// `0.` once there is no step left: the text stops shaking.
+    fn reveal(&mut self, text: &mut Text) -> f32 {
+        while let Some(step) = self.rich_text.steps.get(self.progress) {
+            self.progress += 1;
+            match step {
+                RevealStep::Grapheme { section, grapheme } => {
+                    text.sections[*section].value.push_str(grapheme);
+                    self.shaking = self.rich_text.sections[*section].shake;
+                    return self.update_time / self.speed;
+                }
+                RevealStep::Wait(seconds) => return *seconds,
+                RevealStep::Speed(speed) => self.speed = *speed,
+            }
         }
+        self.shaking = false;
+        0.
+    }
+
+    /// The whole text is shown
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Ends the animation instantly: shows the whole text, ignoring the pauses.
+    pub fn complete(&mut self, text: &mut Text) {
+        if !self.prepared {
+            self.prepare(text);
+        }
+        while self.progress < self.rich_text.steps.len() {
+            self.reveal(text);
+        }
+        self.finished = true;
+        self.shaking = false;
     }
 
     // Same as new but keep the signature
@@ -37,6 +130,21 @@ impl DialogBox {
     // }
 }
 
+/// The style of a section of the markup, over the `base_style` of the DialogBox.
+fn markup_text_style(style: &MarkupStyle, base_style: &TextStyle) -> TextStyle {
+    TextStyle {
+        font: base_style.font.clone(),
+        font_size: if style.bold {
+            base_style.font_size * DIALOG_BOX_BOLD_FONT_SCALE
+        } else {
+            base_style.font_size
+        },
+        color: style
+            .color
+            .map_or(base_style.color, |[r, g, b]| Color::rgb(r, g, b)),
+    }
+}
+
 /// Happens when
 ///   - ui::dialog_panel::update_upper_scroll
 ///     - updates UpperScroll Text with the UpperScroll infos
@@ -58,8 +166,10 @@ pub struct ResetDialogBoxEvent {
     pub text: String,
 }
 
-/// Animates, letter by letter, each Text.
+/// Animates, grapheme by grapheme, each Text.
 /// ( being the DialogBox's 1rt child )
+///
+/// Follows the pauses (`[wait=0.5]`) and speeds (`[speed=2]`) of the markup.
 pub fn update_dialog_box(
     time: Res<Time>,
     mut dialog_box_query: Query<(&mut DialogBox, &Children)>,
@@ -69,23 +179,18 @@ pub fn update_dialog_box(
         dialog_box.update_timer.tick(time.delta());
 
         if dialog_box.update_timer.finished() && !dialog_box.finished {
-            // let mut text = text_query.get_mut(children[0]).unwrap();
             match text_query.get_mut(children[0]) {
                 Ok(mut text) => {
-                    // prompt the simple text
-                    // FIXME: bug - if the given text contains a accent this will crash
-                    match dialog_box.text.chars().nth(dialog_box.progress) {
-                        // will ignore any louche symbol
-                        // FIXME: infinite call when there is a accent
-                        None => warn!("Accent Typical Crash"),
-                        Some(next_letter) => {
-                            text.sections[0].value.push(next_letter);
-
-                            dialog_box.progress += 1;
-                            if dialog_box.progress >= dialog_box.text.len() {
-                                dialog_box.finished = true;
-                            }
-                        }
+                    if !dialog_box.prepared {
+                        dialog_box.prepare(&mut text);
+                    }
+
+                    let delay = dialog_box.reveal(&mut text);
+                    dialog_box.update_timer = Timer::from_seconds(delay, TimerMode::Once);
+
+                    if dialog_box.progress >= dialog_box.rich_text.steps.len() {
+                        dialog_box.finished = true;
+                        dialog_box.shaking = false;
                     }
                 }
                 // FIXME: If there is no TEXT then insert one in it
@@ -124,10 +229,15 @@ pub fn reset_dialog_box(
                     Err(e) => warn!("No Text Section: {:?}", e),
                     Ok(mut text) => {
                         if dialog_box.text != event.text.clone() {
-                            text.sections[0].value.clear();
+                            for section in text.sections.iter_mut() {
+                                section.value.clear();
+                            }
                             // replace current DialogBox with a brand new one
+                            // which keeps the style of the Text before any markup
+                            let base_style = dialog_box.base_style.take();
                             *dialog_box =
                                 DialogBox::new(event.text.clone(), DIALOG_BOX_UPDATE_DELTA_S);
+                            dialog_box.base_style = base_style;
                         }
                     }
                 }
@@ -135,3 +245,70 @@ pub fn reset_dialog_box(
         }
     }
 }
+
+/// Makes the Text of a DialogBox tremble while a `[shake]` section is revealed.
+pub fn shake_dialog_box(
+    time: Res<Time>,
+    dialog_box_query: Query<(&DialogBox, &Children)>,
+    mut style_query: Query<&mut Style, With<Text>>,
+) {
+    for (dialog_box, children) in dialog_box_query.iter() {
+        if let Ok(mut style) = style_query.get_mut(children[0]) {
+            let left = if dialog_box.shaking {
+                Val::Px(
+                    DIALOG_BOX_SHAKE_AMPLITUDE
+                        * (time.elapsed_seconds() * DIALOG_BOX_SHAKE_FREQUENCY).sin(),
+                )
+            } else {
+                Val::Auto
+            };
+            // avoids changing the Style of every Text, every frame
+            if style.left != left {
+                style.left = left;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_text() -> Text {
+        Text::from_section("", TextStyle::default())
+    }
+
+    #[test]
+    fn test_reveal_shakes_the_shake_sections() {
+        let mut text = empty_text();
+        let mut dialog_box = DialogBox::new("[shake]Grr[/shake]".to_string(), 0.1);
+        dialog_box.prepare(&mut text);
+
+        dialog_box.reveal(&mut text);
+        assert!(dialog_box.shaking);
+    }
+
+    #[test]
+    fn test_finished_box_does_not_shake() {
+        let mut text = empty_text();
+        let mut dialog_box = DialogBox::new("[shake]Grr[/shake]".to_string(), 0.1);
+        dialog_box.prepare(&mut text);
+
+        while dialog_box.reveal(&mut text) > 0. {}
+        assert!(!dialog_box.shaking);
+
+        let mut text = empty_text();
+        let mut dialog_box = DialogBox::new("Well... [shake]Grr[/shake]".to_string(), 0.1);
+        dialog_box.complete(&mut text);
+
+        assert!(dialog_box.is_finished());
+        assert!(!dialog_box.shaking);
+        assert_eq!(
+            text.sections
+                .iter()
+                .map(|section| section.value.as_str())
+                .collect::<String>(),
+            "Well... Grr"
+        );
+    }
+}
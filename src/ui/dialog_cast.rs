@@ -0,0 +1,271 @@
+//! Dialog Cast
+//!
+//! A npc can own a whole cast of dialogs, instead of a single one.
+//!
+//! - Engaging a talk chooses a certain dialog from the cast
+//!   - a dialog left mid-way is resumed
+//!     - UNLESS a story dialog (`overrides`) with a higher priority is now available:
+//!       the previous dialog is cancelled to stick to the main line
+//!   - otherwise, the available dialog with the highest priority is chosen
+//! - Finishing a dialog applies its ResetRule
+//!   - Once, Repeatable or after a Cooldown
+
+use bevy::prelude::*;
+
+use crate::{
+    ui::{
+        dialog_asset::DialogAsset,
+        dialog_panel::{CloseDialogPanelEvent, DialogPanel},
+        dialog_system::DialogCursor,
+    },
+    world_flags::{GameEvent, WorldFlags},
+};
+
+/// What happens to a dialog of the cast once finished.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ResetRule {
+    /// Never said again
+    Once,
+    /// Can be said again from the start
+    Repeatable,
+    /// Can be said again from the start, after this number of seconds
+    Cooldown(f64),
+}
+
+/// A dialog of the cast and its rules.
+#[derive(Clone, Debug)]
+pub struct CastDialog {
+    pub source: Handle<DialogAsset>,
+    /// The available dialog with the highest priority is chosen
+    pub priority: i32,
+    pub reset: ResetRule,
+    /// The dialog is only available once all these events have happened
+    pub required: Vec<GameEvent>,
+    /// Cancels a dialog left mid-way, if it has a lower priority
+    pub overrides: bool,
+    /// The progress within this dialog
+    pub cursor: DialogCursor,
+    /// Finished with the ResetRule::Once
+    spent: bool,
+    /// When the dialog is available again (in seconds since the startup)
+    available_at: f64,
+}
+
+impl CastDialog {
+    pub fn new(source: Handle<DialogAsset>, priority: i32, reset: ResetRule) -> CastDialog {
+        CastDialog {
+            source,
+            priority,
+            reset,
+            required: Vec::new(),
+            overrides: false,
+            cursor: DialogCursor::new(),
+            spent: false,
+            available_at: 0.,
+        }
+    }
+
+    /// A dialog of the main line:
+    /// available once all the `required` events have happened,
+    /// it cancels any dialog left mid-way with a lower priority.
+    pub fn story(
+        source: Handle<DialogAsset>,
+        priority: i32,
+        reset: ResetRule,
+        required: Vec<GameEvent>,
+    ) -> CastDialog {
+        CastDialog {
+            required,
+            overrides: true,
+            ..CastDialog::new(source, priority, reset)
+        }
+    }
+
+    pub fn is_available(&self, world_flags: &WorldFlags, now: f64) -> bool {
+        !self.spent && now >= self.available_at && world_flags.has_all(&self.required)
+    }
+
+    fn finish(&mut self, now: f64) {
+        match self.reset {
+            ResetRule::Once => self.spent = true,
+            ResetRule::Repeatable => {}
+            ResetRule::Cooldown(seconds) => self.available_at = now + seconds,
+        }
+        self.cursor.restart();
+    }
+}
+
+/// All the dialogs a npc can say.
+///
+/// The dialog engaged is copied in the Dialog of the npc.
+#[derive(Component, Clone, Debug)]
+pub struct DialogCast {
+    pub dialogs: Vec<CastDialog>,
+    /// The dialog being said (or left mid-way)
+    engaged: Option<usize>,
+}
+
+impl DialogCast {
+    pub fn new(dialogs: Vec<CastDialog>) -> DialogCast {
+        DialogCast {
+            dialogs,
+            engaged: None,
+        }
+    }
+
+    pub fn engaged(&self) -> Option<&CastDialog> {
+        self.engaged.map(|index| &self.dialogs[index])
+    }
+
+    /// Chooses the dialog said when a talk is engaged.
+    ///
+    /// # Return
+    ///
+    /// None if no dialog of the cast is available
+    pub fn engage(&mut self, world_flags: &WorldFlags, now: f64) -> Option<&CastDialog> {
+        // the first one on equal priorities
+        let best = self
+            .dialogs
+            .iter()
+            .enumerate()
+            .filter(|(_, dialog)| dialog.is_available(world_flags, now))
+            .fold(
+                None,
+                |best: Option<(usize, &CastDialog)>, (index, dialog)| match best {
+                    Some((_, best_dialog)) if best_dialog.priority >= dialog.priority => best,
+                    _ => Some((index, dialog)),
+                },
+            )
+            .map(|(index, _)| index);
+
+        match (self.engaged, best) {
+            (Some(left), Some(best))
+                if best != left
+                    && self.dialogs[best].overrides
+                    && self.dialogs[best].priority > self.dialogs[left].priority =>
+            {
+                // in case of big event, cancel previous dialog to stick to the main line
+                self.dialogs[left].cursor.restart();
+                self.engaged = Some(best);
+            }
+            // leaving mid course saves the current dialog
+            (Some(_), _) => {}
+            (None, best) => self.engaged = best,
+        }
+
+        self.engaged()
+    }
+
+    /// Saves the progress in the engaged dialog, when the talk is left.
+    ///
+    /// A finished dialog applies its ResetRule.
+    pub fn leave(&mut self, cursor: &DialogCursor, now: f64) {
+        if let Some(index) = self.engaged {
+            let dialog = &mut self.dialogs[index];
+            if cursor.is_ended() {
+                dialog.finish(now);
+                self.engaged = None;
+            } else {
+                dialog.cursor = cursor.clone();
+            }
+        }
+    }
+}
+
+/// Saves the progress of the talk in the cast of the interlocutor,
+/// when the DialogPanel is closed.
+pub fn leave_cast_dialog(
+    mut close_dialog_panel_events: EventReader<CloseDialogPanelEvent>,
+
+    panel_query: Query<&DialogPanel>,
+    mut cast_query: Query<&mut DialogCast>,
+    time: Res<Time>,
+) {
+    for CloseDialogPanelEvent in close_dialog_panel_events.iter() {
+        if let Ok(panel) = panel_query.get_single() {
+            if let Ok(mut cast) = cast_query.get_mut(panel.main_interlocutor) {
+                cast.leave(&panel.cursor, time.elapsed_seconds_f64());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::dialog_system::NodeId;
+
+    fn cast() -> DialogCast {
+        DialogCast::new(vec![
+            CastDialog::new(Handle::default(), 0, ResetRule::Once),
+            CastDialog::new(Handle::default(), -1, ResetRule::Repeatable),
+            CastDialog::story(
+                Handle::default(),
+                10,
+                ResetRule::Cooldown(60.),
+                vec![GameEvent::FirstKill],
+            ),
+        ])
+    }
+
+    fn engaged_priority(cast: &mut DialogCast, world_flags: &WorldFlags, now: f64) -> i32 {
+        cast.engage(world_flags, now).unwrap().priority
+    }
+
+    #[test]
+    fn test_engage_priority_and_reset_rules() {
+        let mut cast = cast();
+        let world_flags = WorldFlags::default();
+
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 0.), 0);
+        cast.leave(&DialogCursor::ended(), 0.);
+
+        // the first one is said once
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 1.), -1);
+        cast.leave(&DialogCursor::ended(), 1.);
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 2.), -1);
+    }
+
+    #[test]
+    fn test_leave_mid_way() {
+        let mut cast = cast();
+        let world_flags = WorldFlags::default();
+        let cursor = DialogCursor {
+            current: Some(NodeId(3)),
+            path: vec![1],
+            ..DialogCursor::new()
+        };
+
+        cast.engage(&world_flags, 0.);
+        cast.leave(&cursor, 0.);
+
+        assert_eq!(cast.engage(&world_flags, 1.).unwrap().cursor, cursor);
+    }
+
+    #[test]
+    fn test_story_overrides() {
+        let mut cast = cast();
+        let mut world_flags = WorldFlags::default();
+        let cursor = DialogCursor {
+            current: Some(NodeId(3)),
+            path: vec![1],
+            ..DialogCursor::new()
+        };
+
+        cast.engage(&world_flags, 0.);
+        cast.leave(&cursor, 0.);
+
+        world_flags.raise(GameEvent::FirstKill);
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 1.), 10);
+        // the cancelled dialog restarts
+        assert_eq!(cast.dialogs[0].cursor, DialogCursor::new());
+
+        // cooldown
+        cast.leave(&DialogCursor::ended(), 1.);
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 2.), 0);
+        cast.leave(&DialogCursor::ended(), 2.);
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 30.), -1);
+        cast.leave(&DialogCursor::ended(), 30.);
+        assert_eq!(engaged_priority(&mut cast, &world_flags, 61.), 10);
+    }
+}
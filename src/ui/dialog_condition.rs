@@ -0,0 +1,316 @@
+//! Dialog Condition Expressions
+//!
+//! The condition of a choice can hold a boolean expression, next to its other clauses:
+//! `- Fight | karma >= 10 && (event: BeatTheGame || party.has(Hugo)) && !event: FirstKill`
+//!
+//! - `&&`, `||`, `!` and parentheses
+//! - `event: GameEvent`: this event has already happened
+//! - `party.has(Name)`: this companion has been recruited
+//! - `hesitated`: the player let the time of their last choice run out (see `ui::dialog_timer`)
+//! - a comparison (`>=`, `<=`, `>`, `<`, `==`, `!=`) of a number and
+//!   - `karma`
+//!   - `hp`: the ratio of the player's hp, from 0 to 1 (`hp < 0.5`)
+//!   - `party.size`: the player and their recruits
+//!   - `item(potion)`: the amount of this item carried by the player
+//!
+//! The special chars of an argument are escaped by `/`: `party.has(Fabicurion /(2/))`
+//!
+//! An expression is parsed once, with its dialog file (`parse_dialog`),
+//! then evaluated by `DialogCondition::is_verified` against a ConditionContext built from the ECS.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{
+    combat::stuff::Inventory,
+    ui::dialog_system::escape_argument,
+    world_flags::{GameEvent, WorldFlags},
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    /// From the longest to the shortest: `>=` is read before `>`
+    pub const ALL: [Comparison; 6] = [
+        Comparison::GreaterOrEqual,
+        Comparison::LessOrEqual,
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::Greater,
+        Comparison::Less,
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+        }
+    }
+
+    pub fn compare(&self, left: f32, right: f32) -> bool {
+        match self {
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+/// A value of the game, compared to a number.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub enum Operand {
+    Karma,
+    /// The ratio of the player's hp, from 0 to 1
+    Hp,
+    /// The player and their recruits
+    PartySize,
+    /// The amount of this item carried by the player
+    Item(String),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Karma => write!(f, "karma"),
+            Operand::Hp => write!(f, "hp"),
+            Operand::PartySize => write!(f, "party.size"),
+            Operand::Item(item) => write!(f, "item({})", escape_argument(item)),
+        }
+    }
+}
+
+/// A boolean expression, written in the condition of a choice.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub enum ConditionExpr {
+    And(Box<ConditionExpr>, Box<ConditionExpr>),
+    Or(Box<ConditionExpr>, Box<ConditionExpr>),
+    Not(Box<ConditionExpr>),
+    /// `event: FirstKill`
+    Event(GameEvent),
+    /// `party.has(Hugo)`
+    PartyHas(String),
+    /// `hesitated`
+    Hesitated,
+    /// `karma >= 10`
+    Compare(Operand, Comparison, f32),
+}
+
+impl ConditionExpr {
+    pub fn evaluate(&self, context: &ConditionContext) -> bool {
+        match self {
+            ConditionExpr::And(left, right) => left.evaluate(context) && right.evaluate(context),
+            ConditionExpr::Or(left, right) => left.evaluate(context) || right.evaluate(context),
+            ConditionExpr::Not(expression) => !expression.evaluate(context),
+            ConditionExpr::Event(event) => context.world_flags.has(*event),
+            ConditionExpr::PartyHas(name) => context.party.contains(name),
+            ConditionExpr::Hesitated => context.hesitated,
+            ConditionExpr::Compare(operand, comparison, number) => {
+                comparison.compare(context.value(operand), *number)
+            }
+        }
+    }
+
+    /// `||` binds less than `&&`, which binds less than `!`
+    fn precedence(&self) -> u8 {
+        match self {
+            ConditionExpr::Or(..) => 0,
+            ConditionExpr::And(..) => 1,
+            _ => 2,
+        }
+    }
+
+    /// Writes the parentheses needed to be read back as the same expression.
+    fn fmt_within(&self, f: &mut fmt::Formatter, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "(")?;
+            self.fmt_within(f, 0)?;
+            return write!(f, ")");
+        }
+
+        match self {
+            ConditionExpr::And(left, right) => {
+                left.fmt_within(f, 1)?;
+                write!(f, " && ")?;
+                right.fmt_within(f, 2)
+            }
+            ConditionExpr::Or(left, right) => {
+                left.fmt_within(f, 0)?;
+                write!(f, " || ")?;
+                right.fmt_within(f, 1)
+            }
+            ConditionExpr::Not(expression) => {
+                write!(f, "!")?;
+                expression.fmt_within(f, 2)
+            }
+            ConditionExpr::Event(event) => write!(f, "event: {}", event),
+            ConditionExpr::PartyHas(name) => write!(f, "party.has({})", escape_argument(name)),
+            ConditionExpr::Hesitated => write!(f, "hesitated"),
+            ConditionExpr::Compare(operand, comparison, number) => {
+                write!(f, "{} {} {}", operand, comparison.symbol(), number)
+            }
+        }
+    }
+}
+
+/// Written as in a dialog file: `karma >= 10 && !event: FirstKill`
+impl fmt::Display for ConditionExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_within(f, 0)
+    }
+}
+
+/// The state of the game a condition is checked against.
+pub struct ConditionContext<'a> {
+    pub karma: i32,
+    pub world_flags: &'a WorldFlags,
+    /// The names of the recruited companions
+    pub party: Vec<String>,
+    /// The ratio of the player's hp, from 0 to 1
+    pub hp_ratio: f32,
+    pub inventory: Option<&'a Inventory>,
+    /// The player let the time of their last choice run out (see `DialogCursor::hesitated`)
+    pub hesitated: bool,
+}
+
+impl<'a> ConditionContext<'a> {
+    /// A player alone, in full health and carrying nothing
+    pub fn new(karma: i32, world_flags: &'a WorldFlags) -> ConditionContext<'a> {
+        ConditionContext {
+            karma,
+            world_flags,
+            party: Vec::new(),
+            hp_ratio: 1.,
+            inventory: None,
+            hesitated: false,
+        }
+    }
+
+    pub fn value(&self, operand: &Operand) -> f32 {
+        match operand {
+            Operand::Karma => self.karma as f32,
+            Operand::Hp => self.hp_ratio,
+            Operand::PartySize => (1 + self.party.len()) as f32,
+            Operand::Item(item) => {
+                self.inventory.map_or(0, |inventory| inventory.count(item)) as f32
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(operand: Operand, comparison: Comparison, number: f32) -> Box<ConditionExpr> {
+        Box::new(ConditionExpr::Compare(operand, comparison, number))
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut world_flags = WorldFlags::default();
+        world_flags.raise(GameEvent::FirstKill);
+        let mut inventory = Inventory::default();
+        inventory.add("potion", 2);
+
+        let context = ConditionContext {
+            party: vec![String::from("Hugo")],
+            hp_ratio: 0.25,
+            inventory: Some(&inventory),
+            ..ConditionContext::new(10, &world_flags)
+        };
+
+        assert!(compare(Operand::Karma, Comparison::GreaterOrEqual, 10.).evaluate(&context));
+        assert!(compare(Operand::Hp, Comparison::Less, 0.5).evaluate(&context));
+        assert!(compare(Operand::PartySize, Comparison::Equal, 2.).evaluate(&context));
+        assert!(compare(
+            Operand::Item(String::from("potion")),
+            Comparison::Greater,
+            1.
+        )
+        .evaluate(&context));
+        assert!(!compare(
+            Operand::Item(String::from("coin")),
+            Comparison::NotEqual,
+            0.
+        )
+        .evaluate(&context));
+
+        let expression = ConditionExpr::And(
+            Box::new(ConditionExpr::Or(
+                Box::new(ConditionExpr::Event(GameEvent::BeatTheGame)),
+                Box::new(ConditionExpr::PartyHas(String::from("Hugo"))),
+            )),
+            Box::new(ConditionExpr::Not(Box::new(ConditionExpr::Event(
+                GameEvent::FirstKill,
+            )))),
+        );
+        assert!(!expression.evaluate(&context));
+        assert!(expression.evaluate(&ConditionContext {
+            party: vec![String::from("Hugo")],
+            ..ConditionContext::new(0, &WorldFlags::default())
+        }));
+    }
+
+    #[test]
+    fn test_hesitated_is_held_by_the_context() {
+        let world_flags = WorldFlags::default();
+        let hesitated = ConditionExpr::Hesitated;
+
+        assert!(!hesitated.evaluate(&ConditionContext::new(0, &world_flags)));
+        assert!(hesitated.evaluate(&ConditionContext {
+            hesitated: true,
+            ..ConditionContext::new(0, &world_flags)
+        }));
+        assert_eq!(
+            ConditionExpr::Not(Box::new(hesitated)).to_string(),
+            "!hesitated"
+        );
+    }
+
+    #[test]
+    fn test_display_parentheses() {
+        let event = |event| Box::new(ConditionExpr::Event(event));
+
+        let expression = ConditionExpr::And(
+            Box::new(ConditionExpr::Or(
+                event(GameEvent::BeatTheGame),
+                event(GameEvent::AreaCleared),
+            )),
+            Box::new(ConditionExpr::Not(Box::new(ConditionExpr::And(
+                event(GameEvent::FirstKill),
+                compare(Operand::Hp, Comparison::LessOrEqual, 0.5),
+            )))),
+        );
+        assert_eq!(
+            expression.to_string(),
+            "(event: BeatTheGame || event: AreaCleared) && !(event: FirstKill && hp <= 0.5)"
+        );
+
+        let expression = ConditionExpr::Or(
+            event(GameEvent::BeatTheGame),
+            Box::new(ConditionExpr::And(
+                event(GameEvent::FirstKill),
+                compare(Operand::Karma, Comparison::Less, -10.),
+            )),
+        );
+        assert_eq!(
+            expression.to_string(),
+            "event: BeatTheGame || event: FirstKill && karma < -10"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! Dialog Export
+//!
+//! Draws a DialogGraph, to look at a story branch as a graph.
+//!
+//! - `to_dot()`: Graphviz (`dot -Tsvg`)
+//! - `to_mermaid()`: Mermaid, as the combat flow in the README
+//!
+//! Each node shows its speaker and its texts.
+//! Each edge shows the choice (and its condition) leading to the child,
+//! and the trigger events thrown when the node is left.
+//! A jump (`=> label`) is dashed.
+//! A choice or a trigger ending the dialog leads to a last `End` node.
+//!
+//! See the `fight_arena-dialog-graph` binary to draw any dialog file.
+
+use crate::{
+    constants::ui::dialogs::NARATOR,
+    ui::dialog_system::{write_condition, DialogGraph, DialogType, NodeId},
+};
+
+/// A node, with the lines of its label
+struct Vertex {
+    id: String,
+    label: Vec<String>,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    label: Vec<String>,
+    jump: bool,
+}
+
+const END_ID: &str = "dialog_end";
+
+impl DialogGraph {
+    /// # Return
+    ///
+    /// This graph in the Graphviz DOT language
+    ///
+    /// ```dot
+    /// digraph dialog {
+    ///     node [shape=box];
+    ///     n0 [label="Fabien\n- Hello"];
+    ///     n0 -> n1 [label="Hey | None"];
+    /// }
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let (vertices, edges) = self.export();
+
+        let mut res = String::from("digraph dialog {\n    node [shape=box];\n");
+        for vertex in vertices.iter() {
+            let shape = if vertex.id == END_ID {
+                ", shape=doublecircle"
+            } else {
+                ""
+            };
+            res.push_str(&format!(
+                "    {} [label=\"{}\"{}];\n",
+                vertex.id,
+                dot_label(&vertex.label),
+                shape
+            ));
+        }
+        for edge in edges.iter() {
+            let mut attributes = Vec::new();
+            if !edge.label.is_empty() {
+                attributes.push(format!("label=\"{}\"", dot_label(&edge.label)));
+            }
+            if edge.jump {
+                attributes.push("style=dashed".to_string());
+            }
+
+            if attributes.is_empty() {
+                res.push_str(&format!("    {} -> {};\n", edge.from, edge.to));
+            } else {
+                res.push_str(&format!(
+                    "    {} -> {} [{}];\n",
+                    edge.from,
+                    edge.to,
+                    attributes.join(", ")
+                ));
+            }
+        }
+        res.push_str("}\n");
+
+        res
+    }
+
+    /// # Return
+    ///
+    /// This graph as a Mermaid flowchart
+    ///
+    /// ```mermaid
+    /// graph TD
+    ///     n0["Fabien<br/>- Hello"]
+    ///     n0 -->|"Hey | None"| n1
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        let (vertices, edges) = self.export();
+
+        let mut res = String::from("graph TD\n");
+        for vertex in vertices.iter() {
+            if vertex.id == END_ID {
+                res.push_str(&format!(
+                    "    {}((\"{}\"))\n",
+                    vertex.id,
+                    mermaid_label(&vertex.label)
+                ));
+            } else {
+                res.push_str(&format!(
+                    "    {}[\"{}\"]\n",
+                    vertex.id,
+                    mermaid_label(&vertex.label)
+                ));
+            }
+        }
+        for edge in edges.iter() {
+            let arrow = if edge.jump { "-.->" } else { "-->" };
+            if edge.label.is_empty() {
+                res.push_str(&format!("    {} {} {}\n", edge.from, arrow, edge.to));
+            } else {
+                res.push_str(&format!(
+                    "    {} {}|\"{}\"| {}\n",
+                    edge.from,
+                    arrow,
+                    mermaid_label(&edge.label),
+                    edge.to
+                ));
+            }
+        }
+
+        res
+    }
+
+    fn export(&self) -> (Vec<Vertex>, Vec<Edge>) {
+        let mut vertices = Vec::new();
+        let mut edges = Vec::new();
+        self.export_aux(NodeId::ROOT, &mut vertices, &mut edges);
+
+        if edges.iter().any(|edge| edge.to == END_ID) {
+            vertices.push(Vertex {
+                id: END_ID.to_string(),
+                label: vec!["End".to_string()],
+            });
+        }
+
+        (vertices, edges)
+    }
+
+    fn export_aux(&self, id: NodeId, vertices: &mut Vec<Vertex>, edges: &mut Vec<Edge>) {
+        let node = &self[id];
+
+        let mut label = vec![match &node.character {
+            Some(name) => name.clone(),
+            None => String::from(NARATOR),
+        }];
+        let mut choices = Vec::new();
+        for dialog in node.dialog_type.iter() {
+            match dialog {
+                DialogType::Text(text) => label.push(format!("- {}", text)),
+                DialogType::Choice {
+                    text,
+                    condition,
+                    effects,
+                    priority,
+                    weight,
+                } => {
+                    let mut choice = format!(
+                        "{} | {}",
+                        text,
+                        write_condition(condition, *priority, *weight)
+                    );
+                    if !effects.is_empty() {
+                        let effects: Vec<String> =
+                            effects.iter().map(|effect| effect.to_string()).collect();
+                        choice.push_str(&format!(" | {}", effects.join(" ")));
+                    }
+                    choices.push(choice);
+                }
+            }
+        }
+        vertices.push(Vertex {
+            id: vertex_id(id),
+            label,
+        });
+
+        let triggers = if node.trigger_event.is_empty() {
+            None
+        } else {
+            let triggers: Vec<String> = node
+                .trigger_event
+                .iter()
+                .map(|trigger| trigger.to_string())
+                .collect();
+            Some(format!("-> {}", triggers.join(", ")))
+        };
+        let edge_label = |choice: Option<&String>| -> Vec<String> {
+            choice
+                .iter()
+                .map(|choice| choice.to_string())
+                .chain(triggers.clone())
+                .collect()
+        };
+
+        for (child_index, child) in node.children.iter().enumerate() {
+            edges.push(Edge {
+                from: vertex_id(id),
+                to: vertex_id(*child),
+                label: edge_label(choices.get(child_index)),
+                jump: self.is_jump(id, child_index),
+            });
+        }
+        // the choices without child end the dialog
+        for choice in choices.iter().skip(node.children.len()) {
+            edges.push(Edge {
+                from: vertex_id(id),
+                to: END_ID.to_string(),
+                label: edge_label(Some(choice)),
+                jump: false,
+            });
+        }
+        if node.children.is_empty() && choices.is_empty() && triggers.is_some() {
+            edges.push(Edge {
+                from: vertex_id(id),
+                to: END_ID.to_string(),
+                label: edge_label(None),
+                jump: false,
+            });
+        }
+
+        // never follows a jump: the dialog can loop
+        for (child_index, child) in node.children.iter().enumerate() {
+            if !self.is_jump(id, child_index) {
+                self.export_aux(*child, vertices, edges);
+            }
+        }
+    }
+}
+
+fn vertex_id(id: NodeId) -> String {
+    format!("n{}", id.0)
+}
+
+fn dot_label(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| line.replace('\\', "\\\\").replace('"', "\\\""))
+        .collect::<Vec<String>>()
+        .join("\\n")
+}
+
+fn mermaid_label(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        })
+        .collect::<Vec<String>>()
+        .join("<br/>")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ui::dialog_system::parse_dialog;
+
+    const DIALOG: &str = "# Olf
+
+- Hello \"friend\"
+
+## Morgan
+
+- Here my money | e: HasCharisma;
+- You will feel my guitar | None
+- Run | k: 10,MAX;
+
+### Olf
+
+- Thank you
+
+### Olf
+
+- <3
+-> FightEvent
+";
+
+    #[test]
+    fn test_to_dot() {
+        let graph = parse_dialog(DIALOG).unwrap();
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dialog {
+    node [shape=box];
+    n0 [label=\"Olf\\n- Hello \\\"friend\\\"\"];
+    n1 [label=\"Morgan\"];
+    n2 [label=\"Olf\\n- Thank you\"];
+    n3 [label=\"Olf\\n- <3\"];
+    dialog_end [label=\"End\", shape=doublecircle];
+    n0 -> n1;
+    n1 -> n2 [label=\"Here my money | event: HasCharisma;\"];
+    n1 -> n3 [label=\"You will feel my guitar | None\"];
+    n1 -> dialog_end [label=\"Run | karma: 10,100;\"];
+    n3 -> dialog_end [label=\"-> FightEvent\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_dashed_jumps() {
+        let graph =
+            parse_dialog("# Olf {#ask}\n\n- Again ?\n\n## Morgan\n\n- Yes | None\n=> ask\n")
+                .unwrap();
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dialog {
+    node [shape=box];
+    n0 [label=\"Olf\\n- Again ?\"];
+    n1 [label=\"Morgan\"];
+    n0 -> n1;
+    n1 -> n0 [label=\"Yes | None\", style=dashed];
+}
+"
+        );
+        assert!(graph
+            .to_mermaid()
+            .contains("    n1 -.->|\"Yes | None\"| n0\n"));
+    }
+
+    #[test]
+    fn test_jump_to_sibling() {
+        let graph = parse_dialog(
+            "# Morgan\n\n- Fight | None\n- Flee | None\n\n## Olf\n\n=> late\n\n## Olf {#late}\n\n- Too late\n",
+        )
+        .unwrap();
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dialog {
+    node [shape=box];
+    n0 [label=\"Morgan\"];
+    n1 [label=\"Olf\\n- Too late\"];
+    n0 -> n1 [label=\"Fight | None\", style=dashed];
+    n0 -> n1 [label=\"Flee | None\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let graph = parse_dialog(DIALOG).unwrap();
+        assert_eq!(
+            graph.to_mermaid(),
+            "graph TD
+    n0[\"Olf<br/>- Hello #quot;friend#quot;\"]
+    n1[\"Morgan\"]
+    n2[\"Olf<br/>- Thank you\"]
+    n3[\"Olf<br/>- #lt;3\"]
+    dialog_end((\"End\"))
+    n0 --> n1
+    n1 -->|\"Here my money | event: HasCharisma;\"| n2
+    n1 -->|\"You will feel my guitar | None\"| n3
+    n1 -->|\"Run | karma: 10,100;\"| dialog_end
+    n3 -->|\"-#gt; FightEvent\"| dialog_end
+"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Choice Focus
+//!
+//! The player's choices can be picked without the mouse:
+//!
+//! - the focus moves across the shown PlayerChoice buttons
+//!   - keyboard: `Up`/`Down`, `W`/`Z`/`S`
+//!   - gamepad: the D-pad
+//! - the focused choice is picked with `Enter`, `Space` or the gamepad South button
+//! - `1` to `9` pick one of the choices directly, across the pages
+//! - the pages of choices are turned with `Left`/`Right`, `A`/`Q`/`D` or the D-pad
+//!
+//! The focused button takes the hovered color; hovering a button with the mouse focuses it.
+
+use bevy::prelude::*;
+
+use crate::{
+    constants::ui::dialogs::{CHOICES_PER_PAGE, HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON},
+    ui::{
+        dialog_player::DialogDiveEvent,
+        dialog_scroll::{PlayerChoice, PlayerScroll, Scroll},
+    },
+};
+
+/// The index of the focused PlayerChoice button, if any
+#[derive(Resource, Reflect, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ChoiceFocus(pub Option<usize>);
+
+const UP_KEYS: [KeyCode; 3] = [KeyCode::Up, KeyCode::W, KeyCode::Z];
+const DOWN_KEYS: [KeyCode; 2] = [KeyCode::Down, KeyCode::S];
+const PREVIOUS_PAGE_KEYS: [KeyCode; 3] = [KeyCode::Left, KeyCode::A, KeyCode::Q];
+const NEXT_PAGE_KEYS: [KeyCode; 2] = [KeyCode::Right, KeyCode::D];
+const CONFIRM_KEYS: [KeyCode; 3] = [KeyCode::Return, KeyCode::NumpadEnter, KeyCode::Space];
+/// The key of each choice of the PlayerScroll, whatever the page shown
+const CHOICE_KEYS: [(KeyCode, KeyCode); 9] = [
+    (KeyCode::Key1, KeyCode::Numpad1),
+    (KeyCode::Key2, KeyCode::Numpad2),
+    (KeyCode::Key3, KeyCode::Numpad3),
+    (KeyCode::Key4, KeyCode::Numpad4),
+    (KeyCode::Key5, KeyCode::Numpad5),
+    (KeyCode::Key6, KeyCode::Numpad6),
+    (KeyCode::Key7, KeyCode::Numpad7),
+    (KeyCode::Key8, KeyCode::Numpad8),
+    (KeyCode::Key9, KeyCode::Numpad9),
+];
+
+/// # Return
+///
+/// The focus moved by `step` among `len` buttons, wrapping around.
+/// Without focus, Down focuses the first button and Up the last one.
+pub fn move_focus(focus: Option<usize>, len: usize, step: isize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    match focus {
+        Some(index) if index < len => {
+            Some((index as isize + step).rem_euclid(len as isize) as usize)
+        }
+        _ if step < 0 => Some(len - 1),
+        _ => Some(0),
+    }
+}
+
+/// Drops the focus when the player's choices, or their page, change.
+pub fn reset_choice_focus(
+    mut focus: ResMut<ChoiceFocus>,
+    player_scroll_query: Query<(), (Changed<PlayerScroll>, With<Scroll>)>,
+) {
+    if !player_scroll_query.is_empty() {
+        focus.0 = None;
+    }
+}
+
+/// Moves the focus across the shown choices, picks the focused one or the one of a number key,
+/// or turns the page of choices.
+pub fn navigate_choices(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut focus: ResMut<ChoiceFocus>,
+
+    mut player_scroll_query: Query<&mut PlayerScroll, With<Scroll>>,
+
+    mut dialog_dive_event: EventWriter<DialogDiveEvent>,
+) {
+    let mut player_scroll = match player_scroll_query.get_single_mut() {
+        Ok(player_scroll) => player_scroll,
+        Err(_) => return,
+    };
+    let shown = player_scroll.page_choices().len();
+    if shown == 0 {
+        return;
+    }
+
+    let gamepad_just_pressed = |button_type: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+
+    let page_step = if keyboard_input.any_just_pressed(PREVIOUS_PAGE_KEYS)
+        || gamepad_just_pressed(GamepadButtonType::DPadLeft)
+    {
+        -1
+    } else if keyboard_input.any_just_pressed(NEXT_PAGE_KEYS)
+        || gamepad_just_pressed(GamepadButtonType::DPadRight)
+    {
+        1
+    } else {
+        0
+    };
+    if page_step != 0 {
+        // the choices are only displayed again if the page is turned
+        if player_scroll.bypass_change_detection().turn_page(page_step) {
+            player_scroll.set_changed();
+        }
+        return;
+    }
+
+    // `n` picks the n-th choice, even on another page
+    let numbered = CHOICE_KEYS
+        .iter()
+        .position(|(key, numpad_key)| {
+            keyboard_input.just_pressed(*key) || keyboard_input.just_pressed(*numpad_key)
+        })
+        .filter(|index| *index < player_scroll.choices.len());
+
+    let picked = if let Some(index) = numbered {
+        // focuses its button, if it is shown
+        if index / CHOICES_PER_PAGE == player_scroll.page {
+            focus.0 = Some(index % CHOICES_PER_PAGE);
+        }
+        Some(player_scroll.choices[index].0)
+    } else if keyboard_input.any_just_pressed(CONFIRM_KEYS)
+        || gamepad_just_pressed(GamepadButtonType::South)
+    {
+        focus
+            .0
+            .and_then(|index| player_scroll.shown_choice(index))
+            .map(|(child_index, _)| *child_index)
+    } else {
+        if keyboard_input.any_just_pressed(UP_KEYS)
+            || gamepad_just_pressed(GamepadButtonType::DPadUp)
+        {
+            focus.0 = move_focus(focus.0, shown, -1);
+        } else if keyboard_input.any_just_pressed(DOWN_KEYS)
+            || gamepad_just_pressed(GamepadButtonType::DPadDown)
+        {
+            focus.0 = move_focus(focus.0, shown, 1);
+        }
+        None
+    };
+
+    if let Some(child_index) = picked {
+        dialog_dive_event.send(DialogDiveEvent {
+            child_index,
+            skip: false,
+            timed_out: false,
+        });
+    }
+}
+
+/// Colors the focused button like a hovered one.
+pub fn highlight_focused_choice(
+    focus: Res<ChoiceFocus>,
+    mut button_query: Query<(&Interaction, &mut BackgroundColor, &PlayerChoice), With<Button>>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+    for (interaction, mut color, index) in button_query.iter_mut() {
+        *color = match interaction {
+            Interaction::Pressed => PRESSED_BUTTON,
+            _ if focus.0 == Some(index.0) => HOVERED_BUTTON,
+            _ => NORMAL_BUTTON,
+        }
+        .into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_focus_wraps_around() {
+        assert_eq!(move_focus(Some(0), 3, 1), Some(1));
+        assert_eq!(move_focus(Some(2), 3, 1), Some(0));
+        assert_eq!(move_focus(Some(0), 3, -1), Some(2));
+    }
+
+    #[test]
+    fn test_move_focus_without_focus() {
+        assert_eq!(move_focus(None, 3, 1), Some(0));
+        assert_eq!(move_focus(None, 3, -1), Some(2));
+        // the focused choice is no longer shown
+        assert_eq!(move_focus(Some(4), 2, 1), Some(0));
+        assert_eq!(move_focus(Some(1), 0, 1), None);
+    }
+}
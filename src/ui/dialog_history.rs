@@ -0,0 +1,293 @@
+//! Dialog History
+//!
+//! Every line passed and every answer chosen during the session is recorded,
+//! with its speaker and when it was said.
+//!
+//! - `DialogHistory`: resource holding the log, readable by any system
+//! - recorded by `ui::dialog_player::dialog_dive`
+//!   - a text when it is dropped from the UpperScroll (skipped or finished)
+//!   - a choice when it is picked, by the player or by a npc
+//! - Press 'H' while talking to open the history panel,
+//!   scroll it with the mouse wheel
+
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use std::fmt;
+
+use crate::{
+    constants::ui::dialogs::HISTORY_SCROLL_LINE_HEIGHT,
+    ui::{
+        dialog_markup::strip_markup,
+        dialog_panel::{DialogPanel, DialogPanelResources},
+    },
+};
+
+/// A line said during a dialog.
+#[derive(Reflect, PartialEq, Clone, Debug)]
+pub struct DialogLine {
+    /// None for the Narator
+    pub speaker: Option<String>,
+    /// The text as displayed (variables filled, markup removed)
+    pub text: String,
+    /// An answer picked among the choices of a node
+    pub choice: bool,
+    /// When the line was said (in seconds since the startup)
+    pub time: f64,
+}
+
+impl fmt::Display for DialogLine {
+    /// `[01:05] Olf: It's essential` or `[01:07] Morgan > ...` for a choice
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.time as u64;
+        write!(f, "[{:02}:{:02}] ", seconds / 60, seconds % 60)?;
+        if let Some(speaker) = &self.speaker {
+            write!(f, "{}{} ", speaker, if self.choice { " >" } else { ":" })?;
+        } else if self.choice {
+            write!(f, "> ")?;
+        }
+        write!(f, "{}", self.text)
+    }
+}
+
+/// The log of all the dialogs of the session, in order.
+#[derive(Resource, Reflect, Default, Debug)]
+pub struct DialogHistory {
+    lines: Vec<DialogLine>,
+}
+
+impl DialogHistory {
+    /// The tags of the `text` (`[b]`, `[wait=0.5]`, ...) are not kept.
+    pub fn record(&mut self, speaker: Option<String>, text: String, choice: bool, time: f64) {
+        self.lines.push(DialogLine {
+            speaker,
+            text: strip_markup(&text),
+            choice,
+            time,
+        });
+    }
+
+    /// From the oldest to the newest
+    pub fn lines(&self) -> &[DialogLine] {
+        &self.lines
+    }
+
+    pub fn last(&self) -> Option<&DialogLine> {
+        self.lines.last()
+    }
+
+    /// # Return
+    ///
+    /// true if this text has already been said by this `speaker` (not as a choice)
+    pub fn has_seen(&self, speaker: Option<&str>, text: &str) -> bool {
+        let text = strip_markup(text);
+        self.lines
+            .iter()
+            .any(|line| !line.choice && line.speaker.as_deref() == speaker && line.text == text)
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+/// The panel displaying the DialogHistory, over the DialogPanel
+#[derive(Component)]
+pub struct HistoryPanel;
+
+/// The scrollable list of lines within the HistoryPanel
+#[derive(Component, Default)]
+pub struct HistoryList {
+    position: f32,
+}
+
+/// Opens or closes the HistoryPanel when H's pressed,
+/// only while the DialogPanel is open.
+pub fn toggle_history_panel_on_key_press(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+
+    dialog_panel_query: Query<Entity, With<DialogPanel>>,
+    history_panel_query: Query<Entity, With<HistoryPanel>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::H) {
+        return;
+    }
+
+    if let Ok(history_panel) = history_panel_query.get_single() {
+        commands.entity(history_panel).despawn_recursive();
+    } else if let Ok(ui_wall) = dialog_panel_query.get_single() {
+        // despawned with the UI Wall
+        commands.entity(ui_wall).with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Percent(10.),
+                            left: Val::Percent(10.),
+                            width: Val::Percent(80.),
+                            height: Val::Percent(60.),
+                            flex_direction: FlexDirection::Column,
+                            overflow: Overflow::clip_y(),
+                            ..Style::default()
+                        },
+                        background_color: Color::rgba(0.95, 0.9, 0.8, 0.95).into(),
+                        // above the scrolls
+                        z_index: ZIndex::Local(1),
+                        ..NodeBundle::default()
+                    },
+                    HistoryPanel,
+                    Name::new("History Panel"),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                padding: UiRect::all(Val::Px(10.)),
+                                ..Style::default()
+                            },
+                            ..NodeBundle::default()
+                        },
+                        // filled by update_history_panel
+                        HistoryList::default(),
+                        Name::new("History List"),
+                    ));
+                });
+        });
+    }
+}
+
+/// Fills the HistoryList, the newest line first.
+pub fn update_history_panel(
+    mut commands: Commands,
+    history: Res<DialogHistory>,
+    dialog_panel_resources: Res<DialogPanelResources>,
+
+    list_query: Query<(Entity, Ref<HistoryList>)>,
+) {
+    for (list, list_ref) in list_query.iter() {
+        if !history.is_changed() && !list_ref.is_added() {
+            continue;
+        }
+
+        commands
+            .entity(list)
+            .despawn_descendants()
+            .with_children(|parent| {
+                for line in history.lines().iter().rev() {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            line.to_string(),
+                            TextStyle {
+                                font: dialog_panel_resources.text_font.clone(),
+                                font_size: 20.,
+                                color: Color::BLACK,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::bottom(Val::Px(4.)),
+                            ..Style::default()
+                        }),
+                    );
+                }
+            });
+    }
+}
+
+/// Scrolls the HistoryList with the mouse wheel, within the HistoryPanel.
+pub fn scroll_history_panel(
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+
+    mut list_query: Query<(&mut HistoryList, &mut Style, &Parent, &Node)>,
+    node_query: Query<&Node>,
+) {
+    for mouse_wheel_event in mouse_wheel_events.iter() {
+        for (mut list, mut style, parent, list_node) in list_query.iter_mut() {
+            let panel_height = node_query
+                .get(parent.get())
+                .map_or(0., |node| node.size().y);
+            let max_scroll = (list_node.size().y - panel_height).max(0.);
+
+            let dy = match mouse_wheel_event.unit {
+                MouseScrollUnit::Line => mouse_wheel_event.y * HISTORY_SCROLL_LINE_HEIGHT,
+                MouseScrollUnit::Pixel => mouse_wheel_event.y,
+            };
+
+            list.position = (list.position + dy).clamp(-max_scroll, 0.);
+            style.top = Val::Px(list.position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_in_order() {
+        let mut history = DialogHistory::default();
+        assert_eq!(history.last(), None);
+
+        history.record(Some(String::from("Olf")), String::from("Hello"), false, 1.);
+        history.record(Some(String::from("Morgan")), String::from("..."), true, 2.5);
+
+        assert_eq!(history.lines().len(), 2);
+        assert_eq!(history.lines()[0].text, "Hello");
+        assert_eq!(
+            history.last(),
+            Some(&DialogLine {
+                speaker: Some(String::from("Morgan")),
+                text: String::from("..."),
+                choice: true,
+                time: 2.5,
+            })
+        );
+
+        assert!(history.has_seen(Some("Olf"), "[b]Hello[/b]"));
+        assert!(!history.has_seen(Some("Hugo"), "Hello"));
+        // a choice is not a line seen
+        assert!(!history.has_seen(Some("Morgan"), "..."));
+
+        history.clear();
+        assert!(history.lines().is_empty());
+    }
+
+    #[test]
+    fn test_display_line() {
+        let mut history = DialogHistory::default();
+        history.record(
+            Some(String::from("Olf")),
+            String::from("It's essential"),
+            false,
+            65.,
+        );
+        history.record(
+            Some(String::from("Morgan")),
+            String::from("..."),
+            true,
+            67.9,
+        );
+        history.record(
+            None,
+            String::from("The night [wait=1]falls[color=red]..."),
+            false,
+            3600.,
+        );
+
+        assert_eq!(
+            history
+                .lines()
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "[01:05] Olf: It's essential",
+                "[01:07] Morgan > ...",
+                "[60:00] The night falls...",
+            ]
+        );
+    }
+}
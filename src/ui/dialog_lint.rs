@@ -0,0 +1,303 @@
+//! Dialog Lint
+//!
+//! Finds the mistakes a writer can make in a dialog, while still being accepted by `parse_dialog`.
+//!
+//! - empty nodes: a header without any text nor choice
+//! - unreachable nodes: the cursor never dives into them
+//!   - a text node only leads to its first child
+//!   - a choice node has no more children than choices: a choice without child ends the dialog
+//! - nodes mixing texts and choices
+//! - karma thresholds outside `KARMA_MIN..KARMA_MAX`
+//! - texts with an invalid markup (`[b`, `[color=pink]`), displayed as is
+//! - timeouts on a node without choices, or whose default choice does not exist
+//!
+//! The unknown `GameEvent` or `ThrowableEvent` are already refused by `parse_dialog`.
+//!
+//! See the `fight_arena-dialog-lint` binary to check every dialog file.
+
+use std::fmt;
+
+use crate::{
+    constants::character::{KARMA_MAX, KARMA_MIN},
+    ui::{
+        dialog_markup::parse_markup,
+        dialog_system::{DialogGraph, DialogNode, DialogType, NodeId},
+    },
+};
+
+/// A mistake found in a dialog.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DialogLint {
+    pub node: NodeId,
+    /// The author of the node (its header)
+    pub author: String,
+    /// The children taken from the root to the node (as in a DialogCursor)
+    pub path: Vec<usize>,
+    pub message: String,
+}
+
+impl DialogLint {
+    fn new(graph: &DialogGraph, node: NodeId, path: &[usize], message: String) -> DialogLint {
+        DialogLint {
+            node,
+            author: graph[node].character.clone().unwrap_or_default(),
+            path: path.to_vec(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for DialogLint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` at {:?}: {}", self.author, self.path, self.message)
+    }
+}
+
+/// Checks every node of the `graph`.
+///
+/// # Return
+///
+/// All the mistakes found, from the root to the leaves.
+/// Empty if the dialog is clean.
+pub fn lint_dialog(graph: &DialogGraph) -> Vec<DialogLint> {
+    let mut lints = Vec::new();
+    lint_node(graph, NodeId::ROOT, &mut Vec::new(), &mut lints);
+
+    lints
+}
+
+fn lint_node(graph: &DialogGraph, id: NodeId, path: &mut Vec<usize>, lints: &mut Vec<DialogLint>) {
+    let node = &graph[id];
+    let choices = node
+        .dialog_type
+        .iter()
+        .filter(|dialog| matches!(dialog, DialogType::Choice { .. }))
+        .count();
+    let texts = node.dialog_type.len() - choices;
+
+    if node.dialog_type.is_empty() {
+        lints.push(DialogLint::new(
+            graph,
+            id,
+            path,
+            "empty node: it has no text nor choice".to_string(),
+        ));
+    } else if choices > 0 && texts > 0 {
+        lints.push(DialogLint::new(
+            graph,
+            id,
+            path,
+            "a dialog node cannot mix texts and choices".to_string(),
+        ));
+    }
+
+    if choices == 0 && node.children.len() > 1 {
+        lints.push(DialogLint::new(
+            graph,
+            id,
+            path,
+            format!(
+                "a text node can only have one child, found {}",
+                node.children.len()
+            ),
+        ));
+    }
+    if choices > 0 && node.children.len() > choices {
+        lints.push(DialogLint::new(
+            graph,
+            id,
+            path,
+            format!(
+                "{} choices but {} children: each choice leads to its own child",
+                choices,
+                node.children.len()
+            ),
+        ));
+    }
+
+    for threshold in karma_thresholds(node) {
+        let (min, max) = threshold;
+        if min < KARMA_MIN || max > KARMA_MAX {
+            lints.push(DialogLint::new(
+                graph,
+                id,
+                path,
+                format!(
+                    "the karma threshold {:?} is outside {}..{}",
+                    threshold, KARMA_MIN, KARMA_MAX
+                ),
+            ));
+        }
+    }
+
+    if let Some(timeout) = node.timeout {
+        if choices == 0 {
+            lints.push(DialogLint::new(
+                graph,
+                id,
+                path,
+                "a timeout is only read on a node of choices".to_string(),
+            ));
+        } else if timeout.default >= choices {
+            lints.push(DialogLint::new(
+                graph,
+                id,
+                path,
+                format!(
+                    "the default choice n°{} of the timeout does not exist: {} choices",
+                    timeout.default, choices
+                ),
+            ));
+        }
+    }
+
+    for dialog in &node.dialog_type {
+        let text = match dialog {
+            DialogType::Text(text) | DialogType::Choice { text, .. } => text,
+        };
+        if let Err(error) = parse_markup(text) {
+            lints.push(DialogLint::new(
+                graph,
+                id,
+                path,
+                format!("invalid markup in {:?}: {}", text, error),
+            ));
+        }
+    }
+
+    // a text node only dives into its first child
+    let reachable = if choices > 0 { choices } else { 1 };
+    for (child_index, child) in node.children.iter().enumerate() {
+        // never follows a jump: the dialog can loop
+        if graph.is_jump(id, child_index) {
+            continue;
+        }
+
+        path.push(child_index);
+        if child_index >= reachable {
+            lints.push(DialogLint::new(
+                graph,
+                *child,
+                path,
+                "unreachable node".to_string(),
+            ));
+        }
+        lint_node(graph, *child, path, lints);
+        path.pop();
+    }
+}
+
+fn karma_thresholds(node: &DialogNode) -> Vec<(i32, i32)> {
+    node.dialog_type
+        .iter()
+        .filter_map(|dialog| match dialog {
+            DialogType::Choice {
+                condition: Some(condition),
+                ..
+            } => condition.karma_threshold(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::dialog_system::parse_dialog;
+
+    fn messages(dialog: &str) -> Vec<(Vec<usize>, String)> {
+        lint_dialog(&parse_dialog(dialog).unwrap())
+            .into_iter()
+            .map(|lint| (lint.path, lint.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_dialog() {
+        let dialog = "# Fabien\n\n- Hello\n\n## Morgan\n\n- Hey | None\n- No | k: MIN,0;\n\n### Fabien\n\n- :)\n\n### Fabien\n\n- :O\n";
+        assert_eq!(messages(dialog), vec![]);
+
+        // the last choice ends the dialog
+        let dialog = "# Morgan\n\n- Hey | None\n- Bye | None\n\n## Fabien\n\n- :)\n";
+        assert_eq!(messages(dialog), vec![]);
+    }
+
+    #[test]
+    fn test_lint_unreachable_nodes() {
+        let dialog = "# Fabien\n\n- Hello\n\n## Morgan\n\n- Hey\n\n## Morgan\n\n- Ho\n";
+        assert_eq!(
+            messages(dialog),
+            vec![
+                (
+                    vec![],
+                    "a text node can only have one child, found 2".to_string()
+                ),
+                (vec![1], "unreachable node".to_string()),
+            ]
+        );
+
+        let dialog = "# Morgan\n\n- Hey | None\n- No | None\n\n## Fabien\n\n- :)\n\n## Fabien\n\n- :O\n\n## Fabien\n\n- :(\n";
+        assert_eq!(
+            messages(dialog),
+            vec![
+                (
+                    vec![],
+                    "2 choices but 3 children: each choice leads to its own child".to_string()
+                ),
+                (vec![2], "unreachable node".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_empty_node_and_karma() {
+        let dialog = "# Fabien\n\n- Hello\n\n## Morgan\n\n-> FightEvent\n";
+        assert_eq!(
+            messages(dialog),
+            vec![(vec![0], "empty node: it has no text nor choice".to_string())]
+        );
+
+        let dialog = "# Morgan\n\n- Hey | k: -200,0;\n\n## Fabien\n\n- :)\n";
+        assert_eq!(
+            messages(dialog),
+            vec![(
+                vec![],
+                format!(
+                    "the karma threshold (-200, 0) is outside {}..{}",
+                    KARMA_MIN, KARMA_MAX
+                )
+            )]
+        );
+    }
+
+    #[test]
+    fn test_lint_timeouts() {
+        let dialog = "# Fabien [timeout=2s]\n\n- Hello\n\n## Morgan [timeout=5s, default=2]\n\n- Hey | None\n- No | None\n\n### Fabien\n\n- :)\n\n### Fabien\n\n- :O\n";
+        assert_eq!(
+            messages(dialog),
+            vec![
+                (
+                    vec![],
+                    "a timeout is only read on a node of choices".to_string()
+                ),
+                (
+                    vec![0],
+                    "the default choice n°2 of the timeout does not exist: 2 choices".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_invalid_markup() {
+        let dialog = "# Fabien\n\n- [b]Hello[/b] [wait=0.5]you\n\n## Morgan\n\n- [color=pink]Hey | None\n\n### Fabien\n\n- :)\n";
+        assert_eq!(
+            messages(dialog),
+            vec![(
+                vec![0],
+                "invalid markup in \"[color=pink]Hey\": unknown color `pink`, expected a name or `#rrggbb` (char 0)"
+                    .to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Dialog Locales
+//!
+//! Every dialog file exists in several languages: `olf.fr.dialog.md`, `olf.en.dialog.md`.
+//!
+//! - The Locale resource selects the language of all the dialogs
+//!   - switching it (press 'L') swaps every dialog file for its translation
+//!     - the open DialogPanel is rebuilt, keeping the current position in the dialog
+//! - A missing translation falls back to the DEFAULT_LOCALE
+
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    constants::ui::dialogs::{DEFAULT_LOCALE, LOCALES},
+    ui::{
+        dialog_asset::DialogAsset, dialog_cast::DialogCast, dialog_panel::DialogPanel,
+        dialog_system::Dialog,
+    },
+};
+
+/// The language of the dialogs: `en`, `fr`.
+#[derive(Resource, Reflect, PartialEq, Eq, Clone, Debug)]
+pub struct Locale(pub String);
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale(DEFAULT_LOCALE.to_string())
+    }
+}
+
+/// `dialogs/olf` in `fr` is read from `dialogs/olf.fr.dialog.md`
+pub fn localized_path(dialog: &str, locale: &str) -> String {
+    format!("{}.{}.dialog.md", dialog, locale)
+}
+
+/// The reverse of `localized_path`
+///
+/// # Return
+///
+/// None if the `path` is not a localized dialog file
+pub fn split_localized_path(path: &str) -> Option<(&str, &str)> {
+    let without_extension = path.strip_suffix(".dialog.md")?;
+    let dot = without_extension.rfind('.')?;
+    let (dialog, locale) = (&without_extension[..dot], &without_extension[dot + 1..]);
+    if dialog.is_empty() || locale.is_empty() || locale.contains('/') {
+        None
+    } else {
+        Some((dialog, locale))
+    }
+}
+
+/// # Return
+///
+/// The path of the same dialog file in the `locale`,
+/// None if the `path` is not a localized dialog file or is already in this `locale`
+pub fn translated_path(path: &str, locale: &str) -> Option<String> {
+    let (dialog, current_locale) = split_localized_path(path)?;
+
+    if current_locale == locale {
+        None
+    } else {
+        Some(localized_path(dialog, locale))
+    }
+}
+
+/// # Return
+///
+/// The handle of the same dialog file in the `locale`, see `translated_path`
+fn translate(
+    asset_server: &AssetServer,
+    source: &Handle<DialogAsset>,
+    locale: &str,
+) -> Option<Handle<DialogAsset>> {
+    let asset_path = asset_server.get_handle_path(source)?;
+    let path = translated_path(asset_path.path().to_str()?, locale)?;
+
+    Some(asset_server.load(path))
+}
+
+/// Swaps the `source` for its DEFAULT_LOCALE version, if it cannot be loaded.
+///
+/// # Return
+///
+/// true if the `source` falls back
+fn fallback(asset_server: &AssetServer, source: &mut Handle<DialogAsset>) -> bool {
+    if asset_server.get_load_state(&*source) != LoadState::Failed {
+        return false;
+    }
+
+    match translate(asset_server, source, DEFAULT_LOCALE) {
+        Some(fallback) => {
+            *source = fallback;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Cycles through the LOCALES when 'L' is pressed.
+pub fn switch_locale_on_key_press(keyboard_input: Res<Input<KeyCode>>, mut locale: ResMut<Locale>) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        let next = match LOCALES.iter().position(|known| *known == locale.0) {
+            Some(index) => LOCALES[(index + 1) % LOCALES.len()],
+            None => DEFAULT_LOCALE,
+        };
+        info!("Locale: {}", next);
+        locale.0 = next.to_string();
+    }
+}
+
+/// Swaps every dialog file for its translation when the Locale changes.
+///
+/// The progress is kept: the translations share the same tree.
+/// If the translation is already loaded, the open DialogPanel is rebuilt right away,
+/// otherwise by `ui::dialog_asset::update_dialog_on_asset_event`.
+pub fn translate_dialogs(
+    locale: Res<Locale>,
+    asset_server: Res<AssetServer>,
+    dialog_assets: Res<Assets<DialogAsset>>,
+
+    mut dialog_query: Query<(Entity, &mut Dialog, Option<&mut DialogCast>)>,
+    mut panel_query: Query<&mut DialogPanel>,
+) {
+    if !locale.is_changed() || locale.is_added() {
+        return;
+    }
+
+    for (entity, mut dialog, cast) in dialog_query.iter_mut() {
+        if let Some(translation) = translate(&asset_server, &dialog.source, &locale.0) {
+            dialog.source = translation;
+        }
+
+        if let Some(mut cast) = cast {
+            for cast_dialog in cast.dialogs.iter_mut() {
+                if let Some(translation) = translate(&asset_server, &cast_dialog.source, &locale.0)
+                {
+                    cast_dialog.source = translation;
+                }
+            }
+        }
+
+        if let Some(dialog_asset) = dialog_assets.get(&dialog.source) {
+            for mut panel in panel_query.iter_mut() {
+                if panel.main_interlocutor == entity {
+                    panel.rebuild(&dialog_asset.graph);
+                }
+            }
+        }
+    }
+}
+
+/// Falls back to the DEFAULT_LOCALE, when a translation cannot be loaded:
+/// the dialog of a npc, or any dialog of its cast.
+pub fn fallback_missing_translations(
+    asset_server: Res<AssetServer>,
+    mut dialog_query: Query<(&mut Dialog, Option<&mut DialogCast>, &Name)>,
+) {
+    for (mut dialog, cast, name) in dialog_query.iter_mut() {
+        if fallback(&asset_server, &mut dialog.source) {
+            warn!(
+                "{}'s dialog has no translation in this locale, it falls back to `{}`",
+                name, DEFAULT_LOCALE
+            );
+        }
+
+        if let Some(mut cast) = cast {
+            for cast_dialog in cast.dialogs.iter_mut() {
+                if fallback(&asset_server, &mut cast_dialog.source) {
+                    warn!(
+                        "A dialog of {}'s cast has no translation in this locale, it falls back to `{}`",
+                        name, DEFAULT_LOCALE
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_path() {
+        assert_eq!(
+            localized_path("dialogs/olf", "fr"),
+            "dialogs/olf.fr.dialog.md".to_string()
+        );
+        assert_eq!(
+            split_localized_path("dialogs/olf.fr.dialog.md"),
+            Some(("dialogs/olf", "fr"))
+        );
+    }
+
+    #[test]
+    fn test_translated_path() {
+        // switching the locale
+        assert_eq!(
+            translated_path("dialogs/olf.en.dialog.md", "fr"),
+            Some("dialogs/olf.fr.dialog.md".to_string())
+        );
+        // the fallback of a missing translation
+        assert_eq!(
+            translated_path("dialogs/olf.fr.dialog.md", DEFAULT_LOCALE),
+            Some(localized_path("dialogs/olf", DEFAULT_LOCALE))
+        );
+        // already in this locale: nothing to fall back to
+        assert_eq!(
+            translated_path(
+                &localized_path("dialogs/olf", DEFAULT_LOCALE),
+                DEFAULT_LOCALE
+            ),
+            None
+        );
+        assert_eq!(translated_path("dialogs/olf.dialog.md", "fr"), None);
+    }
+
+    #[test]
+    fn test_split_not_localized_path() {
+        assert_eq!(split_localized_path("dialogs/olf.dialog.md"), None);
+        assert_eq!(split_localized_path("dialogs.v2/olf.dialog.md"), None);
+        assert_eq!(split_localized_path("dialogs/olf.fr.md"), None);
+    }
+}
@@ -0,0 +1,354 @@
+//! Dialog Markup
+//!
+//! A text of a dialog can hold some inline tags, read when it is displayed by a DialogBox:
+//!
+//! - `[color=red]...[/color]`: a named color (`red`, `green`, `blue`, ...) or `#rrggbb`
+//! - `[b]...[/b]`: emphasised (a bigger font size)
+//! - `[shake]...[/shake]`: the text trembles while this part is revealed
+//! - `[speed=2]...[/speed]`: revealed twice as fast
+//! - `[wait=0.5]`: a pause of half a second in the reveal
+//!
+//! `[[` writes a simple `[`.
+//! A tag lasts until its closing tag, or the end of the text,
+//! and a closing tag ends the last opened one of its kind.
+//!
+//! The text is split into sections of the same style (a `TextSection` each),
+//! then revealed grapheme by grapheme: `é` or `👋🏽` are never cut.
+//!
+//! An invalid markup is refused by `parse_markup` (and reported by `ui::dialog_lint`);
+//! the DialogBox then displays the text as is.
+//!
+//! The PlayerChoice buttons display the choices without their tags (`strip_markup`).
+
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The look of a section of a text.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct MarkupStyle {
+    /// `[r, g, b]`, None for the default color of the DialogBox
+    pub color: Option<[f32; 3]>,
+    pub bold: bool,
+    pub shake: bool,
+}
+
+/// An instruction of the reveal of a text, in order.
+#[derive(PartialEq, Clone, Debug)]
+pub enum RevealStep {
+    /// Appends a grapheme to the section at this index
+    Grapheme { section: usize, grapheme: String },
+    /// Pauses the reveal (in seconds)
+    Wait(f32),
+    /// The speed of the next graphemes (1 by default): `2.` is twice as fast
+    Speed(f32),
+}
+
+/// A text read from its markup.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct RichText {
+    /// The style of each section, in order
+    pub sections: Vec<MarkupStyle>,
+    pub steps: Vec<RevealStep>,
+}
+
+impl RichText {
+    /// A text without any markup, in a single section.
+    pub fn plain(text: &str) -> RichText {
+        let mut rich_text = RichText::default();
+        rich_text.push_graphemes(text, MarkupStyle::default());
+        rich_text
+    }
+
+    /// # Return
+    ///
+    /// The text without its tags: `[b]Hey[/b]` gives `Hey`
+    pub fn to_plain(&self) -> String {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                RevealStep::Grapheme { grapheme, .. } => Some(grapheme.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Adds a run of text, in the last section if it has the same `style`.
+    fn push_graphemes(&mut self, text: &str, style: MarkupStyle) {
+        if text.is_empty() {
+            return;
+        }
+        if self.sections.last() != Some(&style) {
+            self.sections.push(style);
+        }
+        let section = self.sections.len() - 1;
+
+        self.steps
+            .extend(text.graphemes(true).map(|grapheme| RevealStep::Grapheme {
+                section,
+                grapheme: grapheme.to_string(),
+            }));
+    }
+}
+
+/// An invalid markup.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct MarkupError {
+    /// The index of the char starting the offending tag
+    pub position: usize,
+    pub message: String,
+}
+
+impl MarkupError {
+    fn new(position: usize, message: impl Into<String>) -> MarkupError {
+        MarkupError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (char {})", self.message, self.position)
+    }
+}
+
+/// # Return
+///
+/// The `text` without its tags, or as is if its markup is invalid
+pub fn strip_markup(text: &str) -> String {
+    parse_markup(text).map_or_else(|_| text.to_string(), |rich_text| rich_text.to_plain())
+}
+
+/// `red` or `#ff0000`
+fn parse_color(color: &str) -> Option<[f32; 3]> {
+    let named = match color {
+        "red" => Some([0.8, 0.1, 0.1]),
+        "green" => Some([0.1, 0.6, 0.1]),
+        "blue" => Some([0.1, 0.2, 0.8]),
+        "yellow" => Some([0.9, 0.8, 0.1]),
+        "orange" => Some([0.9, 0.5, 0.1]),
+        "purple" => Some([0.5, 0.1, 0.6]),
+        "white" => Some([1., 1., 1.]),
+        "black" => Some([0., 0., 0.]),
+        "gray" | "grey" => Some([0.5, 0.5, 0.5]),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .ok()
+            .map(|c| c as f32 / 255.)
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// A strictly positive number of a tag: `[speed=2]`
+fn parse_number(value: &str, position: usize, tag: &str) -> Result<f32, MarkupError> {
+    match value.trim().parse::<f32>() {
+        Ok(number) if number.is_finite() && number > 0. => Ok(number),
+        _ => Err(MarkupError::new(
+            position,
+            format!("`{}` expects a positive number: `[{}=0.5]`", tag, tag),
+        )),
+    }
+}
+
+/// Reads the tags of a `text`.
+///
+/// # Errors
+///
+/// Returns a MarkupError, pointing to the offending tag,
+/// if a tag is unknown, not closed by `]`, has an invalid value or closes nothing.
+pub fn parse_markup(text: &str) -> Result<RichText, MarkupError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut rich_text = RichText::default();
+
+    let mut colors: Vec<[f32; 3]> = Vec::new();
+    let mut speeds: Vec<f32> = Vec::new();
+    let mut bold = 0_usize;
+    let mut shake = 0_usize;
+
+    let mut run = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '[' {
+            run.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        if chars.get(i + 1) == Some(&'[') {
+            run.push('[');
+            i += 2;
+            continue;
+        }
+
+        let close = match chars[i..].iter().position(|c| *c == ']') {
+            Some(close) => i + close,
+            None => return Err(MarkupError::new(i, "a tag is not closed by `]`")),
+        };
+        let tag: String = chars[i + 1..close].iter().collect();
+        let (name, value) = match tag.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (tag.trim(), None),
+        };
+
+        // the text before the tag keeps the previous style
+        let style = MarkupStyle {
+            color: colors.last().copied(),
+            bold: bold > 0,
+            shake: shake > 0,
+        };
+        rich_text.push_graphemes(&run, style);
+        run.clear();
+
+        let closes_nothing = || MarkupError::new(i, format!("`[{}]` closes nothing", tag));
+        match (name, value) {
+            ("b", None) => bold += 1,
+            ("/b", None) => bold = bold.checked_sub(1).ok_or_else(closes_nothing)?,
+            ("shake", None) => shake += 1,
+            ("/shake", None) => shake = shake.checked_sub(1).ok_or_else(closes_nothing)?,
+            ("color", Some(color)) => match parse_color(color) {
+                Some(color) => colors.push(color),
+                None => {
+                    return Err(MarkupError::new(
+                        i,
+                        format!("unknown color `{}`, expected a name or `#rrggbb`", color),
+                    ))
+                }
+            },
+            ("/color", None) => {
+                colors.pop().ok_or_else(closes_nothing)?;
+            }
+            ("speed", Some(speed)) => {
+                let speed = parse_number(speed, i, "speed")?;
+                speeds.push(speed);
+                rich_text.steps.push(RevealStep::Speed(speed));
+            }
+            ("/speed", None) => {
+                speeds.pop().ok_or_else(closes_nothing)?;
+                rich_text
+                    .steps
+                    .push(RevealStep::Speed(speeds.last().copied().unwrap_or(1.)));
+            }
+            ("wait", Some(seconds)) => {
+                let seconds = parse_number(seconds, i, "wait")?;
+                rich_text.steps.push(RevealStep::Wait(seconds));
+            }
+            _ => {
+                return Err(MarkupError::new(
+                    i,
+                    format!(
+                        "unknown tag `[{}]`, expected `color`, `b`, `shake`, `speed` or `wait`",
+                        tag
+                    ),
+                ))
+            }
+        }
+
+        i = close + 1;
+    }
+
+    let style = MarkupStyle {
+        color: colors.last().copied(),
+        bold: bold > 0,
+        shake: shake > 0,
+    };
+    rich_text.push_graphemes(&run, style);
+
+    Ok(rich_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grapheme(section: usize, grapheme: &str) -> RevealStep {
+        RevealStep::Grapheme {
+            section,
+            grapheme: grapheme.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_sections_and_steps() {
+        let rich_text =
+            parse_markup("Hé [color=red][b]yo[/b][/color][wait=0.5]![speed=2]?").unwrap();
+
+        assert_eq!(
+            rich_text.sections,
+            vec![
+                MarkupStyle::default(),
+                MarkupStyle {
+                    color: Some([0.8, 0.1, 0.1]),
+                    bold: true,
+                    shake: false,
+                },
+                MarkupStyle::default(),
+            ]
+        );
+        assert_eq!(
+            rich_text.steps,
+            vec![
+                grapheme(0, "H"),
+                grapheme(0, "é"),
+                grapheme(0, " "),
+                grapheme(1, "y"),
+                grapheme(1, "o"),
+                RevealStep::Wait(0.5),
+                // back to the default style: a new section
+                grapheme(2, "!"),
+                RevealStep::Speed(2.),
+                grapheme(2, "?"),
+            ]
+        );
+        assert_eq!(rich_text.to_plain(), "Hé yo!?");
+    }
+
+    #[test]
+    fn test_graphemes_are_never_cut() {
+        // `e` followed by a combining acute accent, and a waving hand with a skin tone
+        let rich_text = RichText::plain("e\u{301}👋🏽");
+
+        assert_eq!(
+            rich_text.steps,
+            vec![grapheme(0, "e\u{301}"), grapheme(0, "👋🏽")]
+        );
+    }
+
+    #[test]
+    fn test_nested_and_escaped_tags() {
+        let rich_text = parse_markup("[[x] [color=#00ff00][color=blue]a[/color]b[/color]").unwrap();
+
+        assert_eq!(rich_text.to_plain(), "[x] ab");
+        assert_eq!(strip_markup("[b]Hey[/b] [[you]"), "Hey [you]");
+        assert_eq!(strip_markup("Hey [you]"), "Hey [you]");
+        assert_eq!(
+            rich_text
+                .sections
+                .iter()
+                .map(|style| style.color)
+                .collect::<Vec<_>>(),
+            vec![None, Some([0.1, 0.2, 0.8]), Some([0., 1., 0.])]
+        );
+    }
+
+    #[test]
+    fn test_invalid_markup() {
+        assert_eq!(
+            parse_markup("Hey [b").unwrap_err(),
+            MarkupError::new(4, "a tag is not closed by `]`")
+        );
+        assert_eq!(parse_markup("a[/b]").unwrap_err().position, 1);
+        assert_eq!(parse_markup("[color=pink]").unwrap_err().position, 0);
+        assert!(parse_markup("[wait=-1]").is_err());
+        assert!(parse_markup("[italic]").is_err());
+    }
+}
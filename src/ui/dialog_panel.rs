@@ -12,39 +12,86 @@
 
 use bevy::prelude::*;
 use bevy_tweening::{lens::UiPositionLens, *};
+use rand::{rngs::StdRng, SeedableRng};
 use std::time::Duration;
 
 use crate::{
-    combat::{CombatEvent, CombatExitEvent, Karma},
-    constants::ui::dialogs::*,
+    combat::{stats::HP, stuff::Inventory, CombatEvent, CombatExitEvent, Karma, Recruted},
+    constants::{character::player::MC_NAME, ui::dialogs::*},
     npc::NPC,
     player::Player,
     ui::{
+        dialog_advance::AdvanceModeButton,
+        dialog_asset::DialogAsset,
+        dialog_cast::DialogCast,
+        dialog_condition::ConditionContext,
         dialog_scroll::{
-            PlayerChoice, PlayerScroll, Scroll, ScrollTimer, UpdateScrollEvent, UpperScroll,
+            ChoicePageButton, PlayerChoice, PlayerScroll, Scroll, ScrollTimer, UpdateScrollEvent,
+            UpperScroll,
         },
-        dialog_system::{init_tree_file, Dialog, DialogType},
+        dialog_speaker::{Speaker, SpeakerName, SpeakerPortrait},
+        dialog_system::{
+            interpolate, Dialog, DialogContext, DialogCursor, DialogGraph, DialogNode, DialogType,
+        },
+        dialog_timer::ChoiceTimerBar,
     },
+    world_flags::WorldFlags,
 };
 
 /// Represents The UI Wall.
 /// Every UI Wall is associated with an entity (can be the player or the interlocutor like an object).
 ///
-/// If the dialog_tree changes, it will update the scrolls.
+/// If the cursor (or the dialog_tree) changes, it will update the scrolls.
+///
+/// To modify the dialog, just move the cursor of the DialogPanel.
 ///
-/// To modify the dialog, just modify the DialogPanel.
+/// The author of the current node is resolved by `ui::dialog_speaker::update_speaker`.
 ///
 /// # Note
 ///
-/// TODO: feature - sync author with interlocutor (to know which one is talking)
 /// REFACTOR: Turn DialogPanel into a Resource
 #[derive(Component, Reflect)]
 pub struct DialogPanel {
     // keep track of the origanal interlocutor
     // their dialog will be change/update in update_dialog_tree
     pub main_interlocutor: Entity,
+    /// The whole dialog of the interlocutor
+    pub dialog_tree: DialogGraph,
     // XXX: will allow us to detect change especially in the opening
-    pub dialog_tree: String,
+    pub cursor: DialogCursor,
+    /// The choice picked by the npc in the current node (pick_npc_choice)
+    pub npc_choice: Option<usize>,
+}
+
+impl DialogPanel {
+    /// Replaces the dialog (modified or translated file),
+    /// keeping the progress if the branch taken still exists.
+    pub fn rebuild(&mut self, dialog_tree: &DialogGraph) {
+        self.dialog_tree = dialog_tree.clone();
+        if !self.cursor.is_valid(&self.dialog_tree) {
+            warn!("The dialog progress is lost, it restarts");
+            self.cursor.restart();
+            self.npc_choice = None;
+        }
+    }
+}
+
+/// Draws the choices said by the npcs.
+///
+/// Can be seeded to always replay the same dialogs.
+#[derive(Resource)]
+pub struct DialogRng(pub StdRng);
+
+impl Default for DialogRng {
+    fn default() -> Self {
+        DialogRng(StdRng::from_entropy())
+    }
+}
+
+impl DialogRng {
+    pub fn seeded(seed: u64) -> Self {
+        DialogRng(StdRng::seed_from_u64(seed))
+    }
 }
 
 /// Happens when
@@ -59,16 +106,18 @@ pub struct DialogPanel {
 #[derive(Event)]
 pub struct CreateDialogPanelEvent {
     interlocutor: Entity,
-    dialog_tree: String,
+    dialog_tree: DialogGraph,
+    cursor: DialogCursor,
 }
 
 /// Happens when
+///   - ui::dialog_player::dialog_dive
+///     - the cursor of the DialogPanel is ended
 ///   - ui::dialog_panel::update_dialog_panel
-///     - the dialog tree contained within the DialogPanel is empty
+///     - the current node of the DialogPanel is empty
 /// Read in
 ///   - ui::dialog_panel::end_node_dialog
-///     - fills the given interlocutor with a blank "..." dialog
-///     and exit the Combat ( send CombatExitEvent )
+///     - exit the Combat ( send CombatExitEvent )
 #[derive(Event)]
 pub struct EndNodeDialogEvent;
 
@@ -87,7 +136,7 @@ pub struct CloseDialogPanelEvent;
 
 #[derive(Resource)]
 pub struct DialogPanelResources {
-    text_font: Handle<Font>,
+    pub text_font: Handle<Font>,
     appartements: Handle<Image>,
     stained_glass_panels: Handle<Image>,
     background: Handle<Image>,
@@ -139,6 +188,7 @@ pub fn create_dialog_panel_on_key_press(
     query: Query<(Entity, &Animator<Style>, &Style), With<DialogPanel>>,
     keyboard_input: Res<Input<KeyCode>>,
     player_query: Query<(Entity, &Dialog), With<Player>>,
+    dialog_assets: Res<Assets<DialogAsset>>,
 ) {
     if keyboard_input.just_pressed(KeyCode::O) {
         if let Ok((_entity, animator, _style)) = query.get_single() {
@@ -153,17 +203,15 @@ pub fn create_dialog_panel_on_key_press(
             let (player, dialog) = player_query.single();
             // warn!("The player doesn't have a Dialog")
 
-            let dialog_tree: String;
-            match &dialog.current_node {
-                Some(text) => dialog_tree = text.to_owned(),
-                None => dialog_tree = String::new(),
+            match dialog_assets.get(&dialog.source) {
+                Some(dialog_asset) => create_dialog_panel_event.send(CreateDialogPanelEvent {
+                    // keep track of player's personal thoughts
+                    interlocutor: player,
+                    dialog_tree: dialog_asset.graph.clone(),
+                    cursor: dialog.cursor.clone(),
+                }),
+                None => warn!("The player's dialog file is not loaded yet"),
             }
-
-            create_dialog_panel_event.send(CreateDialogPanelEvent {
-                // keep track of player's personal thoughts
-                interlocutor: player,
-                dialog_tree,
-            });
         }
     }
 }
@@ -177,6 +225,8 @@ pub fn create_dialog_panel_on_key_press(
 ///
 /// Interpret the dialog carried by the entity.
 ///
+/// If the npc owns a DialogCast, the dialog is first chosen from it.
+///
 /// In Dialog Sequence,
 /// we might -want to- have the last text
 /// when the player is ask to choose a answer.
@@ -200,7 +250,10 @@ pub fn create_dialog_panel_on_combat_event(
     // cause player can only talk with theirself
     // by create_dialog_panel_on_key_press
     // not by CombatEvent
-    npc_query: Query<(Entity, &Dialog), With<NPC>>,
+    mut npc_query: Query<(Entity, &mut Dialog, Option<&mut DialogCast>), With<NPC>>,
+    dialog_assets: Res<Assets<DialogAsset>>,
+    world_flags: Res<WorldFlags>,
+    time: Res<Time>,
 ) {
     for ev in ev_combat.iter() {
         // if already open go to combat tab
@@ -215,17 +268,35 @@ pub fn create_dialog_panel_on_combat_event(
             info!("Open UI Combat");
 
             let npc = ev.npc_entity;
-            match npc_query.get(npc) {
-                Ok((_npc_entity, dialog)) => {
-                    let dialog_tree: String;
-                    match &dialog.current_node {
-                        Some(text) => dialog_tree = text.to_owned(),
-                        None => dialog_tree = String::new(),
+            match npc_query.get_mut(npc) {
+                Ok((_npc_entity, mut dialog, cast)) => {
+                    if let Some(mut cast) = cast {
+                        match cast.engage(&world_flags, time.elapsed_seconds_f64()) {
+                            Some(engaged) => {
+                                dialog.source = engaged.source.clone();
+                                dialog.cursor = engaged.cursor.clone();
+                            }
+                            // the npc has nothing to say: `...`
+                            None => dialog.cursor = DialogCursor::ended(),
+                        }
+                    }
+
+                    match dialog_assets.get(&dialog.source) {
+                        Some(dialog_asset) => {
+                            // the file of a dialog kept in the cast can be modified
+                            if !dialog.cursor.is_valid(&dialog_asset.graph) {
+                                warn!("The dialog progress of {:?} is lost, it restarts", npc);
+                                dialog.cursor.restart();
+                            }
+
+                            create_dialog_panel_event.send(CreateDialogPanelEvent {
+                                interlocutor: npc,
+                                dialog_tree: dialog_asset.graph.clone(),
+                                cursor: dialog.cursor.clone(),
+                            })
+                        }
+                        None => warn!("The dialog file of {:?} is not loaded yet", npc),
                     }
-                    create_dialog_panel_event.send(CreateDialogPanelEvent {
-                        interlocutor: npc,
-                        dialog_tree,
-                    });
                 }
 
                 Err(e) => {
@@ -301,6 +372,7 @@ pub fn create_dialog_panel(
     for CreateDialogPanelEvent {
         interlocutor,
         dialog_tree,
+        cursor,
     } in create_dialog_panel_events.iter()
     {
         info!("open dialog event");
@@ -372,8 +444,11 @@ pub fn create_dialog_panel(
                 // REFACTOR: Turn DialogPanel into a Resource
                 DialogPanel {
                     main_interlocutor: *interlocutor,
-                    dialog_tree: dialog_tree.to_owned(),
+                    dialog_tree: dialog_tree.clone(),
+                    cursor: cursor.clone(),
+                    npc_choice: None,
                 },
+                Speaker::default(),
                 Animator::new(dialog_panel_tween),
                 Name::new("UI Wall"),
             ))
@@ -481,6 +556,53 @@ pub fn create_dialog_panel(
                             },
                             ..TextBundle::default()
                         });
+
+                        // after the text: the DialogBox's text is the first child
+                        parent
+                            .spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        top: Val::Px(330.),
+                                        left: Val::Percent(24.),
+                                        flex_direction: FlexDirection::Row,
+                                        align_items: AlignItems::Center,
+                                        ..Style::default()
+                                    },
+                                    ..NodeBundle::default()
+                                },
+                                Name::new("Speaker"),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    ImageBundle {
+                                        style: Style {
+                                            width: Val::Px(40.),
+                                            height: Val::Px(40.),
+                                            margin: UiRect::right(Val::Px(8.)),
+                                            ..Style::default()
+                                        },
+                                        // shown by update_speaker
+                                        visibility: Visibility::Hidden,
+                                        ..ImageBundle::default()
+                                    },
+                                    SpeakerPortrait,
+                                    Name::new("Speaker Portrait"),
+                                ));
+
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font: dialog_panel_resources.text_font.clone(),
+                                            font_size: 25.,
+                                            color: Color::BLACK,
+                                        },
+                                    ),
+                                    SpeakerName,
+                                    Name::new("Speaker Name"),
+                                ));
+                            });
                     })
                     // .insert(DialogBox::new(dialog[0].clone(), DIALOG_BOX_UPDATE_DELTA_S))
                     ;
@@ -525,6 +647,7 @@ pub fn create_dialog_panel(
                         PlayerScroll {
                             // will be changed in update_dialog_panel
                             choices: vec![],
+                            page: 0,
                         },
                         ScrollTimer(Timer::from_seconds(
                             SCROLL_ANIMATION_DELTA_S,
@@ -533,152 +656,105 @@ pub fn create_dialog_panel(
                         Name::new("Player Scroll"),
                     ))
                     .with_children(|parent| {
-                        // TODO: feature - 3 PlayerChoice is enough, to have much reuse theses three in another page
+                        // the choices of the page shown (ui::dialog_scroll::update_player_scroll)
+                        for (index, top) in CHOICE_BUTTON_TOPS.iter().enumerate() {
+                            spawn_choice_button(
+                                parent,
+                                index,
+                                *top,
+                                dialog_panel_resources.text_font.clone(),
+                            );
+                        }
+                    });
 
-                        // First potential choice
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: Style {
-                                        // TODO: custom size ? (text dependent)
-                                        // Percent ?
-                                        width: Val::Px(300.),
-                                        height: Val::Px(30.),
-                                        margin: UiRect::all(Val::Auto),
-                                        // margin: UiRect {
-                                        //     top: Val::Percent(105.),
-                                        //     left: Val::Percent(24.),
-                                        //     ..UiRect::default()
-                                        // },
-                                        // justify_content: JustifyContent::SpaceAround,
-                                        top: Val::Px(450.),
-                                        left: Val::Px(10.),
-                                        ..default()
-                                    },
-                                    background_color: NORMAL_BUTTON.into(),
-                                    ..default()
-                                },
-                                PlayerChoice(0),
-                                Name::new("First Choice"),
-                            ))
-                            // .insert(DialogBox::new("".to_owned(), DIALOG_BOX_UPDATE_DELTA_S))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle {
-                                    text: Text::from_section(
-                                        "",
-                                        TextStyle {
-                                            font: dialog_panel_resources.text_font.clone(),
-                                            // TODO: Find the correct value for the choice font size
-                                            font_size: 20.,
-                                            color: Color::BLACK,
-                                        },
-                                    )
-                                    .with_alignment(TextAlignment::Left),
-                                    style: Style {
-                                        flex_wrap: FlexWrap::Wrap,
-                                        max_width: Val::Px(300.),
-                                        max_height: Val::Percent(100.),
-                                        ..Style::default()
-                                    },
-                                    ..TextBundle::default()
-                                });
-                            });
+                // the time left to pick a timed choice (ui::dialog_timer)
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(430.),
+                            left: Val::Px(10.),
+                            width: Val::Px(CHOICE_TIMER_BAR_WIDTH),
+                            height: Val::Px(6.),
+                            ..Style::default()
+                        },
+                        background_color: CHOICE_TIMER_BAR_COLOR.into(),
+                        // shown by tick_choice_countdown
+                        visibility: Visibility::Hidden,
+                        ..NodeBundle::default()
+                    },
+                    ChoiceTimerBar,
+                    Name::new("Choice Timer Bar"),
+                ));
 
-                        // Second potential choice
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: Style {
-                                        // TODO: custom size ? (text dependent)
-                                        width: Val::Px(300.),
-                                        height: Val::Px(30.),
-                                        top: Val::Px(250.),
-                                        left: Val::Px(10.),
-                                        margin: UiRect::all(Val::Auto),
-                                        // margin: UiRect {
-                                        //     top: Val::Percent(125.),
-                                        //     left: Val::Percent(24.),
-                                        //     ..UiRect::default()
-                                        // },
-                                        // justify_content: JustifyContent::SpaceAround,
-                                        ..default()
-                                    },
-                                    background_color: NORMAL_BUTTON.into(),
-                                    ..default()
+                // turn the pages of the player's choices (ui::dialog_scroll)
+                for (page_button, left, label) in [
+                    (ChoicePageButton::Previous, 320., "<"),
+                    (ChoicePageButton::Next, 360., ">"),
+                ] {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    top: Val::Px(420.),
+                                    left: Val::Px(left),
+                                    width: Val::Px(30.),
+                                    height: Val::Px(30.),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..Style::default()
                                 },
-                                PlayerChoice(1),
-                                Name::new("Second Choice"),
-                            ))
-                            // .insert(DialogBox::new("".to_owned(), DIALOG_BOX_UPDATE_DELTA_S))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle {
-                                    text: Text::from_section(
-                                        "",
-                                        TextStyle {
-                                            font: dialog_panel_resources.text_font.clone(),
-                                            // TODO: Find the correct value for the choice font size
-                                            font_size: 20.,
-                                            color: Color::BLACK,
-                                        },
-                                    )
-                                    .with_alignment(TextAlignment::Left),
-                                    style: Style {
-                                        flex_wrap: FlexWrap::Wrap,
-                                        max_width: Val::Px(300.),
-                                        max_height: Val::Percent(100.),
-                                        ..Style::default()
-                                    },
-                                    ..TextBundle::default()
-                                });
-                            });
-
-                        // Third potential choice
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: Style {
-                                        // TODO: custom size ? (text dependent)
-                                        width: Val::Px(300.),
-                                        height: Val::Px(30.),
-                                        top: Val::Px(50.),
-                                        left: Val::Px(10.),
-                                        margin: UiRect::all(Val::Auto),
-                                        // margin: UiRect {
-                                        //     top: Val::Percent(145.),
-                                        //     left: Val::Percent(24.),
-                                        //     ..UiRect::default()
-                                        // },
-                                        // justify_content: JustifyContent::SpaceAround,
-                                        ..default()
-                                    },
-                                    background_color: NORMAL_BUTTON.into(),
-                                    ..default()
+                                background_color: NORMAL_BUTTON.into(),
+                                // shown by update_choice_page_buttons
+                                visibility: Visibility::Hidden,
+                                ..ButtonBundle::default()
+                            },
+                            page_button,
+                            Name::new(format!("{:?} Choice Page", page_button)),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                label,
+                                TextStyle {
+                                    font: dialog_panel_resources.text_font.clone(),
+                                    font_size: 20.,
+                                    color: Color::BLACK,
                                 },
-                                PlayerChoice(2),
-                                Name::new("Third Choice"),
-                            ))
-                            // .insert(DialogBox::new("".to_owned(), DIALOG_BOX_UPDATE_DELTA_S))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle {
-                                    text: Text::from_section(
-                                        "",
-                                        TextStyle {
-                                            font: dialog_panel_resources.text_font.clone(),
-                                            // TODO: Find the correct value for the choice font size
-                                            font_size: 20.,
-                                            color: Color::BLACK,
-                                        },
-                                    )
-                                    .with_alignment(TextAlignment::Left),
-                                    style: Style {
-                                        flex_wrap: FlexWrap::Wrap,
-                                        max_width: Val::Px(300.),
-                                        max_height: Val::Percent(100.),
-                                        ..Style::default()
-                                    },
-                                    ..TextBundle::default()
-                                });
-                            });
+                            ));
+                        });
+                }
+
+                // toggles the advance mode of the texts (ui::dialog_advance)
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                top: Val::Px(10.),
+                                right: Val::Px(10.),
+                                width: Val::Px(120.),
+                                height: Val::Px(30.),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Style::default()
+                            },
+                            background_color: NORMAL_BUTTON.into(),
+                            ..ButtonBundle::default()
+                        },
+                        AdvanceModeButton,
+                        Name::new("Advance Mode Button"),
+                    ))
+                    .with_children(|parent| {
+                        // written by update_advance_mode_button
+                        parent.spawn(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: dialog_panel_resources.text_font.clone(),
+                                font_size: 20.,
+                                color: Color::BLACK,
+                            },
+                        ));
                     });
             });
 
@@ -687,11 +763,58 @@ pub fn create_dialog_panel(
     }
 }
 
+/// Spawns the PlayerChoice button at this `index` of the player scroll,
+/// displaying the choice at the same place in the page shown.
+fn spawn_choice_button(parent: &mut ChildBuilder, index: usize, top: f32, font: Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    // TODO: custom size ? (text dependent)
+                    width: Val::Px(300.),
+                    height: Val::Px(30.),
+                    top: Val::Px(top),
+                    left: Val::Px(10.),
+                    margin: UiRect::all(Val::Auto),
+                    ..default()
+                },
+                background_color: NORMAL_BUTTON.into(),
+                ..default()
+            },
+            PlayerChoice(index),
+            Name::new(format!("Choice n°{}", index + 1)),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        // TODO: Find the correct value for the choice font size
+                        font_size: 20.,
+                        color: Color::BLACK,
+                    },
+                )
+                .with_alignment(TextAlignment::Left),
+                style: Style {
+                    flex_wrap: FlexWrap::Wrap,
+                    max_width: Val::Px(300.),
+                    max_height: Val::Percent(100.),
+                    ..Style::default()
+                },
+                ..TextBundle::default()
+            });
+        });
+}
+
 /// # Purpose
 ///
 /// When the dialog file implied in the talk is changed,
 /// updates the scrolls' content.
 ///
+/// Also filters the player's choices again when the WorldFlags change:
+/// a flag raised mid-dialog can reveal a choice.
+///
 /// # Process
 ///
 /// check the current node from the interlocutor
@@ -703,20 +826,26 @@ pub fn create_dialog_panel(
 ///   - Player Choice
 ///     - update the player_scroll (implied: let the upper_scroll)
 ///   - NPC Choice
-///     TODO: feature - NPC Choice
-///     for now, the player has to choose what the npc should say..
+///     - the npc says the choice picked by pick_npc_choice
+/// - the dialog is ended
+///   - the interlocutor has nothing more to say: `...`
+///
+/// The variables of the texts (`{player.name}`) are filled with the current state of the game.
 pub fn update_dialog_panel(
     panel_query: Query<
-        (Entity, &DialogPanel),
+        (Entity, Ref<DialogPanel>),
         // REFACTOR: Handle the interlocutor change in the UIPanel
         // even detect interlocutor change
-        (Changed<DialogPanel>, With<Animator<Style>>),
+        With<Animator<Style>>,
     >,
 
     mut upper_scroll_query: Query<(&mut UpperScroll, Entity), With<Scroll>>,
     mut player_scroll_query: Query<(&mut PlayerScroll, Entity), With<Scroll>>,
 
-    player_query: Query<(Entity, &Karma), With<Player>>,
+    player_query: Query<(&Karma, Option<&HP>, Option<&Inventory>), With<Player>>,
+    party_query: Query<&Name, (With<NPC>, With<Recruted>)>,
+    names_query: Query<&Name>,
+    world_flags: Res<WorldFlags>,
 
     mut end_node_dialog_event: EventWriter<EndNodeDialogEvent>,
     mut update_scroll_content: EventWriter<UpdateScrollEvent>,
@@ -729,27 +858,48 @@ pub fn update_dialog_panel(
     //   just wait for the DialogTree to change;
     //   Nothing change yet
     if let Ok((_ui_wall, panel)) = panel_query.get_single() {
+        // a raised flag can reveal some choices of the player, without leaving the node
+        let flags_changed = world_flags.is_changed()
+            && panel
+                .cursor
+                .current(&panel.dialog_tree)
+                .is_some_and(|current| current.is_choice() && is_said_by_mc(current));
+        if !panel.is_changed() && !flags_changed {
+            return;
+        }
+
         info!("DEBUG: smth changed...");
 
-        let dialog_panel = &panel.dialog_tree;
-        // DEBUG: print DialogTree
-        println!("{:?}", dialog_panel);
+        // DEBUG: print DialogCursor
+        println!("{:?}", panel.cursor);
+
+        let (karma, hp, inventory) = player_query.single();
+        let condition_context = condition_context(
+            karma,
+            hp,
+            inventory,
+            &party_query,
+            &world_flags,
+            panel.cursor.hesitated,
+        );
+        let context = DialogContext {
+            player_name: MC_NAME.to_string(),
+            karma: karma.0,
+            party_size: 1 + condition_context.party.len(),
+            npc_name: names_query
+                .get(panel.main_interlocutor)
+                .map_or(String::new(), |name| name.to_string()),
+        };
 
         // check what is the current dialog node
-        if dialog_panel.is_empty() {
-            // info!("DEBUG Empty Dialog Tree");
-            end_node_dialog_event.send(EndNodeDialogEvent);
-        } else {
-            let dialog_tree = init_tree_file(dialog_panel.to_owned());
-
-            let current = &dialog_tree.borrow();
-
+        if let Some(current) = panel.cursor.current(&panel.dialog_tree) {
             let dialogs = &current.dialog_type;
 
             // throw Err(outOfBound) when dialog_type is empty (not intended)
-            if dialogs.len() < 1 {
-                // FIXME: handle this err
-                panic!("Err: dialog_type is empty");
+            if dialogs.is_empty() {
+                warn!("Empty DialogNode, the dialog is ended");
+                end_node_dialog_event.send(EndNodeDialogEvent);
+                return;
             }
 
             let (mut player_scroll, _player_scroll_entity) = player_scroll_query.single_mut();
@@ -761,10 +911,13 @@ pub fn update_dialog_panel(
                     for dialog in dialogs.iter() {
                         match dialog {
                             DialogType::Text(text) => {
-                                texts.push(text.to_owned());
+                                texts.push(interpolate(text, &context));
                                 info!("DEBUG: add text: {}", text);
                             }
-                            _ => panic!("Err: DialogTree Incorrect; A texts' vector contains something else"),
+                            // refused by parse_dialog
+                            _ => warn!(
+                                "DialogTree Incorrect; A texts' vector contains something else"
+                            ),
                         }
                     }
                     // replace the entire upper scroll's content
@@ -773,51 +926,173 @@ pub fn update_dialog_panel(
                     upper_scroll.texts = texts;
 
                     // Clear the previous choice if there is any
-                    player_scroll.choices.clear();
+                    player_scroll.set_choices(Vec::new());
+                }
+                DialogType::Choice { .. } if !is_said_by_mc(current) => {
+                    match panel.npc_choice {
+                        Some(child_index) => {
+                            if let Some(DialogType::Choice { text, .. }) = dialogs.get(child_index)
+                            {
+                                let (mut upper_scroll, _upper_scroll_entity) =
+                                    upper_scroll_query.single_mut();
+                                upper_scroll.texts = vec![interpolate(text, &context)];
+                                info!("DEBUG: npc choice: {}", text);
+                            }
+                            player_scroll.set_choices(Vec::new());
+                        }
+                        // wait for the npc to pick its choice
+                        None => return,
+                    }
                 }
-                DialogType::Choice {
-                    text: _,
-                    condition: _,
-                } => {
+                DialogType::Choice { .. } => {
                     // replace current by the new set of choices
-                    let mut choices = Vec::<String>::new();
-                    for dialog in dialogs.iter() {
+                    // each one keeps its index: the index of the child it leads to
+                    let mut choices = Vec::<(usize, String)>::new();
+                    for (child_index, dialog) in dialogs.iter().enumerate() {
                         match dialog {
-                            DialogType::Choice { text, condition } => {
+                            DialogType::Choice {
+                                text, condition, ..
+                            } => {
                                 match condition {
                                     Some(cond) => {
-                                        let (_player, karma) = player_query.single();
-                                        if cond.is_verified(karma.0) {
-                                            choices.push(text.to_owned());
+                                        // hide the choices whose condition does not hold
+                                        if cond.is_verified(&condition_context) {
+                                            choices
+                                                .push((child_index, interpolate(text, &context)));
                                             info!("DEBUG: add choice: {}", text);
                                         }
                                     }
                                     // no condition
                                     None => {
-                                        choices.push(text.to_owned());
+                                        choices.push((child_index, interpolate(text, &context)));
                                         info!("DEBUG: add choice: {}", text);
                                     }
                                 }
                             }
-                            _ => panic!("Err: DialogTree Incorrect; A choices' vector contains something else"),
+                            // refused by parse_dialog
+                            _ => warn!(
+                                "DialogTree Incorrect; A choices' vector contains something else"
+                            ),
                         }
                     }
                     // update the player_scroll
-                    player_scroll.choices = choices;
+                    // (keeps the page shown if the flags did not change them)
+                    if panel.is_changed() || player_scroll.choices != choices {
+                        player_scroll.set_choices(choices);
+                    }
                 }
             }
             // ask to update the content of scroll
+            // (the upper scroll is left as is when only the flags changed)
+            if panel.is_changed() {
+                update_scroll_content.send(UpdateScrollEvent);
+            }
+        } else {
+            // info!("DEBUG Ended Dialog");
+            let (mut upper_scroll, _upper_scroll_entity) = upper_scroll_query.single_mut();
+            upper_scroll.texts = vec![String::from("...")];
+
+            let (mut player_scroll, _player_scroll_entity) = player_scroll_query.single_mut();
+            player_scroll.set_choices(Vec::new());
+
             update_scroll_content.send(UpdateScrollEvent);
         }
     }
 }
 
+/// The state of the game the conditions of the choices are checked against.
+fn condition_context<'a>(
+    karma: &Karma,
+    hp: Option<&HP>,
+    inventory: Option<&'a Inventory>,
+    party_query: &Query<&Name, (With<NPC>, With<Recruted>)>,
+    world_flags: &'a WorldFlags,
+    hesitated: bool,
+) -> ConditionContext<'a> {
+    ConditionContext {
+        party: party_query.iter().map(|name| name.to_string()).collect(),
+        hp_ratio: hp.map_or(1., |hp| {
+            if hp.max_hp > 0 {
+                hp.current_hp as f32 / hp.max_hp as f32
+            } else {
+                0.
+            }
+        }),
+        inventory,
+        hesitated,
+        ..ConditionContext::new(karma.0, world_flags)
+    }
+}
+
+/// # Return
+///
+/// true if the author of this node is the Main Character
+pub fn is_said_by_mc(node: &DialogNode) -> bool {
+    node.character.as_deref() == Some(MC_NAME)
+}
+
+/// Picks the choice said by the npc,
+/// when the current node of the DialogPanel is a npc's choice.
+///
+/// # Note
+///
+/// The verified choices with the highest priority are drawn according to their weight.
+/// If none is verified, the dialog is ended.
+pub fn pick_npc_choice(
+    mut panel_query: Query<&mut DialogPanel, (Changed<DialogPanel>, With<Animator<Style>>)>,
+
+    player_query: Query<(&Karma, Option<&HP>, Option<&Inventory>), With<Player>>,
+    party_query: Query<&Name, (With<NPC>, With<Recruted>)>,
+    world_flags: Res<WorldFlags>,
+    mut dialog_rng: ResMut<DialogRng>,
+
+    mut end_node_dialog_event: EventWriter<EndNodeDialogEvent>,
+) {
+    for mut panel in panel_query.iter_mut() {
+        if panel.npc_choice.is_some() {
+            continue;
+        }
+
+        let pick = match panel.cursor.current(&panel.dialog_tree) {
+            Some(current) if current.is_choice() && !is_said_by_mc(current) => {
+                // a npc can react to the hesitation of the player
+                let hesitated = panel.cursor.hesitated;
+                let context = match player_query.get_single() {
+                    Ok((karma, hp, inventory)) => condition_context(
+                        karma,
+                        hp,
+                        inventory,
+                        &party_query,
+                        &world_flags,
+                        hesitated,
+                    ),
+                    Err(_) => ConditionContext {
+                        hesitated,
+                        ..ConditionContext::new(0, &world_flags)
+                    },
+                };
+                current.pick_choice(&context, &mut dialog_rng.0)
+            }
+            _ => continue,
+        };
+
+        match pick {
+            Some(child_index) => panel.npc_choice = Some(child_index),
+            None => {
+                warn!("The npc has no verified choice, the dialog is ended");
+                panel.cursor.end();
+                end_node_dialog_event.send(EndNodeDialogEvent);
+            }
+        }
+    }
+}
+
 /// # Save principe
 ///
-/// Updates the String within the entity interlocutor.
+/// Updates the DialogCursor within the entity interlocutor.
 /// This just updates the Dialog contained in the interlocutor to be retrieve the next time we talk with it.
 /// We want to save the dialog progress at each state;
-/// Each time the dialog_tree of the panel is changed
+/// Each time the cursor of the panel is moved
 /// (?OR can be delay to the end of fight)
 ///
 /// # Note
@@ -830,9 +1105,9 @@ pub fn update_dialog_tree(
 ) {
     for panel in dialog_panel_query.iter() {
         let interlocutor = panel.main_interlocutor;
-        let new_dialog_tree = panel.dialog_tree.clone();
         match interlocutor_query.get_mut(interlocutor) {
-            Ok((_entity, mut dialog)) => dialog.current_node = Some(new_dialog_tree),
+            // the original dialog_tree is kept untouched
+            Ok((_entity, mut dialog)) => dialog.cursor = panel.cursor.clone(),
             Err(e) => warn!(
                 "The entity linked with the Ui Wall doesn't have any Dialog Component: {:?}",
                 e
@@ -841,62 +1116,25 @@ pub fn update_dialog_tree(
     }
 }
 
-/// Fills the given interlocutor with a blank "..." dialog
-/// and exit the Combat ( send CombatExitEvent )
+/// Exits the Combat ( send CombatExitEvent )
+///
+/// The interlocutor keeps its ended cursor (saved by update_dialog_tree):
+/// at the next encounter, they will have nothing more to say: `...`
 pub fn end_node_dialog(
     mut end_node_dialog_event: EventReader<EndNodeDialogEvent>,
 
-    panel_query: Query<(Entity, &DialogPanel), With<Animator<Style>>>,
-    mut interlocutor_query: Query<
-        (Entity, &Name, &mut Dialog),
-        // Player or NPC
-        // TODO: feature - include Object (rm these Withs)
-        Or<(With<Player>, With<NPC>)>,
-    >,
-
     mut ev_combat_exit: EventWriter<CombatExitEvent>,
 ) {
     for _ in end_node_dialog_event.iter() {
         info!("DEBUG: EndNodeEvent...");
 
-        let (_ui_wall, panel) = panel_query.single();
-
-        // TODO: feature - manage a cast of NPC choice for each dialog
-        // with a priority system to choose
-        // engaging a dialog will then choose a certain dialog from the cast
-        // leaving mid course will save the current dialog
-        // UNLESS there is a overide
-        // in case of big event, cancel previous dialog to stick to the main line
-
-        // reset the dialog to the first node: NPC's Choice cast
+        // the dialog of the interlocutor is saved in their cast
+        // by ui::dialog_cast::leave_cast_dialog, when the panel is closed
 
         info!("exit dialog");
 
-        let interlocutor = panel.main_interlocutor;
-        let (_interlocutor_entity, name, mut dialog) =
-            interlocutor_query.get_mut(interlocutor).unwrap();
-
-        // replace the current tree by a simple text: `...`
-        let display_name = name.replace("NPC ", "");
-
-        // let blank_dialog_tree = "# name\n\n- ...\n"
-        //     .replace("name", &display_name)
-        //     .to_owned();
-
-        let blank_dialog_tree = format!("# {}\n\n- ...\n", &display_name);
-
-        // don't change panel.dialog_tree here
-        // it will be detect by update_dialog_panel
-        // i'm living in the fear
-        // i'm in danger
-        // my own program wants me dead
-
-        // let's overide update_dialog_tree, here and now.
-        dialog.current_node = Some(blank_dialog_tree);
-
         ev_combat_exit.send(CombatExitEvent);
 
-        // at the next enconter there will be ... as dialog
         // prevent closing the dialog_panel instant after engaging dialog
     }
 }
@@ -6,17 +6,24 @@ use bevy_tweening::Animator;
 use crate::{
     constants::ui::dialogs::*,
     ui::{
-        dialog_panel::DialogPanel,
+        dialog_box::DialogBox,
+        dialog_focus::ChoiceFocus,
+        dialog_history::DialogHistory,
+        dialog_panel::{DialogPanel, EndNodeDialogEvent},
         dialog_scroll::{PlayerChoice, PlayerScroll, Scroll, UpdateScrollEvent, UpperScroll},
-        dialog_system::init_tree_file,
+        dialog_system::{ChoiceEffectEvent, DialogType, TriggerEvent},
     },
 };
 
 /// Happens when
 ///   - ui::dialog_player::button_system
 ///     - Choice selected
+///   - ui::dialog_focus::navigate_choices
+///     - Choice selected with the keyboard or a gamepad
 ///   - ui::dialog_player::skip_forward_dialog
 ///     - P pressed
+///   - ui::dialog_timer::tick_choice_countdown
+///     - the time to pick a choice is up
 /// Read in
 ///   - ui::dialog_player::dialog_dive
 ///     - analyze the current node;
@@ -28,6 +35,8 @@ use crate::{
 pub struct DialogDiveEvent {
     pub child_index: usize,
     pub skip: bool,
+    /// The choice is picked because the time was up: the player hesitated
+    pub timed_out: bool,
 }
 
 /// Happens when
@@ -40,11 +49,15 @@ pub struct DialogDiveEvent {
 pub struct DropFirstTextUpperScroll;
 
 /// Action for each Interaction of the button
+///
+/// A hovered button takes the focus (see `ui::dialog_focus`).
 pub fn button_system(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor, &PlayerChoice, &Children),
         (Changed<Interaction>, With<Button>),
     >,
+    player_scroll_query: Query<&PlayerScroll, With<Scroll>>,
+    mut focus: ResMut<ChoiceFocus>,
 
     mut dialog_dive_event: EventWriter<DialogDiveEvent>,
     // mut text_query: Query<&mut Text>,
@@ -53,10 +66,20 @@ pub fn button_system(
         // let mut text = text_query.get_mut(children[0]).unwrap();
         match *interaction {
             Interaction::Pressed => {
-                dialog_dive_event.send(DialogDiveEvent {
-                    child_index: index.0,
-                    skip: false,
-                });
+                // some choices can be hidden, or on another page:
+                // the n-th button does not always lead to the n-th child
+                match player_scroll_query
+                    .get_single()
+                    .ok()
+                    .and_then(|player_scroll| player_scroll.shown_choice(index.0))
+                {
+                    Some((child_index, _)) => dialog_dive_event.send(DialogDiveEvent {
+                        child_index: *child_index,
+                        skip: false,
+                        timed_out: false,
+                    }),
+                    None => warn!("The button n°{} has no choice", index.0),
+                }
 
                 // text.sections[0].value = "Press".to_string();
                 *color = PRESSED_BUTTON.into();
@@ -64,10 +87,16 @@ pub fn button_system(
             Interaction::Hovered => {
                 // text.sections[0].value = "Hover".to_string();
                 *color = HOVERED_BUTTON.into();
+                focus.0 = Some(index.0);
             }
             Interaction::None => {
                 // text.sections[0].value = "Button".to_string();
-                *color = NORMAL_BUTTON.into();
+                *color = if focus.0 == Some(index.0) {
+                    HOVERED_BUTTON
+                } else {
+                    NORMAL_BUTTON
+                }
+                .into();
             }
         }
     }
@@ -79,21 +108,24 @@ pub fn button_system(
 ///
 /// check the upper scroll content
 ///
+/// - if the text is still typing
+///   - show it entirely (DialogBox::complete)
+///
 /// Only skip text
 ///
 /// - if len > 1
 ///   - pop the first elem from the vector texts
 /// - else (if) only one text remain or none
-///   - replace the current dialog node of the panel by its child (of the current)
+///   - move the cursor of the panel to the child of the current node
 pub fn skip_forward_dialog(
     query: Query<(Entity, &Animator<Style>), With<DialogPanel>>,
     keyboard_input: Res<Input<KeyCode>>,
 
+    mut dialog_box_query: Query<(&mut DialogBox, &Children), With<UpperScroll>>,
+    mut text_query: Query<&mut Text>,
+
     mut dialog_dive_event: EventWriter<DialogDiveEvent>,
 ) {
-    // TODO: feature - if the text is not entirely prompted, skip the animation (update_dialog_box)
-    // instead of skiping the text
-
     // REFACTOR: ? If Ui is open ? instead of testing this query ?
     // or just with ui_wall.finished: bool
     if let Ok((_ui_wall, animator)) = query.get_single() {
@@ -108,9 +140,20 @@ pub fn skip_forward_dialog(
         else if keyboard_input.just_pressed(KeyCode::P) {
             info!("DEBUG: P pressed");
 
+            // a text still typing is shown entirely, instead of being skipped
+            if let Ok((mut dialog_box, children)) = dialog_box_query.get_single_mut() {
+                if !dialog_box.is_finished() {
+                    if let Ok(mut text) = text_query.get_mut(children[0]) {
+                        dialog_box.complete(&mut text);
+                        return;
+                    }
+                }
+            }
+
             dialog_dive_event.send(DialogDiveEvent {
                 child_index: 0,
                 skip: true,
+                timed_out: false,
             });
         }
     }
@@ -128,6 +171,8 @@ pub fn skip_forward_dialog(
 /// Every modification of the DialogPanel's content
 /// will modify the dialog contained the concerned interlocutor
 ///
+/// Each text left and each choice picked is recorded in the DialogHistory
+///
 /// DOC: Noisy comments
 /// FIXME: Quit dialog issue
 pub fn dialog_dive(
@@ -135,65 +180,128 @@ pub fn dialog_dive(
 
     mut panel_query: Query<&mut DialogPanel, With<Animator<Style>>>,
     upper_scroll_query: Query<&mut UpperScroll, With<Scroll>>,
+    player_scroll_query: Query<&PlayerScroll, With<Scroll>>,
+    mut history: ResMut<DialogHistory>,
+    time: Res<Time>,
 
     mut drop_first_text_upper_scroll_event: EventWriter<DropFirstTextUpperScroll>,
-    // mut trigger_event: EventWriter<TriggerEvent>,
+    mut end_node_dialog_event: EventWriter<EndNodeDialogEvent>,
+    mut trigger_event: EventWriter<TriggerEvent>,
+    mut choice_effect_event: EventWriter<ChoiceEffectEvent>,
 ) {
     for event in dialog_dive_event.iter() {
         info!("DEBUG: DialogDive Event");
         let mut panel = panel_query.single_mut();
-        // let interlocutor = panel.main_interlocutor;
-
-        let dialog_panel = panel.dialog_tree.clone();
 
-        if dialog_panel.is_empty() {
-            warn!("Empty DialogTree; The Interlocutor is still in dialog but has nothing to say.");
+        let current = match panel.cursor.current(&panel.dialog_tree) {
+            Some(current) => current.clone(),
+            // The Interlocutor is still in dialog but has nothing more to say.
+            None => {
+                end_node_dialog_event.send(EndNodeDialogEvent);
+                continue;
+            }
+        };
+
+        let upper_scroll = upper_scroll_query.single();
+
+        // option 1: if it is the very last text of the dialog
+        // or
+        // option 2: if the monologue is not finished (except the last text `> 1`)
+        // then drop it
+
+        // option 2: (precision)
+        // if there is at least 2 elem in the upper scroll
+        // XXX: after selecting a choice, this test will **normally** always be ignored
+        // cause can't be in a choice phase while having text left in the UpperScroll
+        // if not, the player could choose smth for 'nothing'
+
+        // a npc's choice is already picked (ui::dialog_panel::pick_npc_choice)
+        let npc_choice = panel.npc_choice;
+        let child_index = npc_choice.unwrap_or(event.child_index);
+
+        let now = time.elapsed_seconds_f64();
+
+        if upper_scroll.texts.len() > 1 {
+            history.record(
+                current.character.clone(),
+                upper_scroll.texts[0].clone(),
+                false,
+                now,
+            );
+            drop_first_text_upper_scroll_event.send(DropFirstTextUpperScroll);
+        } else if npc_choice.is_some() || !(current.is_choice() && event.skip) {
+            // shouldn't exist : end choice (which hasn't child)
+            // so, we don't test it here
+
+            // the npc's choice is displayed in the upper scroll, the player's in their scroll
+            let said = if current.is_choice() && npc_choice.is_none() {
+                player_scroll_query
+                    .get_single()
+                    .ok()
+                    .and_then(|player_scroll| {
+                        player_scroll
+                            .choices
+                            .iter()
+                            .find(|(index, _)| *index == child_index)
+                            .map(|(_, text)| text.clone())
+                    })
+            } else {
+                upper_scroll.texts.first().cloned()
+            };
+            if let Some(text) = said {
+                history.record(current.character.clone(), text, current.is_choice(), now);
+            }
 
-            // force the chnage detection
-            panel.dialog_tree.clear();
-            warn!("DEBUG:force clear dialog panel");
-        } else {
-            let dialog_tree = init_tree_file(dialog_panel.to_owned());
-            let upper_scroll = upper_scroll_query.single();
-
-            // option 1: if it is the very last text of the dialog
-            // or
-            // option 2: if the monologue is not finished (except the last text `> 1`)
-            // then drop it
-
-            // option 2: (precision)
-            // if there is at least 2 elem in the upper scroll
-            // XXX: after selecting a choice, this test will **normally** always be ignored
-            // cause can't be in a choice phase while having text left in the UpperScroll
-            // if not, the player could choose smth for 'nothing'
-
-            if upper_scroll.texts.len() > 1 {
-                drop_first_text_upper_scroll_event.send(DropFirstTextUpperScroll);
-            } else if !(dialog_tree.borrow().is_choice() && event.skip) {
-                // shouldn't exist : end choice (which hasn't child)
-                // so, we don't test it here
-
-                // REFACTOR: Check if the Trigger event field is not empty before sending anything
-                // trigger_event.send(TriggerEvent(dialog_tree.borrow().trigger_event.clone()));
-
-                if dialog_tree.borrow().is_end_node() {
-                    // will be handle by the update_dialog_panel system
-                    // as Exit the Combat
-
-                    panel.dialog_tree.clear();
-                    info!("clear dialog panel");
-                } else {
-                    // go down on the first child
-                    // DOC: Specifics Rules link
-                    // ignore the other child if there is one
-                    // **the rule implied not**
-                    // cause a text must have one child or none
+            // the node is left: throw its trigger events (handled by ui::dialog_trigger)
+            if !current.trigger_event.is_empty() {
+                trigger_event.send(TriggerEvent {
+                    interlocutor: panel.main_interlocutor,
+                    triggers: current.trigger_event.clone(),
+                });
+            }
 
-                    let child = dialog_tree.borrow().children[event.child_index]
-                        .borrow()
-                        .print_file();
+            // the choice picked by the player applies its effects to them (handled by combat),
+            // a npc's choice does not
+            if let Some(DialogType::Choice { effects, .. }) = current.dialog_type.get(child_index) {
+                if npc_choice.is_none() && !effects.is_empty() {
+                    choice_effect_event.send(ChoiceEffectEvent {
+                        effects: effects.clone(),
+                    });
+                }
+            }
 
-                    panel.dialog_tree = child;
+            // the player's hesitation lasts until they pick their next choice
+            if current.is_choice() && npc_choice.is_none() {
+                panel.cursor.hesitated = event.timed_out;
+            }
+            panel.npc_choice = None;
+
+            if current.is_end_node() {
+                // will be handle by the end_node_dialog system
+                // as Exit the Combat
+
+                panel.cursor.end();
+                end_node_dialog_event.send(EndNodeDialogEvent);
+                info!("end dialog panel");
+            } else {
+                // go down on the first child
+                // DOC: Specifics Rules link
+                // ignore the other child if there is one
+                // **the rule implied not**
+                // cause a text must have one child or none
+
+                let DialogPanel {
+                    dialog_tree,
+                    cursor,
+                    ..
+                } = &mut *panel;
+                // a choice without child ends the dialog (see `ui::dialog_export`)
+                if cursor.dive(dialog_tree, child_index).is_none() {
+                    info!(
+                        "The choice n°{} has no child, the dialog is ended",
+                        child_index
+                    );
+                    end_node_dialog_event.send(EndNodeDialogEvent);
                 }
             }
         }
@@ -203,7 +311,8 @@ pub fn dialog_dive(
 /// Disables empty button,
 /// (hidden == disable)
 ///
-/// Prevents checking a index in the choices list.
+/// Prevents checking a index in the choices list,
+/// the last page can hold fewer choices than buttons.
 pub fn hide_empty_button(
     mut button_query: Query<(Entity, &mut Visibility, &PlayerChoice), With<Button>>,
 
@@ -221,9 +330,8 @@ pub fn hide_empty_button(
                 Ok((_, mut visibility, player_choice)) => {
                     // REFACTOR: just deref it
                     let choice_index = player_choice.0;
-                    let choices = player_scroll.choices.clone();
 
-                    *visibility = if choice_index < choices.len() {
+                    *visibility = if choice_index < player_scroll.page_choices().len() {
                         Visibility::Inherited
                     } else {
                         Visibility::Hidden
@@ -240,28 +348,6 @@ pub fn hide_empty_button(
     }
 }
 
-// /// DOC
-// /// TODO: feat - Add triggerEvent (and add to the app)
-// ///
-// /// Options
-// ///
-// /// - Match the enum into handle it direclty
-// /// - Match the enum into throw the correct event
-// pub fn throw_trigger_event(mut trigger_event: EventReader<TriggerEvent>) {
-//     for TriggerEvent(triggers) in trigger_event.iter() {
-//         for event_to_exec in triggers.iter() {
-//             match event_to_exec {
-//                 ThrowableEvent::FightEvent => {
-//                     info!("Fight Event")
-//                 }
-//                 ThrowableEvent::HasFriend => {
-//                     info!("Has Friend Event")
-//                 }
-//             }
-//         }
-//     }
-// }
-
 /// Drops first text from the UpperScroll
 pub fn drop_first_text_upper_scroll(
     mut drop_event: EventReader<DropFirstTextUpperScroll>,
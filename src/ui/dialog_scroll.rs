@@ -3,7 +3,8 @@
 use bevy::prelude::*;
 
 use crate::{
-    constants::ui::dialogs::SCROLL_ANIMATION_FRAMES_NUMBER, ui::dialog_box::ResetDialogBoxEvent,
+    constants::ui::dialogs::{CHOICES_PER_PAGE, SCROLL_ANIMATION_FRAMES_NUMBER},
+    ui::{dialog_box::ResetDialogBoxEvent, dialog_markup::strip_markup},
 };
 
 use super::dialog_panel::DialogPanelResources;
@@ -37,15 +38,81 @@ pub struct UpperScroll {
 }
 
 /// Saves all choice we could have to display
+///
+/// Each choice is saved with the index of the child it leads to,
+/// as some choices of the current node can be hidden.
+///
+/// The choices are shown by pages of `CHOICES_PER_PAGE`, one per PlayerChoice button.
 #[derive(Component, Reflect)]
 pub struct PlayerScroll {
-    pub choices: Vec<String>,
+    pub choices: Vec<(usize, String)>,
+    /// The index of the page shown
+    pub page: usize,
+}
+
+impl PlayerScroll {
+    /// Replaces the choices, and goes back to the first page.
+    pub fn set_choices(&mut self, choices: Vec<(usize, String)>) {
+        self.choices = choices;
+        self.page = 0;
+    }
+
+    /// The number of pages (at least one, even empty)
+    pub fn page_count(&self) -> usize {
+        self.choices.len().div_ceil(CHOICES_PER_PAGE).max(1)
+    }
+
+    /// The choices of the page shown, in order
+    pub fn page_choices(&self) -> &[(usize, String)] {
+        let start = (self.page * CHOICES_PER_PAGE).min(self.choices.len());
+        let end = (start + CHOICES_PER_PAGE).min(self.choices.len());
+        &self.choices[start..end]
+    }
+
+    /// The choice displayed by the PlayerChoice button at this index
+    pub fn shown_choice(&self, button: usize) -> Option<&(usize, String)> {
+        self.page_choices().get(button)
+    }
+
+    /// Moves `step` pages forward (or backward), within the existing pages.
+    ///
+    /// # Return
+    ///
+    /// true if the page shown has changed
+    pub fn turn_page(&mut self, step: isize) -> bool {
+        let page = self
+            .page
+            .saturating_add_signed(step)
+            .min(self.page_count() - 1);
+        let turned = page != self.page;
+        self.page = page;
+        turned
+    }
 }
 
 /// Represents all button which may contain choice for the player to made
+///
+/// Its index is the place of the choice displayed in the page shown.
 #[derive(Component)]
 pub struct PlayerChoice(pub usize);
 
+/// The buttons turning the pages of the PlayerScroll
+#[derive(Component, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ChoicePageButton {
+    Previous,
+    Next,
+}
+
+impl ChoicePageButton {
+    /// The pages turned by this button
+    pub fn step(&self) -> isize {
+        match self {
+            ChoicePageButton::Previous => -1,
+            ChoicePageButton::Next => 1,
+        }
+    }
+}
+
 /// Happens when
 ///   - ui::dialog_panel::create_dialog_panel
 ///     - UI Wall creation
@@ -58,8 +125,9 @@ pub struct PlayerChoice(pub usize);
 ///   - ui::dialog_panel::update_upper_scroll
 ///     - create a dialogBox with the text contained in the UpperScroll,
 ///     or update Text in existing dialogBox.
-///   - ui::dialog_panel::update_player_scroll
-///     - update choices displayed in the player scroll.
+///
+/// The choices displayed in the player scroll are updated as soon as it changes,
+/// see `update_player_scroll`.
 #[derive(Event)]
 pub struct UpdateScrollEvent;
 
@@ -144,44 +212,127 @@ pub fn update_upper_scroll(
 /// Player scroll can contain multiple choice
 /// that will be displayed at the same time.
 ///
-/// For each choice, resets the DialogBox associated with its index
+/// For each choice of the page shown, resets the DialogBox associated with its place,
+/// with the choice stripped of its markup.
+///
+/// Happens when the choices or the page of the PlayerScroll change.
 pub fn update_player_scroll(
-    mut scroll_event: EventReader<UpdateScrollEvent>,
-
-    player_scroll_query: Query<(&PlayerScroll, &Children, Entity), With<Scroll>>,
+    player_scroll_query: Query<
+        (&PlayerScroll, &Children, Entity),
+        (Changed<PlayerScroll>, With<Scroll>),
+    >,
 
     mut reset_event: EventWriter<ResetDialogBoxEvent>,
 ) {
-    for _ev in scroll_event.iter() {
-        info!("- Player - Scroll Event !");
-
-        match player_scroll_query.get_single() {
-            Err(e) => warn!("{}", e),
-            Ok((player_scroll, scroll_children, _player_scroll_entity)) => {
-                let mut place = 0;
-
-                // REFACTOR: every 3 choices create a page and start again from the 1st child
-                for choice in &player_scroll.choices {
-                    // FIXME: CRASH HERE OutOfBound if place > 3 (view the refactor above)
-                    if place > 3 {
-                        continue;
-                        // place = place + 1;
-                    }
+    for (player_scroll, scroll_children, _player_scroll_entity) in player_scroll_query.iter() {
+        info!("- Player - Scroll Update !");
 
-                    // The button's visibility is based on the size
-                    // of the vector: player_scroll.choices
-                    reset_event.send(ResetDialogBoxEvent {
-                        dialog_box: scroll_children[place],
-                        text: choice.to_owned(),
-                    });
+        // The button's visibility is based on the size
+        // of the page: ui::dialog_player::hide_empty_button
+        for (place, (_child_index, choice)) in player_scroll.page_choices().iter().enumerate() {
+            match scroll_children.get(place) {
+                Some(button) => reset_event.send(ResetDialogBoxEvent {
+                    dialog_box: *button,
+                    // read again by the DialogBox: `[[` keeps a simple `[`
+                    text: strip_markup(choice).replace('[', "[["),
+                }),
+                None => warn!("The player scroll has no button n°{}", place),
+            }
+        }
+        info!(
+            "DEBUG: player scroll gain {} choice-s, page {}/{}",
+            player_scroll.choices.len(),
+            player_scroll.page + 1,
+            player_scroll.page_count()
+        );
+    }
+}
 
-                    place = place + 1;
-                }
-                info!("DEBUG: player scroll gain {} choice-s", place);
+/// Turns the page of the PlayerScroll when a ChoicePageButton is pressed.
+pub fn turn_choice_page(
+    interaction_query: Query<(&Interaction, &ChoicePageButton), Changed<Interaction>>,
+    mut player_scroll_query: Query<&mut PlayerScroll, With<Scroll>>,
+) {
+    for (interaction, page_button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Ok(mut player_scroll) = player_scroll_query.get_single_mut() {
+            // the choices are only displayed again if the page is turned
+            if player_scroll
+                .bypass_change_detection()
+                .turn_page(page_button.step())
+            {
+                player_scroll.set_changed();
             }
         }
-        // if no choice
-        // info!("empty player scroll");
-        // send event to close (reverse open) upper scroll ?
+    }
+}
+
+/// Shows a ChoicePageButton only if there is a page to turn to.
+pub fn update_choice_page_buttons(
+    player_scroll_query: Query<&PlayerScroll, (Changed<PlayerScroll>, With<Scroll>)>,
+    mut page_button_query: Query<(&ChoicePageButton, &mut Visibility)>,
+) {
+    for player_scroll in player_scroll_query.iter() {
+        for (page_button, mut visibility) in page_button_query.iter_mut() {
+            let shown = match page_button {
+                ChoicePageButton::Previous => player_scroll.page > 0,
+                ChoicePageButton::Next => player_scroll.page + 1 < player_scroll.page_count(),
+            };
+            *visibility = if shown {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player_scroll(choices: usize) -> PlayerScroll {
+        PlayerScroll {
+            choices: (0..choices).map(|i| (i, format!("choice {}", i))).collect(),
+            page: 0,
+        }
+    }
+
+    #[test]
+    fn test_choices_are_split_in_pages() {
+        let mut scroll = player_scroll(CHOICES_PER_PAGE + 2);
+
+        assert_eq!(scroll.page_count(), 2);
+        assert_eq!(scroll.page_choices().len(), CHOICES_PER_PAGE);
+
+        assert!(scroll.turn_page(1));
+        assert_eq!(scroll.page_choices().len(), 2);
+        assert_eq!(
+            scroll.shown_choice(0),
+            Some(&scroll.choices[CHOICES_PER_PAGE])
+        );
+        assert_eq!(scroll.shown_choice(2), None);
+
+        // no page after the last one, nor before the first one
+        assert!(!scroll.turn_page(1));
+        assert!(scroll.turn_page(-1));
+        assert!(!scroll.turn_page(-1));
+        assert_eq!(scroll.page, 0);
+    }
+
+    #[test]
+    fn test_new_choices_are_shown_from_the_first_page() {
+        let mut scroll = player_scroll(CHOICES_PER_PAGE * 2);
+        scroll.turn_page(1);
+
+        scroll.set_choices(vec![(3, "Hey".to_string())]);
+        assert_eq!(scroll.page, 0);
+        assert_eq!(scroll.page_count(), 1);
+
+        scroll.set_choices(Vec::new());
+        assert_eq!(scroll.page_count(), 1);
+        assert!(scroll.page_choices().is_empty());
     }
 }
@@ -0,0 +1,161 @@
+//! Dialog Speakers
+//!
+//! The authors of a dialog file (`# Olf`) are only names.
+//! While talking, they are resolved against the `Name` of the entities taking part in the talk,
+//! in this order:
+//!
+//! - the main interlocutor
+//! - the player, who always says the lines of the MC (`# Morgan`)
+//! - the recruited party
+//! - any npc nearby (within `SPEAKER_RANGE` of the player)
+//!
+//! So Olf, Hugo and the Admiral can all speak in the same dialog.
+//! An author found nowhere is said by the main interlocutor:
+//! the Fabicurions (`Fabicurion 0`, `Fabicurion 1`, ...) all say the lines of `# Fabien`.
+//! The panel displays the name of the current speaker, and their Portrait if they have one.
+
+use bevy::prelude::*;
+
+use crate::{
+    combat::Recruted,
+    constants::{character::player::MC_NAME, ui::dialogs::SPEAKER_RANGE},
+    npc::NPC,
+    player::Player,
+    ui::dialog_panel::DialogPanel,
+};
+
+/// The face displayed in the dialog panel, when this entity is talking.
+#[derive(Component, Reflect)]
+pub struct Portrait(pub Handle<Image>);
+
+/// The entity saying the current node of the DialogPanel.
+///
+/// None for the Narator.
+#[derive(Component, Reflect, Default)]
+pub struct Speaker(pub Option<Entity>);
+
+/// The text displaying the name of the current speaker
+#[derive(Component)]
+pub struct SpeakerName;
+
+/// The image displaying the portrait of the current speaker
+#[derive(Component)]
+pub struct SpeakerPortrait;
+
+/// # Return
+///
+/// The first candidate named `author`.
+pub fn resolve_speaker<'a>(
+    author: &str,
+    candidates: impl IntoIterator<Item = (Entity, &'a Name)>,
+) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .find(|(_, name)| name.as_str() == author)
+        .map(|(entity, _)| entity)
+}
+
+/// Resolves the author of the current node, each time the DialogPanel changes,
+/// and displays their name and portrait.
+///
+/// An ended dialog is said by the main interlocutor (`...`).
+pub fn update_speaker(
+    mut panel_query: Query<(&DialogPanel, &mut Speaker), Changed<DialogPanel>>,
+
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    party_query: Query<Entity, (With<NPC>, With<Recruted>)>,
+    nearby_query: Query<(Entity, &GlobalTransform), Or<(With<NPC>, With<Player>)>>,
+    names_query: Query<&Name>,
+    portrait_query: Query<&Portrait>,
+
+    mut speaker_name_query: Query<&mut Text, With<SpeakerName>>,
+    mut speaker_portrait_query: Query<(&mut UiImage, &mut Visibility), With<SpeakerPortrait>>,
+) {
+    for (panel, mut speaker) in panel_query.iter_mut() {
+        let author = match panel.cursor.current(&panel.dialog_tree) {
+            Some(current) => current.character.clone(),
+            None => names_query
+                .get(panel.main_interlocutor)
+                .ok()
+                .map(|name| name.to_string()),
+        };
+
+        let mut participants = vec![panel.main_interlocutor];
+        if let Ok((player, player_transform)) = player_query.get_single() {
+            participants.push(player);
+            participants.extend(party_query.iter());
+            participants.extend(
+                nearby_query
+                    .iter()
+                    .filter(|(_, transform)| {
+                        transform
+                            .translation()
+                            .truncate()
+                            .distance(player_transform.translation().truncate())
+                            <= SPEAKER_RANGE
+                    })
+                    .map(|(entity, _)| entity),
+            );
+        }
+
+        let candidates = participants
+            .iter()
+            .filter_map(|entity| Some((*entity, names_query.get(*entity).ok()?)));
+        speaker.0 = match author.as_deref() {
+            Some(MC_NAME) => player_query.get_single().ok().map(|(player, _)| player),
+            Some(author) => match resolve_speaker(author, candidates) {
+                Some(entity) => Some(entity),
+                None => {
+                    info!(
+                        "Nobody around is named {}, the main interlocutor says it",
+                        author
+                    );
+                    Some(panel.main_interlocutor)
+                }
+            },
+            None => None,
+        };
+
+        for mut text in speaker_name_query.iter_mut() {
+            text.sections[0].value = author.clone().unwrap_or_default();
+        }
+
+        let portrait = speaker.0.and_then(|entity| portrait_query.get(entity).ok());
+        for (mut image, mut visibility) in speaker_portrait_query.iter_mut() {
+            match portrait {
+                Some(Portrait(handle)) => {
+                    image.texture = handle.clone();
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_speaker_in_order() {
+        let olf = Name::new("Olf");
+        let hugo = Name::new("Hugo");
+        let other_hugo = Name::new("Hugo");
+        let candidates = [
+            (Entity::from_raw(0), &olf),
+            (Entity::from_raw(1), &hugo),
+            (Entity::from_raw(2), &other_hugo),
+        ];
+
+        assert_eq!(
+            resolve_speaker("Olf", candidates),
+            Some(Entity::from_raw(0))
+        );
+        assert_eq!(
+            resolve_speaker("Hugo", candidates),
+            Some(Entity::from_raw(1))
+        );
+        assert_eq!(resolve_speaker("Admiral", candidates), None);
+    }
+}
@@ -10,67 +10,81 @@
 //!     - Karma based
 //!     - Event based
 //!     - Choice based
+//!     - a boolean expression of them (`ui::dialog_condition`)
 //!   - A node can send Specific Event
 //!
-//! Tree structure based on https://applied-math-coding.medium.com/a-tree-structure-implemented-in-rust-8344783abd75
+//! - A DialogGraph holds all the DialogNodes of a dialog (an arena)
+//!   - each node is pointed by its NodeId
+//!   - a DialogCursor walks through it
+//!   - a node can jump to any anchored node (`=> ask_again`):
+//!     the dialogs can loop and converge
+//!
+//! Every piece of the graph can be cloned, compared, reflected and (de)serialized.
 
 // use bevy::prelude::*;
 
-use std::{cell::RefCell, fmt, rc::Rc, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
 
-use bevy::prelude::{info, warn, Component};
+use bevy::prelude::{info, warn, Component, Entity, Handle, Reflect};
 use bevy_ecs::event::Event;
-
-use crate::constants::character::{KARMA_MAX, KARMA_MIN};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{
+        character::{KARMA_MAX, KARMA_MIN},
+        combat::team::{TEAM_FABICURION, TEAM_MC, TEAM_OLF},
+        ui::dialogs::NARATOR,
+    },
+    ui::{
+        dialog_asset::DialogAsset,
+        dialog_condition::{Comparison, ConditionContext, ConditionExpr, Operand},
+    },
+    world_flags::GameEvent,
+};
 
 // mod tests;
 
 /// Points to the current DialogNode the npc is in.
 ///
-/// Holds a String which can be converted to a Rc<RefCell<DialogNode>>
-/// by print_file()
+/// The `source` is the dialog file holding the whole DialogGraph,
+/// which is never modified by the progress in the dialog.
+/// The `cursor` keeps track of this progress:
+/// the current node and the branch taken to reach it.
 ///
 /// # Example
 ///
 /// ```rust
 /// Dialog {
-///      current_node: Some(
-/// "# Fabien
-///
-/// - Hello
-///
-/// ## Morgan
-///
-/// - Hey | None
-/// - No Hello | None
-/// - Want to share a flat ? | None
-///
-/// ### Fabien
-///
-/// - :)
-///
-/// ### Fabien
-///
-/// - :O
-///
-/// ### Fabien
-///
-/// - Sure"
-/// .to_string())
+///     source: asset_server.load("dialogs/fabien.dialog.md"),
+///     cursor: DialogCursor::new(),
 /// }
-///
 /// ```
 #[derive(Component, PartialEq, Clone, Debug)]
 pub struct Dialog {
-    pub current_node: Option<String>,
+    pub source: Handle<DialogAsset>,
+    pub cursor: DialogCursor,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum DialogType {
     Text(String),
     Choice {
         text: String,
         condition: Option<DialogCondition>,
+        /// Applied to the player when the choice is picked
+        effects: Vec<ChoiceEffect>,
+        /// Only read when a npc picks its choice:
+        /// the verified choices with the highest priority take over the others
+        priority: i32,
+        /// Only read when a npc picks its choice:
+        /// the odds of a choice among the ones with the same priority (at least 1)
+        weight: u32,
     },
 }
 
@@ -83,6 +97,9 @@ impl DialogType {
         DialogType::Choice {
             text: String::from(""),
             condition: None,
+            effects: vec![],
+            priority: 0,
+            weight: 1,
         }
     }
 
@@ -95,10 +112,16 @@ impl DialogType {
                 DialogType::Choice {
                     text: _text1,
                     condition: _cond1,
+                    effects: _effects1,
+                    priority: _priority1,
+                    weight: _weight1,
                 },
                 DialogType::Choice {
                     text: _text2,
                     condition: _cond2,
+                    effects: _effects2,
+                    priority: _priority2,
+                    weight: _weight2,
                 },
             ) => return true,
             _ => return false,
@@ -110,6 +133,9 @@ impl DialogType {
             DialogType::Choice {
                 text: _text,
                 condition: _cond,
+                effects: _effects,
+                priority: _priority,
+                weight: _weight,
             } => return true,
 
             DialogType::Text(_) => return false,
@@ -124,63 +150,71 @@ impl DialogType {
     }
 }
 
-// TODO: MOVE IT UP
-#[derive(PartialEq, Clone, Copy, Debug)]
-pub enum GameEvent {
-    BeatTheGame,
-    FirstKill,
-    AreaCleared,
-    HasCharisma,
-    HasFriend,
-}
-
-impl fmt::Display for GameEvent {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            GameEvent::BeatTheGame => write!(f, "BeatTheGame"),
-            GameEvent::FirstKill => write!(f, "FirstKill"),
-            GameEvent::AreaCleared => write!(f, "AreaCleared"),
-            GameEvent::HasCharisma => write!(f, "HasCharisma"),
-            GameEvent::HasFriend => write!(f, "HasFriend"),
-        }
-    }
-}
-
-impl FromStr for GameEvent {
-    type Err = (); // ParseIntError;
-
-    fn from_str(input: &str) -> Result<GameEvent, Self::Err> {
-        match input {
-            "BeatTheGame" => Ok(GameEvent::BeatTheGame),
-            "FirstKill" => Ok(GameEvent::FirstKill),
-            "AreaCleared" => Ok(GameEvent::AreaCleared),
-            "HasCharisma" => Ok(GameEvent::HasCharisma),
-            "HasFriend" => Ok(GameEvent::HasFriend),
-            _ => Err(()),
-        }
-    }
+/// Happens in
+///   - ui::dialog_player
+///     - dialog_dive
+///     Exit a node which holds some trigger events
+/// Read in
+///   - ui::dialog_trigger
+///     - throw_trigger_event
+///     Run the handler registered for each ThrowableEvent
+#[derive(Event)]
+pub struct TriggerEvent {
+    /// The entity the player is talking to
+    pub interlocutor: Entity,
+    pub triggers: Vec<ThrowableEvent>,
 }
 
 /// Happens in
 ///   - ui::dialog_player
 ///     - dialog_dive
-///     Exit a node
+///     The player picks a choice which holds some effects (never sent for a npc's choice)
 /// Read in
-///   - ui::dialog_player
-///     - throw_trigger_event
-///     Match the Enum and handle it
-///     REFACTOR: or/and TriggerEvent Handle by sending these real Event
+///   - combat
+///     - apply_choice_effects
+///     Change the player's stats
 #[derive(Event)]
-pub struct TriggerEvent(pub Vec<ThrowableEvent>);
+pub struct ChoiceEffectEvent {
+    pub effects: Vec<ChoiceEffect>,
+}
 
-/// DOC
-///
 /// List all triggerable event,
 /// that can be send when quitting a dialog node
-#[derive(PartialEq, Clone, Copy, Debug)]
+///
+/// Written `-> FightEvent, Karma(+5), GiveItem(potion, 2), JoinTeam(TEAM_MC)`
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum ThrowableEvent {
+    /// The interlocutor fights the player
     FightEvent,
+    /// The interlocutor joins the player's team
     HasFriend,
+    /// `Karma(+5)`: the player's karma changes by this delta
+    Karma(i32),
+    /// `GiveItem(potion, 2)`: the player receives this amount of the item,
+    /// `,` and parentheses are escaped in its name: `GiveItem(salt/, pepper, 1)`
+    GiveItem { item: String, amount: u32 },
+    /// `JoinTeam(TEAM_MC)`: the interlocutor joins this team
+    JoinTeam(i32),
+}
+
+impl ThrowableEvent {
+    pub const FIGHT_EVENT: &'static str = "FightEvent";
+    pub const HAS_FRIEND: &'static str = "HasFriend";
+    pub const KARMA: &'static str = "Karma";
+    pub const GIVE_ITEM: &'static str = "GiveItem";
+    pub const JOIN_TEAM: &'static str = "JoinTeam";
+
+    /// The name under which the handler of this event
+    /// is registered in the TriggerRegistry: `ThrowableEvent::KARMA`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ThrowableEvent::FightEvent => ThrowableEvent::FIGHT_EVENT,
+            ThrowableEvent::HasFriend => ThrowableEvent::HAS_FRIEND,
+            ThrowableEvent::Karma(_) => ThrowableEvent::KARMA,
+            ThrowableEvent::GiveItem { .. } => ThrowableEvent::GIVE_ITEM,
+            ThrowableEvent::JoinTeam(_) => ThrowableEvent::JOIN_TEAM,
+        }
+    }
 }
 
 impl fmt::Display for ThrowableEvent {
@@ -188,23 +222,47 @@ impl fmt::Display for ThrowableEvent {
         match self {
             ThrowableEvent::FightEvent => write!(f, "FightEvent"),
             ThrowableEvent::HasFriend => write!(f, "HasFriend"),
+            ThrowableEvent::Karma(delta) => write!(f, "Karma({:+})", delta),
+            ThrowableEvent::GiveItem { item, amount } => {
+                write!(f, "GiveItem({}, {})", escape_argument(item), amount)
+            }
+            ThrowableEvent::JoinTeam(team) => match team_name(*team) {
+                Some(name) => write!(f, "JoinTeam({})", name),
+                None => write!(f, "JoinTeam({})", team),
+            },
         }
     }
 }
 
 impl FromStr for ThrowableEvent {
-    type Err = (); // ParseIntError;
+    type Err = ();
 
     fn from_str(input: &str) -> Result<ThrowableEvent, Self::Err> {
-        match input {
-            "FightEvent" => Ok(ThrowableEvent::FightEvent),
-            "HasFriend" => Ok(ThrowableEvent::HasFriend),
-            _ => Err(()),
+        let line = split_lines(input).concat();
+        let trigger = trim(&line);
+        if trigger.is_empty() {
+            return Err(());
         }
+
+        parse_trigger(trigger).map_err(|_| ())
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+/// The teams a dialog can refer to by their name.
+const TEAMS: [(&str, i32); 3] = [
+    ("TEAM_MC", TEAM_MC),
+    ("TEAM_OLF", TEAM_OLF),
+    ("TEAM_FABICURION", TEAM_FABICURION),
+];
+
+fn team_name(team: i32) -> Option<&'static str> {
+    TEAMS
+        .iter()
+        .find(|(_, id)| *id == team)
+        .map(|(name, _)| *name)
+}
+
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct DialogCondition {
     /// `(0,0) = infinite / no threshold`
     ///
@@ -212,6 +270,9 @@ pub struct DialogCondition {
     /// will always be prompted
     karma_threshold: Option<(i32, i32)>,
     event: Option<Vec<GameEvent>>,
+    /// `karma >= 10 && !event: FirstKill;`, see `ui::dialog_condition`
+    #[reflect(ignore)]
+    expression: Option<ConditionExpr>,
 }
 
 impl DialogCondition {
@@ -219,15 +280,40 @@ impl DialogCondition {
         DialogCondition {
             karma_threshold: None,
             event: None,
+            expression: None,
         }
     }
 
-    pub fn is_verified(&self, karma: i32) -> bool {
-        // TODO: feature - also check if its event has been already triggered in the game
+    /// `(min, max)`, None if the karma does not matter
+    pub fn karma_threshold(&self) -> Option<(i32, i32)> {
+        self.karma_threshold
+    }
+
+    pub fn expression(&self) -> Option<&ConditionExpr> {
+        self.expression.as_ref()
+    }
+
+    /// # Return
+    ///
+    /// true if the karma of the `context` is within the threshold,
+    /// all the required events have already happened in the game
+    /// and the expression holds
+    pub fn is_verified(&self, context: &ConditionContext) -> bool {
+        if let Some(events) = &self.event {
+            if !context.world_flags.has_all(events) {
+                return false;
+            }
+        }
+
+        if let Some(expression) = &self.expression {
+            if !expression.evaluate(context) {
+                return false;
+            }
+        }
 
         match self.karma_threshold {
             Some(karma_threshold) => {
-                if karma >= karma_threshold.0 && karma <= karma_threshold.1 {
+                if context.karma >= karma_threshold.0 && context.karma <= karma_threshold.1 {
                     return true;
                 }
             }
@@ -239,14 +325,175 @@ impl DialogCondition {
     }
 }
 
-/// Points to the first DialogNode
-/// Used to be linked with an entity
-#[derive(PartialEq, Clone, Debug)]
-pub struct DialogTree {
-    pub current: Option<DialogNode>,
+/// Written as in a dialog file: `karma: -50,0; event: OlfIsGone, PatTheDog;`
+impl fmt::Display for DialogCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut clauses = Vec::new();
+        if let Some((min, max)) = self.karma_threshold {
+            clauses.push(format!("karma: {},{};", min, max));
+        }
+        if let Some(events) = &self.event {
+            if !events.is_empty() {
+                let events: Vec<String> = events.iter().map(|event| event.to_string()).collect();
+                clauses.push(format!("event: {};", events.join(", ")));
+            }
+        }
+        match &self.expression {
+            // `event: FirstKill;` alone would be read back as an event clause
+            Some(expression @ ConditionExpr::Event(_)) => {
+                clauses.push(format!("({});", expression))
+            }
+            Some(expression) => clauses.push(format!("{};", expression)),
+            None => {}
+        }
+
+        write!(f, "{}", clauses.join(" "))
+    }
+}
+
+/// Writes the condition slot of a choice, as in a dialog file:
+/// `karma: -50,0; priority: 2; weight: 3;`, or `None` if it has no clause.
+pub fn write_condition(condition: &Option<DialogCondition>, priority: i32, weight: u32) -> String {
+    let mut clauses = Vec::new();
+    if let Some(condition) = condition {
+        clauses.push(condition.to_string());
+    }
+    if priority != 0 {
+        clauses.push(format!("priority: {};", priority));
+    }
+    if weight != 1 {
+        clauses.push(format!("weight: {};", weight));
+    }
+
+    if clauses.is_empty() {
+        String::from("None")
+    } else {
+        clauses.join(" ")
+    }
+}
+
+/// What picking a choice does to the player: `karma: -10;`
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ChoiceEffect {
+    /// `karma: -10;`: kept within KARMA_MIN..KARMA_MAX
+    Karma(i32),
+    /// `hp: +5;`: kept within 0..max_hp
+    Hp(i32),
+    /// `mana: -5;`: kept within 0..max_mana
+    Mana(i32),
+}
+
+/// Written as in a dialog file: `karma: -10;`
+impl fmt::Display for ChoiceEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChoiceEffect::Karma(delta) => write!(f, "karma: {:+};", delta),
+            ChoiceEffect::Hp(delta) => write!(f, "hp: {:+};", delta),
+            ChoiceEffect::Mana(delta) => write!(f, "mana: {:+};", delta),
+        }
+    }
+}
+
+/// The variables a dialog text can refer to: `{player.name}`.
+pub const DIALOG_VARIABLES: [&str; 4] = ["player.name", "karma", "party.size", "npc.name"];
+
+/// The state of the game, read to fill the variables of a dialog text.
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct DialogContext {
+    pub player_name: String,
+    pub karma: i32,
+    /// The player and all their recruits
+    pub party_size: usize,
+    /// The interlocutor
+    pub npc_name: String,
+}
+
+impl DialogContext {
+    /// # Return
+    ///
+    /// None if `variable` is not one of the DIALOG_VARIABLES
+    pub fn get(&self, variable: &str) -> Option<String> {
+        match variable {
+            "player.name" => Some(self.player_name.clone()),
+            "karma" => Some(self.karma.to_string()),
+            "party.size" => Some(self.party_size.to_string()),
+            "npc.name" => Some(self.npc_name.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Replaces each `{variable}` of a dialog text by its value in the `context`.
+///
+/// Any other brace is part of the text: `parse_dialog` only keeps the escaped ones (`/{`).
+pub fn interpolate(text: &str, context: &DialogContext) -> String {
+    let mut res = String::new();
+    let mut rest = text;
+
+    while let Some(open) = rest.find('{') {
+        res.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after
+            .find('}')
+            .and_then(|close| Some((close, context.get(&after[..close])?)))
+        {
+            Some((close, value)) => {
+                res.push_str(&value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                res.push('{');
+                rest = after;
+            }
+        }
+    }
+    res.push_str(rest);
+
+    res
+}
+
+/// # Return
+///
+/// The variable starting at the brace `chars[open]`: `{karma}`, None if it is a simple brace
+fn variable_at(chars: &[char], open: usize) -> Option<&'static str> {
+    let close = open + chars[open..].iter().position(|c| *c == '}')?;
+    let variable: String = chars[open + 1..close].iter().collect();
+    DIALOG_VARIABLES
+        .iter()
+        .find(|known| **known == variable)
+        .copied()
+}
+
+/// Points to a DialogNode within its DialogGraph.
+#[derive(
+    Reflect, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Debug,
+)]
+pub struct NodeId(pub usize);
+
+impl NodeId {
+    /// The first DialogNode of any DialogGraph.
+    pub const ROOT: NodeId = NodeId(0);
+}
+
+/// The time left to the player to pick a choice: `## Morgan [timeout=5s, default=2]`
+///
+/// When it is up, the `default` choice is picked for them (see `ui::dialog_timer`).
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+pub struct ChoiceTimeout {
+    /// In seconds
+    pub seconds: f32,
+    /// The index of the choice picked when the time is up, from 0
+    pub default: usize,
+}
+
+/// Written as in a dialog file: `[timeout=5s, default=2]`
+impl fmt::Display for ChoiceTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[timeout={}s, default={}]", self.seconds, self.default)
+    }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct DialogNode {
     /// Choice can have multiple children (to give a real impact to the choice)
     ///
@@ -264,23 +511,96 @@ pub struct DialogNode {
     ///
     /// can be an npc OR a player.
     ///
-    /// The u32 is the id of the entity,
-    /// The String is their name
-    pub character: Option<(u32, String)>,
-    pub children: Vec<Rc<RefCell<DialogNode>>>,
-    /// maybe too much (prefer a stack in the TreeIterator)
-    pub parent: Option<Rc<RefCell<DialogNode>>>,
+    /// Their name, resolved against the `Name` of the entities
+    /// taking part in the talk (see `ui::dialog_speaker`).
+    /// None for the Narator.
+    pub character: Option<String>,
+    /// The nodes written under this one,
+    /// and the anchored nodes it jumps to (`=> label`)
+    pub children: Vec<NodeId>,
+    /// The indexes of the `children` only jumped to, sorted,
+    /// the other children are written under this node
+    pub jumps: Vec<usize>,
+    /// The node this one is written under, None for the root
+    ///
+    /// A jump does not change the parent of its target.
+    pub parent: Option<NodeId>,
     pub trigger_event: Vec<ThrowableEvent>,
+    /// The anchor of this node (`{#label}`), to jump to it
+    pub label: Option<String>,
+    /// The time left to the player to pick one of these choices, None to wait forever
+    pub timeout: Option<ChoiceTimeout>,
 }
 
 impl DialogNode {
+    /// Picks the choice said by a npc.
+    ///
+    /// Among the verified choices, only the ones with the highest priority are kept,
+    /// then one of them is drawn according to its weight.
+    /// If they all weigh nothing (a graph built in code), the first one is picked.
+    ///
+    /// # Return
+    ///
+    /// The index of the picked choice (which is the index of the child it leads to),
+    /// None if this node holds no verified choice
+    pub fn pick_choice<R: Rng + ?Sized>(
+        &self,
+        context: &ConditionContext,
+        rng: &mut R,
+    ) -> Option<usize> {
+        // (child_index, priority, weight)
+        let verified: Vec<(usize, i32, u32)> = self
+            .dialog_type
+            .iter()
+            .enumerate()
+            .filter_map(|(child_index, dialog)| match dialog {
+                DialogType::Choice {
+                    condition,
+                    priority,
+                    weight,
+                    ..
+                } => match condition {
+                    Some(condition) if !condition.is_verified(context) => None,
+                    _ => Some((child_index, *priority, *weight)),
+                },
+                DialogType::Text(_) => None,
+            })
+            .collect();
+
+        let highest_priority = verified.iter().map(|(_, priority, _)| *priority).max()?;
+        let candidates: Vec<(usize, u32)> = verified
+            .into_iter()
+            .filter(|(_, priority, _)| *priority == highest_priority)
+            .map(|(child_index, _, weight)| (child_index, weight))
+            .collect();
+
+        // summed in u64: the weights of a few choices can overflow a u32
+        let total_weight: u64 = candidates.iter().map(|(_, weight)| *weight as u64).sum();
+        if total_weight == 0 {
+            return candidates.first().map(|(child_index, _)| *child_index);
+        }
+        let mut draw = rng.gen_range(0..total_weight);
+        for (child_index, weight) in candidates {
+            if draw < weight as u64 {
+                return Some(child_index);
+            }
+            draw -= weight as u64;
+        }
+
+        // the draw is always below the total weight
+        None
+    }
+
     pub fn new() -> DialogNode {
         return DialogNode {
             dialog_type: vec![],
             character: None,
             children: vec![],
+            jumps: vec![],
             parent: None,
             trigger_event: vec![],
+            label: None,
+            timeout: None,
         };
     }
 
@@ -307,9 +627,202 @@ impl DialogNode {
         }
         return false;
     }
+}
+
+/// Holds every DialogNode of a dialog, the root being at `NodeId::ROOT`.
+///
+/// A node only knows its children and its parent by their NodeId,
+/// so the whole graph can be cloned, compared, reflected and (de)serialized.
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct DialogGraph {
+    nodes: Vec<DialogNode>,
+}
+
+impl Default for DialogGraph {
+    /// A graph with a blank root.
+    fn default() -> Self {
+        DialogGraph::new(DialogNode::new())
+    }
+}
+
+impl Index<NodeId> for DialogGraph {
+    type Output = DialogNode;
+
+    fn index(&self, id: NodeId) -> &DialogNode {
+        &self.nodes[id.0]
+    }
+}
+
+impl IndexMut<NodeId> for DialogGraph {
+    fn index_mut(&mut self, id: NodeId) -> &mut DialogNode {
+        &mut self.nodes[id.0]
+    }
+}
+
+impl DialogGraph {
+    /// Creates a graph only made of its `root`.
+    ///
+    /// The links (`children`, `jumps`, `parent`) of the given `root` are dropped.
+    pub fn new(root: DialogNode) -> DialogGraph {
+        DialogGraph {
+            nodes: vec![DialogNode {
+                children: vec![],
+                jumps: vec![],
+                parent: None,
+                ..root
+            }],
+        }
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&DialogNode> {
+        self.nodes.get(id.0)
+    }
+
+    /// Adds `node` as the last child of `parent`.
+    ///
+    /// The links (`children`, `jumps`, `parent`) of the given `node` are dropped.
+    ///
+    /// # Return
+    ///
+    /// The NodeId of the new child
+    pub fn add_child(&mut self, parent: NodeId, node: DialogNode) -> NodeId {
+        let child = NodeId(self.nodes.len());
+        self.nodes.push(DialogNode {
+            children: vec![],
+            jumps: vec![],
+            parent: Some(parent),
+            ..node
+        });
+        self[parent].children.push(child);
+
+        child
+    }
+
+    /// Makes `from` lead to `to` (as its last child), without moving `to`.
+    ///
+    /// The dialog can then loop or converge.
+    pub fn add_jump(&mut self, from: NodeId, to: NodeId) {
+        let child_index = self[from].children.len();
+        self[from].children.push(to);
+        self[from].jumps.push(child_index);
+    }
+
+    /// # Return
+    ///
+    /// true if the `child_index`-th child of `parent` is not written under it,
+    /// but only jumped to.
+    pub fn is_jump(&self, parent: NodeId, child_index: usize) -> bool {
+        self[parent].jumps.contains(&child_index)
+    }
+
+    /// # Return
+    ///
+    /// The node anchored `{#label}`
+    pub fn find_label(&self, label: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.label.as_deref() == Some(label))
+            .map(NodeId)
+    }
+
+    /// The label of the node, or a generated one if it has none.
+    fn anchor(&self, id: NodeId) -> String {
+        match &self[id].label {
+            Some(label) => label.clone(),
+            None => format!("node_{}", id.0),
+        }
+    }
+
+    fn is_jumped_to(&self, id: NodeId) -> bool {
+        self.nodes
+            .iter()
+            .any(|node| node.jumps.iter().any(|jump| node.children[*jump] == id))
+    }
+
+    /// # Return
+    ///
+    /// A new DialogGraph made of the node `id` (as root) and all the nodes written under it.
+    ///
+    /// The jumps leading out of the subgraph are dropped.
+    pub fn subgraph(&self, id: NodeId) -> DialogGraph {
+        let mut written = Vec::new();
+        self.written_under(id, &mut written);
+
+        // the NodeId of each copy within the subgraph
+        let copies: HashMap<NodeId, NodeId> = written
+            .iter()
+            .enumerate()
+            .map(|(copy, id)| (*id, NodeId(copy)))
+            .collect();
+
+        DialogGraph {
+            nodes: written
+                .iter()
+                .map(|id| {
+                    // (copy, is_jump) of each child kept in the subgraph
+                    let children: Vec<(NodeId, bool)> = self[*id]
+                        .children
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(child_index, child)| {
+                            Some((*copies.get(child)?, self.is_jump(*id, child_index)))
+                        })
+                        .collect();
+
+                    DialogNode {
+                        children: children.iter().map(|(child, _)| *child).collect(),
+                        jumps: children
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, (_, is_jump))| *is_jump)
+                            .map(|(child_index, _)| child_index)
+                            .collect(),
+                        parent: self[*id]
+                            .parent
+                            .and_then(|parent| copies.get(&parent).copied()),
+                        ..self[*id].clone()
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Lists `id` and all the nodes written under it, from the top to the bottom of the file.
+    ///
+    /// Never follows a jump: stops even if the dialog loops.
+    fn written_under(&self, id: NodeId, written: &mut Vec<NodeId>) {
+        written.push(id);
+        for (child_index, child) in self[id].children.iter().enumerate() {
+            if !self.is_jump(id, child_index) {
+                self.written_under(*child, written);
+            }
+        }
+    }
 
-    pub fn add_child(&mut self, new_node: Rc<RefCell<DialogNode>>) {
-        self.children.push(new_node);
+    /// Removes these nodes, which must no longer be linked by any other node.
+    fn remove(&mut self, removed: &[NodeId]) {
+        let mut new_ids = Vec::with_capacity(self.nodes.len());
+        let mut next = 0;
+        for id in 0..self.nodes.len() {
+            if removed.contains(&NodeId(id)) {
+                new_ids.push(None);
+            } else {
+                new_ids.push(Some(NodeId(next)));
+                next += 1;
+            }
+        }
+        let new_id = |id: NodeId| new_ids[id.0].expect("a removed node is still linked");
+
+        self.nodes = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .enumerate()
+            .filter(|(id, _)| new_ids[*id].is_some())
+            .map(|(_, node)| DialogNode {
+                children: node.children.iter().map(|child| new_id(*child)).collect(),
+                parent: node.parent.map(new_id),
+                ..node
+            })
+            .collect();
     }
 
     /// # Convention
@@ -330,12 +843,16 @@ impl DialogNode {
     /// ["CP"]->["Hello"->["NiceTalk"], "No Hello"->["BadTalk"], "Give ChickenSandwich"->["WinTalk"]]
     ///
     pub fn print_flat(&self) -> String {
+        return self.print_flat_aux(NodeId::ROOT);
+    }
+
+    fn print_flat_aux(&self, id: NodeId) -> String {
         let mut res = String::from("[");
-        for dialog in &self.dialog_type {
+        for dialog in &self[id].dialog_type {
             if let DialogType::Text(text) = dialog {
                 res.push_str(&text);
                 res.push_str(", ");
-            } else if let DialogType::Choice { text, condition: _ } = dialog {
+            } else if let DialogType::Choice { text, .. } = dialog {
                 res.push_str(&text);
                 res.push_str(", ");
             }
@@ -345,10 +862,17 @@ impl DialogNode {
         res = res.replace(", ]", "]");
 
         let children = String::from("->[")
-            + &self
+            + &self[id]
                 .children
                 .iter()
-                .map(|tn| tn.borrow().print_flat())
+                .enumerate()
+                .map(|(child_index, child)| {
+                    if self.is_jump(id, child_index) {
+                        format!("[=> {}]", self.anchor(*child))
+                    } else {
+                        self.print_flat_aux(*child)
+                    }
+                })
                 .collect::<Vec<String>>()
                 .join("; ")
             + "]";
@@ -400,99 +924,226 @@ impl DialogNode {
     ///
     /// ```
     pub fn print_file(&self) -> String {
-        return self.print_file_aux(String::from("#"));
+        return self.print_file_aux(NodeId::ROOT, String::from("#"));
     }
 
-    fn print_file_aux(&self, headers: String) -> String {
-        let mut res = headers.clone();
+    fn print_file_aux(&self, id: NodeId, headers: String) -> String {
+        let node = &self[id];
+        let mut res = format!(
+            "{} {}",
+            headers,
+            escape(node.character.as_deref().unwrap_or(NARATOR))
+        );
+        if node.label.is_some() || self.is_jumped_to(id) {
+            res.push_str(&format!(" {{#{}}}", self.anchor(id)));
+        }
+        if let Some(timeout) = node.timeout {
+            res.push_str(&format!(" {}", timeout));
+        }
+        res.push_str("\n\n");
 
-        let character: String;
-        match &self.character {
-            Some((_id, name)) => character = " ".to_string() + name,
+        for dialog in &node.dialog_type {
+            match dialog {
+                DialogType::Text(text) => res.push_str(&format!("- {}\n", escape_text(text))),
+                DialogType::Choice {
+                    text,
+                    condition,
+                    effects,
+                    priority,
+                    weight,
+                } => {
+                    res.push_str(&format!(
+                        "- {} | {}",
+                        escape_text(text),
+                        write_condition(condition, *priority, *weight)
+                    ));
+
+                    if !effects.is_empty() {
+                        let effects: Vec<String> =
+                            effects.iter().map(|effect| effect.to_string()).collect();
+                        res.push_str(&format!(" | {}", effects.join(" ")));
+                    }
+                    res.push('\n');
+                }
+            }
+        }
 
-            None => character = String::from(" Narator"),
+        if !node.trigger_event.is_empty() {
+            let triggers: Vec<String> = node
+                .trigger_event
+                .iter()
+                .map(|trigger| trigger.to_string())
+                .collect();
+            res.push_str(&format!("-> {}\n", triggers.join(", ")));
         }
-        res.push_str(&character);
-        res.push_str("\n\n");
 
-        for dialog in &self.dialog_type {
-            if let DialogType::Text(text) = dialog {
-                res.push_str("- ");
-                res.push_str(&text);
-            } else if let DialogType::Choice { text, condition } = dialog {
-                res.push_str("- ");
-                res.push_str(&text);
-                res.push_str(" | ");
-
-                match condition {
-                    Some(dialog_condition) => {
-                        match dialog_condition.karma_threshold {
-                            Some((min, max)) => {
-                                res.push_str("karma: min,max; ");
-                                res = res.replace("min", &min.to_string());
-                                res = res.replace("max", &max.to_string());
-                            }
-                            None => {}
-                        }
+        // the jumps are written before any child header:
+        // when the node has some, each jump is written as a header holding only the jump
+        let has_written_child = node.jumps.len() < node.children.len();
+        if !has_written_child {
+            for child in &node.children {
+                res.push_str(&format!("=> {}\n", self.anchor(*child)));
+            }
+            return res;
+        }
 
-                        match &dialog_condition.event {
-                            Some(events) => {
-                                if !events.is_empty() {
-                                    // plurial ?
-                                    res.push_str("event: ");
-                                    for event in events {
-                                        res.push_str(&event.to_string());
-                                        res.push_str(",");
-                                    }
-                                    res.push_str(";");
-                                    res = res.replace(",;", ";");
-                                }
-                            }
-                            None => {}
-                        }
-                    }
+        for (child_index, child) in node.children.iter().enumerate() {
+            // a blank line between two headers
+            if !res.ends_with("\n\n") {
+                res.push('\n');
+            }
 
-                    None => {
-                        res.push_str("None");
-                    }
-                }
+            if !self.is_jump(id, child_index) {
+                res.push_str(&self.print_file_aux(*child, headers.clone() + "#"));
+            } else {
+                let character = self[*child].character.as_deref().unwrap_or(NARATOR);
+                res.push_str(&format!(
+                    "{}# {}\n\n=> {}\n",
+                    headers,
+                    escape(character),
+                    self.anchor(*child)
+                ));
             }
-            res.push('\n');
         }
-        res.push_str("\n\nEND");
 
-        // res = res.replace("\n\n\n", "\n\n");
+        res
+    }
+}
 
-        // event
+/// Walks through a DialogGraph, from its root to one of its leaves.
+///
+/// `current` is None once the dialog is ended.
+///
+/// `path` is the branch taken since the root:
+/// the index of each child the cursor dived into (the n-th choice selected).
+/// Once ended, the cursor keeps the path of the last node it reached.
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct DialogCursor {
+    pub current: Option<NodeId>,
+    pub path: Vec<usize>,
+    /// The last choice of the player was picked when their time was up (`ui::dialog_timer`):
+    /// the next choices can require it (`hesitated`), until the player picks one.
+    #[serde(default)]
+    pub hesitated: bool,
+}
 
-        let children = &self
-            .children
-            .iter()
-            .map(|tn| tn.borrow().print_file_aux(headers.clone() + "#"))
-            .collect::<Vec<String>>()
-            .join("\n\n");
+impl Default for DialogCursor {
+    fn default() -> Self {
+        DialogCursor::new()
+    }
+}
 
-        res.push_str(&children);
+impl DialogCursor {
+    /// A cursor at the root of the graph.
+    pub fn new() -> DialogCursor {
+        DialogCursor {
+            current: Some(NodeId::ROOT),
+            path: vec![],
+            hesitated: false,
+        }
+    }
 
-        // smooth the end of phase
-        res = res.replace(" \n", "\n");
+    /// A cursor which has nothing left to walk through.
+    pub fn ended() -> DialogCursor {
+        DialogCursor {
+            current: None,
+            path: vec![],
+            hesitated: false,
+        }
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.current.is_none()
+    }
 
-        res = res.replace("#\n\n#", "##");
-        res = res.replace("\n\n\n", "\n\n");
+    /// # Return
+    ///
+    /// The node pointed by the cursor, None if the dialog is ended
+    pub fn current<'a>(&self, graph: &'a DialogGraph) -> Option<&'a DialogNode> {
+        self.current.and_then(|id| graph.get(id))
+    }
 
-        // remove the last "\n\n"
-        res = res.replace("END#", "#");
-        // keep only one last '\n' (to respect init rules)
-        res = res.replace("\n\nEND", "\n");
+    /// Goes down to the `child_index`-th child of the current node.
+    ///
+    /// Ends the dialog if there is no such child.
+    ///
+    /// # Return
+    ///
+    /// The new current node
+    pub fn dive(&mut self, graph: &DialogGraph, child_index: usize) -> Option<NodeId> {
+        match self
+            .current(graph)
+            .and_then(|node| node.children.get(child_index).copied())
+        {
+            Some(child) => {
+                self.path.push(child_index);
+                self.current = Some(child);
+            }
+            None => self.current = None,
+        }
 
-        return res;
+        self.current
     }
-}
 
-/// # Argument
-///
-/// * `s` - A string that holds a DialogTree
-///
+    pub fn end(&mut self) {
+        self.current = None;
+    }
+
+    /// Goes back to the root, forgetting the branch taken.
+    pub fn restart(&mut self) {
+        *self = DialogCursor::new();
+    }
+
+    /// Goes back to the previous node.
+    ///
+    /// An ended cursor goes back to the last node it reached.
+    /// Stays on the root if already there.
+    ///
+    /// # Return
+    ///
+    /// The new current node,
+    /// None if the `path` does not lead anywhere in this `graph`
+    pub fn rewind(&mut self, graph: &DialogGraph) -> Option<NodeId> {
+        if !self.is_ended() {
+            self.path.pop();
+        }
+        self.current = DialogCursor::follow(graph, &self.path);
+
+        self.current
+    }
+
+    /// # Return
+    ///
+    /// The node reached by taking the branch `path` from the root of the `graph`,
+    /// None if one of these children does not exist
+    pub fn follow(graph: &DialogGraph, path: &[usize]) -> Option<NodeId> {
+        let mut current = NodeId::ROOT;
+        graph.get(current)?;
+
+        for child_index in path {
+            current = *graph.get(current)?.children.get(*child_index)?;
+        }
+
+        Some(current)
+    }
+
+    /// # Return
+    ///
+    /// true if the `path` still leads to the `current` node within this `graph`
+    /// (a modified dialog file can break it).
+    pub fn is_valid(&self, graph: &DialogGraph) -> bool {
+        match (self.current, DialogCursor::follow(graph, &self.path)) {
+            (Some(current), Some(reached)) => current == reached,
+            (None, reached) => reached.is_some(),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// # Argument
+///
+/// * `s` - A string that holds a DialogTree
+///
 /// # Panics
 ///
 /// The creation will panic
@@ -512,7 +1163,7 @@ impl DialogNode {
 /// ```rust
 /// # main() -> Result<(), std::num::ParseIntError> {
 ///
-/// let tree: DialogTree = init_tree_flat(
+/// let tree: DialogGraph = init_tree_flat(
 ///     String::from(
 ///         "[Hello]->[[I have to tell something], [You beat Olf !]->[[Now you can chill at the hospis]]]"
 ///     )
@@ -537,10 +1188,10 @@ impl DialogNode {
     since = "0.3.0",
     note = "init_tree_flat doesn't implement enought features and is less intuitive than init_tree_file. Users should instead use init_tree_file"
 )]
-pub fn init_tree_flat(s: String) -> Rc<RefCell<DialogNode>> {
-    let root = Rc::new(RefCell::new(DialogNode::new()));
+pub fn init_tree_flat(s: String) -> DialogGraph {
+    let mut graph = DialogGraph::default();
 
-    let mut current = root.clone();
+    let mut current = NodeId::ROOT;
 
     let mut save = String::new();
     // init with text
@@ -561,8 +1212,8 @@ pub fn init_tree_flat(s: String) -> Rc<RefCell<DialogNode>> {
         if *c == ']' && save.is_empty() {
             // go Up
 
-            match &current.clone().borrow().parent {
-                Some(parent) => current = Rc::clone(&parent),
+            match graph[current].parent {
+                Some(parent) => current = parent,
                 None => info!("orphan"),
             }
         } else if (*c == ',' || (*c == ']' && dialog_type.is_choice() && !except))
@@ -578,8 +1229,11 @@ pub fn init_tree_flat(s: String) -> Rc<RefCell<DialogNode>> {
             let choice = DialogType::Choice {
                 text: save.clone(),
                 condition: None,
+                effects: vec![],
+                priority: 0,
+                weight: 1,
             };
-            current.borrow_mut().dialog_type.push(choice);
+            graph[current].dialog_type.push(choice);
 
             // println!("choice with {}", save.clone());
 
@@ -591,7 +1245,7 @@ pub fn init_tree_flat(s: String) -> Rc<RefCell<DialogNode>> {
             }
 
             let text = DialogType::Text(save.clone());
-            current.borrow_mut().dialog_type.push(text);
+            graph[current].dialog_type.push(text);
 
             // println!("text with {}", save.clone());
 
@@ -603,47 +1257,24 @@ pub fn init_tree_flat(s: String) -> Rc<RefCell<DialogNode>> {
                 save.remove(save.len() - 1);
             }
 
-            let child = Rc::new(RefCell::new(DialogNode::new()));
-            current.borrow_mut().children.push(Rc::clone(&child));
-            {
-                let mut mut_child = child.borrow_mut();
-                mut_child.parent = Some(Rc::clone(&current));
-            }
-
-            // setting up the *second* link parent
-            child.borrow_mut().parent = Some(current);
-
             // go down into a new child
-            current = child;
+            current = graph.add_child(current, DialogNode::new());
 
             // Reset the default type
             dialog_type = DialogType::new_text();
         } else if *c == ';' && !except {
-            let adelphe = Rc::new(RefCell::new(DialogNode::new()));
-
-            match &current.borrow().parent {
-                Some(parent) => {
-                    // setting the same parent as the previous adelphe
-                    adelphe.borrow_mut().parent = Some(Rc::clone(&parent));
-
-                    parent.borrow_mut().children.push(Rc::clone(&adelphe));
-                    // setting the same parent as the previous adelphe
-                    // {
-                    //     let mut mut_adelphe = adelphe.borrow_mut();
-                    //     mut_adelphe.parent = Some(Rc::clone(&parent));
-                    // }
-                }
+            match graph[current].parent {
+                // setting the same parent as the previous adelphe
+                // and go 'right' into this new adelphe
+                Some(parent) => current = graph.add_child(parent, DialogNode::new()),
 
                 // A group of adelphe are'nt suppose to be orphean
                 None => {
                     warn!("Adelphes/group of children being orphean");
-                    // the field parent of our new adelphe is already None
+                    // stay on the orphean: its content will be continued
                 }
             }
 
-            // go 'right' into a new adelphe
-            current = adelphe;
-
             // Reset the default type
             dialog_type = DialogType::new_text();
         }
@@ -663,7 +1294,7 @@ pub fn init_tree_flat(s: String) -> Rc<RefCell<DialogNode>> {
         }
     }
 
-    return root;
+    return graph;
 }
 
 fn is_special_char_flat(c: char) -> bool {
@@ -672,1353 +1303,3605 @@ fn is_special_char_flat(c: char) -> bool {
     return special_char.contains(&c);
 }
 
-/// # Argument
-///
-/// * `s` - A string that holds a DialogTree
-///
-/// # Panics
-///
-/// The creation will panic
-/// if any argument to the process is not valid DialogTree format.
-///
-/// # Conventions
-///
-/// ## Dialog Creation
-///
-/// A Dialog can involve as many characters as you like.
-/// A Dialog's Node is composed of:
-///
-/// - the author
-/// - the content
-///   - A serie of text;
-///   To create a monologue.
-///   - A serie of choice.
-///
-///     If the author of this serie is
-///     - a NPC:
-///     The choice will be selected by a priority system,
-///     Important Event dialog before random dialog.
-///     - the Main Character:
-///     The choices to be made will be indicated to the player.
-///     In The player scroll at the bottom the UI Wall.
-/// - the event the Node will launch when it's exited.
-///
-/// ## Rules
-///
-/// - A choice is made of
-///   - a text
-///   - condition; Must end by `;` if != None
-///     - karma threshold; (x,y) with x, y ∈ N.
-///     The player's karma must be within this certain range
-///     - event;
-///     All followed events must be triggered to enable this choice.
-/// - A text can have only one child
-/// - A dialog node cannot have more than one type of dialog_type
-///   - for example
-///   within a same DialogNode, having a text and a choice in the dialog_type field
-///   will break soon or later
-///   FIXME: make it break soon as possible
-///
-/// ***The end marker of the dialog is `\n`.***
-/// You **MUST** end this string by a `\n`.
-///
-/// ## Tips
-///
-/// - You can type
-///   - `k: x,y;` instead of `karma: x,y;`
-///   - `e: Event1, Event2;` instead of `event: Event1, Event2;`
-/// - You can use `MAX`/`MIN` to pick the highest/lowest karma threshold possible
-/// - Prefere not typing anything if it's something like this: `k: MIN,MAX;`
-/// - No matter in the order: `karma: 50,-50;` will result by `karma_threshold: Some((-50,50))`
-/// - To use '-', '\n', '|', '#' or '>' prefere use the char '/' just before these chars
-/// to prevent unwanted phase transition (from content phase to whatever phase)
-///
-/// # Examples
-///
-/// The MainCharacter's catchphrase is followed
-/// by two possible outcomes/choices for the interlocutor.
-///
-/// - a generic one
-///   - random chill dialog,
-///   a simple Text("I have to tell something")
-/// - a huge text to cheer the fact that Olf's reign is over
-///   - only enable when the event `Olf's takedown` occurs
-///   - a first simple Text with a second simple Text as child
-///
-/// ```rust
-/// # // ignore the extra '#' on the example (code block )
-/// # main() -> Result<(), std::num::ParseIntError> {
-///
-/// let tree: DialogTree = init_tree_file(
-///     String::from(
-/// "# Morgan
-///
-/// - Hello
-///
-/// ### Fabien lae Random
-///
-/// - I have to tell something | None
-/// - You beat Olf ! | event: OlfBeaten;
-///
-/// #### Fabien lae Random
-///
-/// - Something
-///
-/// #### Fabien lae Random
-///
-/// - Now you can chill at the hospis
-///
-/// -> OpenTheHospis\n"
-///     )
-/// );
-/// #     Ok(())
-/// # }
-/// ```
-///
-/// To create a long monologue,
-/// avoid using `\n`, prefere using `-`
-/// to seperated paragraph
-///
-/// ```rust
-/// # main() -> Result<(), std::num::ParseIntError> {
-///
-/// let tree: DialogTree = init_tree_file(
-///     String::from(
-/// "# Olf
-///
-/// - Hello
-/// - Do you mind giving me your belongings ?
-/// - Or maybe...
-/// - You want to fight me ?
-///
-/// ### Morgan
-///
-/// - Here my money | e: WonTheLottery;
-/// - You will feel my guitar | None
-/// - Call Homie | k: 10,MAX;
+/// An error raised while reading a dialog written in the `init_tree_file` convention.
 ///
-/// #### Olf
-///
-/// - Thank you very much
-///
-/// #### Olf
-///
-/// - Nice
-/// -> FightEvent
-///
-/// #### Olf
-///
-/// - Not Nice
-/// -> FightEvent\n"
-///     )
-/// );
-///
-/// #     Ok(())
-/// # }
-/// ```
-pub fn init_tree_file(s: String) -> Rc<RefCell<DialogNode>> {
-    let root = Rc::new(RefCell::new(DialogNode::new()));
+/// Points to the offending token, with its line and column (both starting at 1).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DialogParseError {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
 
-    let mut current = root.clone();
+impl DialogParseError {
+    /// `token` must contain at least one char, to be located.
+    fn new(token: &[DialogChar], message: &str) -> DialogParseError {
+        DialogParseError {
+            line: token[0].line,
+            column: token[0].column,
+            token: to_string(token),
+            message: message.to_string(),
+        }
+    }
+}
 
-    let mut author = String::new();
-    let mut save = String::new();
-    // k, e, karma or event
-    let mut condition = DialogCondition::new();
-    // CANT due to reading text one letter/number by one: avoid using karma as a string into .parse::<i32>().unwrap()
-    // let mut negative_karma = false;
-    let mut karma = String::new();
-    let mut event = String::new();
+impl fmt::Display for DialogParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {} (found `{}`)",
+            self.line, self.column, self.message, self.token
+        )
+    }
+}
 
-    // init with text
-    let mut dialog_type: DialogType = DialogType::new_text();
+impl std::error::Error for DialogParseError {}
 
-    // Check if a given char should be insert as text
-    // allow to have text with special char (like - / # )
-    let mut except = false;
+/// A char of a dialog file and its position.
+///
+/// `escaped` is true if the char was preceded by the except char `/`.
+/// An escaped char is located on its `/`.
+#[derive(Clone, Copy, Debug)]
+struct DialogChar {
+    c: char,
+    escaped: bool,
+    line: usize,
+    column: usize,
+}
 
-    let mut header_numbers = 0;
-    let mut last_header_numbers = 0;
+/// Splits the file into its lines, applying the except char `/`.
+///
+/// An escaped `\n` does not end the line, it is part of the text.
+fn split_lines(s: &str) -> Vec<Vec<DialogChar>> {
+    let mut lines = Vec::new();
+    let mut current_line = Vec::new();
+
+    let mut line = 1;
+    let mut column = 1;
+    let mut except: Option<(usize, usize)> = None;
+
+    for c in s.chars() {
+        match except {
+            Some((except_line, except_column)) => {
+                current_line.push(DialogChar {
+                    c,
+                    escaped: true,
+                    line: except_line,
+                    column: except_column,
+                });
+                except = None;
+            }
+            None if c == '/' => except = Some((line, column)),
+            None if c == '\n' => lines.push(std::mem::take(&mut current_line)),
+            None => current_line.push(DialogChar {
+                c,
+                escaped: false,
+                line,
+                column,
+            }),
+        }
 
-    let mut author_phase = false;
-    let mut content_phase = false;
-    let mut condition_phase = false;
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
 
-    let mut karma_phase = false;
-    let mut event_phase = false;
-    // allow to write `event:` or `ejhlksdfh:` instend of `e:`
-    let mut post_colon = false;
+    // a lonely `/` at the very end of the file
+    if let Some((line, column)) = except {
+        current_line.push(DialogChar {
+            c: '/',
+            escaped: true,
+            line,
+            column,
+        });
+    }
+    lines.push(current_line);
 
-    let mut trigger_phase = false;
+    lines
+}
 
-    // let mut new_line = false;
+/// # Return
+///
+/// true if `dialog_char` is the special char `c`, and not an escaped one.
+fn is_special(dialog_char: &DialogChar, c: char) -> bool {
+    !dialog_char.escaped && dialog_char.c == c
+}
 
-    let chars = s.chars().collect::<Vec<char>>();
-    // println!("{}", chars.len());
-    // println!("{}", s);
+/// Removes the (non-escaped) blanks at both ends.
+fn trim(chars: &[DialogChar]) -> &[DialogChar] {
+    let is_blank = |dialog_char: &DialogChar| {
+        !dialog_char.escaped
+            && (dialog_char.c == ' ' || dialog_char.c == '\t' || dialog_char.c == '\r')
+    };
 
-    for (_, c) in chars
+    let start = chars
         .iter()
-        .enumerate()
-        .filter(|(idx, _)| *idx < chars.len())
-    {
-        // println!("c: {}", *c);
+        .position(|dialog_char| !is_blank(dialog_char))
+        .unwrap_or(chars.len());
+    let end = chars
+        .iter()
+        .rposition(|dialog_char| !is_blank(dialog_char))
+        .map_or(start, |last| last + 1);
 
-        if (content_phase && author_phase)
-            || (content_phase && condition_phase)
-            || (author_phase && condition_phase)
-        {
-            // warn!(
-            //     "author phase: {}, content phase: {}, condition phase: {}",
-            //     author_phase, content_phase, condition_phase
-            // );
-            panic!("Illegal Combinaison of phase; author phase: {}, content phase: {}, condition phase: {}",
-            author_phase, content_phase, condition_phase);
-        }
-
-        // transitions
-
-        if *c == '#' && !except {
-            header_numbers += 1;
-            author_phase = true;
-        }
-        // !condition_phase to permit negative number in the karma threshold
-        else if *c == '-' && !condition_phase && !except {
-            content_phase = true;
-        } else if *c == '>' && content_phase && !except {
-            content_phase = false;
-            trigger_phase = true;
-        } else if *c == '|' && content_phase && !except {
-            // remove the space on the first position
-            while save.starts_with(" ") {
-                save.remove(0);
-            }
+    &chars[start..end]
+}
 
-            dialog_type = DialogType::new_choice();
+/// Splits on every (non-escaped) `separator`.
+fn split(chars: &[DialogChar], separator: char) -> Vec<&[DialogChar]> {
+    chars
+        .split(|dialog_char| is_special(dialog_char, separator))
+        .collect()
+}
 
-            condition_phase = true;
-            content_phase = !content_phase;
-        } else if *c == ',' && karma_phase && condition_phase {
-            // can be solved with karma_min, karma_max
-            // println!("karma 1rst elem: {}", karma);
-            let k: i32;
-            // karma = karma.to_uppercase();
-            if karma == "MAX".to_string() || karma == "max".to_string() {
-                k = KARMA_MAX;
-            } else if karma == "MIN".to_string() || karma == "min".to_string() {
-                k = KARMA_MIN;
-            } else {
-                k = karma.parse::<i32>().unwrap();
-            }
-            condition.karma_threshold = Some((k, KARMA_MAX));
-
-            // reuse this variable
-            karma.clear();
-        }
-        //
-        // End of Phase
-        //
-        else if *c == '\n' && author_phase {
-            while author.starts_with(" ") {
-                author.remove(0);
-            }
-            // println!("author: {}", author);
-
-            // root: header_numbers != 1
-            if last_header_numbers != 0 {
-                // println!("previous:\n{}", current.borrow().print_file());
-                // println!("last_header_numbers {}", last_header_numbers);
-                // println!("header_numbers {}", header_numbers);
-
-                // set current to the parent of the incomming node
-                // if last_header_numbers - header_numbers +1 == 0;
-                //     cause 0..0 = 0 iteration
-                // then current = incomming node's parent
-                // so skip this step
-                let limit = last_header_numbers - header_numbers + 1;
-                for _step in 0..limit {
-                    // println!("step {}", _step);
-                    // should not panic anyway
-                    let parent = current.clone().borrow().parent.clone();
-                    // println!(
-                    //     "parent {}:\n{}",
-                    //     _step,
-                    //     parent.clone().unwrap().borrow().print_file()
-                    // );
-                    current = parent.clone().unwrap();
-
-                    // match &current.borrow_mut().parent {
-                    //     Some(parent) => current.borrow_mut() = parent.to_owned(),
-                    //     None => panic!("no parent"),
-                    // }
-                }
+fn find(chars: &[DialogChar], c: char) -> Option<usize> {
+    chars
+        .iter()
+        .position(|dialog_char| is_special(dialog_char, c))
+}
 
-                let child = Rc::new(RefCell::new(DialogNode::new()));
-                current.borrow_mut().children.push(Rc::clone(&child));
-                {
-                    let mut mut_child = child.borrow_mut();
-                    mut_child.parent = Some(Rc::clone(&current));
-                }
+fn to_string(chars: &[DialogChar]) -> String {
+    chars.iter().map(|dialog_char| dialog_char.c).collect()
+}
 
-                // setting up the *second* link parent
-                child.borrow_mut().parent = Some(current);
+/// # Return
+///
+/// true if `c` must be escaped by `/` to be read back as is.
+///
+/// The blanks are only escaped at both ends (`at_end`), not to be trimmed.
+fn must_escape(c: char, at_end: bool) -> bool {
+    matches!(c, '/' | '\n' | '|' | '{' | '}') || (at_end && matches!(c, ' ' | '\t' | '\r'))
+}
 
-                // go down into a new child
-                current = child;
-            }
+/// Writes a raw string (an author) to be read back by `to_string`.
+///
+/// A last `]` is escaped, not to be read as a timeout: `# Hugo [the Great/]`
+fn escape(s: &str) -> String {
+    let last = s.chars().count().saturating_sub(1);
+    let mut res = String::new();
+
+    for (i, c) in s.chars().enumerate() {
+        if must_escape(c, i == 0 || i == last) || (i == last && c == ']') {
+            res.push('/');
+        }
+        res.push(c);
+    }
 
-            // TODO: give the real entity_id or remove id
-            current.borrow_mut().character = Some((0, author.to_owned()));
+    res
+}
 
-            author.clear();
+/// Writes a raw string (the argument of a trigger or of a condition)
+/// to be read back by `parse_trigger` or `parse_condition`.
+///
+/// The `,`, `;` and parentheses are escaped, not to split the arguments or the clauses,
+/// nor to close an argument: `item(salt/, /(pepper/))`
+pub fn escape_argument(s: &str) -> String {
+    let last = s.chars().count().saturating_sub(1);
+    let mut res = String::new();
+
+    for (i, c) in s.chars().enumerate() {
+        if must_escape(c, i == 0 || i == last) || matches!(c, ',' | ';' | '(' | ')') {
+            res.push('/');
+        }
+        res.push(c);
+    }
 
-            last_header_numbers = header_numbers;
-            header_numbers = 0;
+    res
+}
 
-            except = false;
-            author_phase = false;
-        } else if *c == ';' && karma_phase {
-            // println!("karma: {}", karma);
-            let k: i32;
-            // karma = karma.to_uppercase();
-            if karma == "MAX".to_string() || karma == "max".to_string() {
-                k = KARMA_MAX;
-            } else if karma == "MIN".to_string() || karma == "min".to_string() {
-                k = KARMA_MIN;
-            } else {
-                k = karma.parse::<i32>().unwrap();
-            }
-            match condition.karma_threshold {
-                // _ cause the second i32 is KARMA_MAX
-                Some((x, _)) => {
-                    if x <= k {
-                        condition.karma_threshold = Some((x, k));
-                    } else {
-                        condition.karma_threshold = Some((k, x));
-                    }
-                }
-                None => {
-                    warn!("init creation condition has been skipped");
-                }
-            }
-            karma.clear();
-
-            karma_phase = false;
-        } else if (*c == ';' || *c == ',') && event_phase {
-            // println!("event : {}", event);
-            let e = event.parse::<GameEvent>().unwrap();
-            match condition.event {
-                Some(vec) => {
-                    let mut events = vec.clone();
-                    events.push(e);
-                    condition.event = Some(events);
-                }
-                None => condition.event = Some(vec![e]),
+/// Writes a dialog text to be read back by `to_text`.
+///
+/// The variables are kept as is, any other brace is escaped (`/{`).
+fn escape_text(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let last = chars.len().saturating_sub(1);
+    let mut res = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let variable = if c == '{' {
+            variable_at(&chars, i)
+        } else {
+            None
+        };
+        if let Some(variable) = variable {
+            res.push('{');
+            res.push_str(variable);
+            res.push('}');
+            i += variable.chars().count() + 2;
+        } else {
+            if must_escape(c, i == 0 || i == last) {
+                res.push('/');
             }
+            res.push(c);
+            i += 1;
+        }
+    }
 
-            // println!("event cleared");
-            event.clear();
+    res
+}
 
-            if *c == ';' {
-                event_phase = false;
+/// Reads the text of a dialog, checking its variables: `{player.name}`.
+///
+/// The escaped braces (`/{` and `/}`) are kept as simple braces,
+/// they cannot surround a variable name: `interpolate` would still fill it.
+fn to_text(chars: &[DialogChar]) -> Result<String, DialogParseError> {
+    let mut text = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let dialog_char = &chars[i];
+        if dialog_char.escaped && dialog_char.c == '{' {
+            let plain: Vec<char> = chars[i..].iter().map(|dialog_char| dialog_char.c).collect();
+            if let Some(variable) = variable_at(&plain, 0) {
+                return Err(DialogParseError::new(
+                    &chars[i..i + variable.chars().count() + 2],
+                    "a variable cannot be escaped, it would still be filled",
+                ));
             }
-        } else if *c == '\n' && condition_phase && dialog_type.is_choice()
-        // && !karma_phase
-        // && !event_phase
-        {
-            // remove the space on the first position
-            while save.starts_with(" ") {
-                save.remove(0);
+            text.push(dialog_char.c);
+        } else if is_special(dialog_char, '}') {
+            return Err(DialogParseError::new(
+                &chars[i..=i],
+                "this brace closes no variable, use `/}` to write it",
+            ));
+        } else if is_special(dialog_char, '{') {
+            let close = match find(&chars[i..], '}') {
+                Some(close) => i + close,
+                None => {
+                    return Err(DialogParseError::new(
+                        &chars[i..],
+                        "a variable is not closed, use `/{` to write a brace",
+                    ))
+                }
+            };
+            let variable = to_string(&chars[i + 1..close]);
+            if !DIALOG_VARIABLES.contains(&variable.as_str()) {
+                return Err(DialogParseError::new(
+                    &chars[i..=close],
+                    &format!("unknown variable, expected one of {:?}", DIALOG_VARIABLES),
+                ));
             }
 
-            // remove the space on the last position
-            while save.ends_with(" ") {
-                save.remove(save.len() - 1);
-            }
+            text.push('{');
+            text.push_str(&variable);
+            text.push('}');
+            i = close;
+        } else {
+            text.push(dialog_char.c);
+        }
+        i += 1;
+    }
+
+    Ok(text)
+}
 
-            // println!("choice inserted: {}", save);
+/// Parses a karma bound: a number, `MAX` or `MIN`.
+fn parse_karma(bound: &[DialogChar], clause: &[DialogChar]) -> Result<i32, DialogParseError> {
+    let bound = trim(bound);
+    if bound.is_empty() {
+        return Err(DialogParseError::new(
+            clause,
+            "a karma threshold needs two bounds: `karma: min,max;`",
+        ));
+    }
 
-            let choice: DialogType;
+    match to_string(bound).as_str() {
+        "MAX" | "max" => Ok(KARMA_MAX),
+        "MIN" | "min" => Ok(KARMA_MIN),
+        karma => karma.parse::<i32>().map_err(|_| {
+            DialogParseError::new(bound, "a karma bound must be a number, `MIN` or `MAX`")
+        }),
+    }
+}
 
-            match (condition.karma_threshold, condition.clone().event) {
-                (Some(_), None) | (None, Some(_)) | (Some(_), Some(_)) => {
-                    choice = DialogType::Choice {
-                        text: save.clone(),
-                        condition: Some(condition.clone()),
-                    };
-                }
-                // (None, None)
-                _ => {
-                    choice = DialogType::Choice {
-                        text: save.clone(),
-                        condition: None,
-                    }
-                }
+/// Splits on every (non-escaped) `,` which is not between parentheses.
+fn split_arguments(chars: &[DialogChar]) -> Result<Vec<&[DialogChar]>, DialogParseError> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, dialog_char) in chars.iter().enumerate() {
+        if is_special(dialog_char, '(') {
+            depth += 1;
+        } else if is_special(dialog_char, ')') {
+            if depth == 0 {
+                return Err(DialogParseError::new(
+                    &chars[i..=i],
+                    "this parenthesis closes nothing",
+                ));
             }
+            depth -= 1;
+        } else if depth == 0 && is_special(dialog_char, ',') {
+            arguments.push(&chars[start..i]);
+            start = i + 1;
+        }
+    }
+    if depth > 0 {
+        return Err(DialogParseError::new(
+            trim(&chars[start..]),
+            "a parenthesis is not closed",
+        ));
+    }
+    arguments.push(&chars[start..]);
 
-            current.borrow_mut().dialog_type.push(choice);
+    Ok(arguments)
+}
 
-            condition_phase = false;
+/// Parses one trigger event: `Event` or `Event(arg1, arg2)`.
+///
+/// `trigger` must be trimmed and not empty.
+fn parse_trigger(trigger: &[DialogChar]) -> Result<ThrowableEvent, DialogParseError> {
+    let (name, arguments) = match find(trigger, '(') {
+        None => (trigger, Vec::new()),
+        Some(open) => {
+            if !is_special(&trigger[trigger.len() - 1], ')') {
+                return Err(DialogParseError::new(
+                    trigger,
+                    "the arguments of a trigger event must end its name: `Event(arg1, arg2)`",
+                ));
+            }
+            let arguments = split_arguments(&trigger[open + 1..trigger.len() - 1])?
+                .into_iter()
+                .map(trim)
+                .collect::<Vec<_>>();
+            if arguments.iter().any(|argument| argument.is_empty()) {
+                return Err(DialogParseError::new(
+                    trigger,
+                    "an argument of this trigger event is missing",
+                ));
+            }
+            (trim(&trigger[..open]), arguments)
+        }
+    };
 
-            save.clear();
-            condition = DialogCondition::new();
-        } else if *c == '\n'
-            && content_phase
-            && !save.is_empty()
-            && dialog_type.is_text()
-            && !except
+    if name.is_empty() {
+        return Err(DialogParseError::new(
+            trigger,
+            "a trigger event is missing its name",
+        ));
+    }
+
+    // the number of arguments must match the signature of the event
+    let expect = |signature: &str, count: usize| {
+        if arguments.len() == count {
+            Ok(())
+        } else {
+            Err(DialogParseError::new(
+                trigger,
+                &format!("this trigger event expects `{}`", signature),
+            ))
+        }
+    };
+
+    match to_string(name).as_str() {
+        ThrowableEvent::FIGHT_EVENT => {
+            expect("FightEvent", 0)?;
+            Ok(ThrowableEvent::FightEvent)
+        }
+        ThrowableEvent::HAS_FRIEND => {
+            expect("HasFriend", 0)?;
+            Ok(ThrowableEvent::HasFriend)
+        }
+        ThrowableEvent::KARMA => {
+            expect("Karma(delta)", 1)?;
+            match to_string(arguments[0]).parse::<i32>() {
+                Ok(delta) => Ok(ThrowableEvent::Karma(delta)),
+                Err(_) => Err(DialogParseError::new(
+                    arguments[0],
+                    "a karma delta must be a number: `+5`",
+                )),
+            }
+        }
+        ThrowableEvent::GIVE_ITEM => {
+            expect("GiveItem(item, amount)", 2)?;
+            match to_string(arguments[1]).parse::<u32>() {
+                Ok(amount) => Ok(ThrowableEvent::GiveItem {
+                    item: to_string(arguments[0]),
+                    amount,
+                }),
+                Err(_) => Err(DialogParseError::new(
+                    arguments[1],
+                    "an amount of item must be a positive number",
+                )),
+            }
+        }
+        ThrowableEvent::JOIN_TEAM => {
+            expect("JoinTeam(team)", 1)?;
+            let team = to_string(arguments[0]);
+            match TEAMS.iter().find(|(name, _)| *name == team) {
+                Some((_, id)) => Ok(ThrowableEvent::JoinTeam(*id)),
+                None => match team.parse::<i32>() {
+                    Ok(id) => Ok(ThrowableEvent::JoinTeam(id)),
+                    Err(_) => Err(DialogParseError::new(
+                        arguments[0],
+                        "a team must be a number or a team constant: `TEAM_MC`",
+                    )),
+                },
+            }
+        }
+        _ => Err(DialogParseError::new(name, "unknown trigger event")),
+    }
+}
+
+/// Parses the effects of a choice (everything after the second `|`): `karma: -10; hp: +5;`
+fn parse_effects(chars: &[DialogChar]) -> Result<Vec<ChoiceEffect>, DialogParseError> {
+    let mut effects = Vec::new();
+
+    for clause in split(chars, ';') {
+        let clause = trim(clause);
+        if clause.is_empty() {
+            continue;
+        }
+
+        let colon = match find(clause, ':') {
+            Some(colon) => colon,
+            None => {
+                return Err(DialogParseError::new(
+                    clause,
+                    "expected an effect: `karma: -10;`",
+                ))
+            }
+        };
+        let key = trim(&clause[..colon]);
+        let delta = match to_string(trim(&clause[colon + 1..])).parse::<i32>() {
+            Ok(delta) => delta,
+            Err(_) => {
+                return Err(DialogParseError::new(
+                    clause,
+                    "an effect changes a stat by a number: `karma: -10;`",
+                ))
+            }
+        };
+
+        match to_string(key).as_str() {
+            "k" | "karma" => effects.push(ChoiceEffect::Karma(delta)),
+            "hp" => effects.push(ChoiceEffect::Hp(delta)),
+            "mana" => effects.push(ChoiceEffect::Mana(delta)),
+            _ => {
+                return Err(DialogParseError::new(
+                    clause,
+                    "unknown effect, expected `karma`, `hp` or `mana`",
+                ))
+            }
+        }
+    }
+
+    Ok(effects)
+}
+
+/// A label is made of letters, digits, `_` and `-`: `ask_again`.
+///
+/// `line` is pointed to if the label is invalid.
+fn parse_label(label: &[DialogChar], line: &[DialogChar]) -> Result<String, DialogParseError> {
+    let label = trim(label);
+    let is_valid = |dialog_char: &DialogChar| {
+        !dialog_char.escaped
+            && (dialog_char.c.is_alphanumeric() || dialog_char.c == '_' || dialog_char.c == '-')
+    };
+
+    if label.is_empty() || !label.iter().all(is_valid) {
+        Err(DialogParseError::new(
+            line,
+            "a label is made of letters, digits, `_` and `-`: `{#ask_again}`",
+        ))
+    } else {
+        Ok(to_string(label))
+    }
+}
+
+/// Splits the anchor off the author of a header: `Olf {#ask_again}`.
+///
+/// # Return
+///
+/// The author, and the anchor (if any): `{#ask_again}`
+fn split_anchor(
+    header: &[DialogChar],
+) -> Result<(&[DialogChar], Option<&[DialogChar]>), DialogParseError> {
+    let start = match (1..header.len())
+        .find(|i| is_special(&header[i - 1], '{') && is_special(&header[*i], '#'))
+    {
+        Some(hash) => hash - 1,
+        None => return Ok((header, None)),
+    };
+
+    let anchor = &header[start..];
+    if !is_special(&anchor[anchor.len() - 1], '}') {
+        return Err(DialogParseError::new(
+            anchor,
+            "an anchor ends the header: `# Author {#label}`",
+        ));
+    }
+
+    Ok((trim(&header[..start]), Some(anchor)))
+}
+
+/// Splits the timeout off a header: `Morgan {#ask} [timeout=5s, default=2]`.
+///
+/// # Return
+///
+/// The rest of the header, and the timeout (if any): `[timeout=5s, default=2]`
+fn split_timeout(
+    header: &[DialogChar],
+) -> Result<(&[DialogChar], Option<&[DialogChar]>), DialogParseError> {
+    if header.is_empty() || !is_special(&header[header.len() - 1], ']') {
+        return Ok((header, None));
+    }
+
+    match header
+        .iter()
+        .rposition(|dialog_char| is_special(dialog_char, '['))
+    {
+        Some(start) => Ok((trim(&header[..start]), Some(&header[start..]))),
+        None => Err(DialogParseError::new(
+            header,
+            "a timeout ends the header: `## Author [timeout=5s, default=0]`",
+        )),
+    }
+}
+
+/// Parses the timeout of a header: `[timeout=5s, default=2]`.
+///
+/// `default` can be omitted: the first choice is picked.
+fn parse_timeout(timeout: &[DialogChar]) -> Result<ChoiceTimeout, DialogParseError> {
+    let mut seconds = None;
+    let mut default = 0;
+
+    for setting in split(&timeout[1..timeout.len() - 1], ',') {
+        let setting = trim(setting);
+        let (key, value) = match find(setting, '=') {
+            Some(equal) => (
+                to_string(trim(&setting[..equal])),
+                to_string(trim(&setting[equal + 1..])),
+            ),
+            None => (to_string(setting), String::new()),
+        };
+
+        match key.as_str() {
+            "timeout" => {
+                seconds = match value.strip_suffix('s').unwrap_or(&value).parse::<f32>() {
+                    Ok(seconds) if seconds.is_finite() && seconds > 0. => Some(seconds),
+                    _ => {
+                        return Err(DialogParseError::new(
+                            setting,
+                            "a timeout is a positive duration: `timeout=5s`",
+                        ))
+                    }
+                };
+            }
+            "default" => {
+                default = match value.parse::<usize>() {
+                    Ok(default) => default,
+                    Err(_) => {
+                        return Err(DialogParseError::new(
+                            setting,
+                            "the default is the index of a choice, from 0: `default=2`",
+                        ))
+                    }
+                };
+            }
+            _ => {
+                return Err(DialogParseError::new(
+                    setting,
+                    "unknown setting, expected `timeout` or `default`",
+                ))
+            }
+        }
+    }
+
+    match seconds {
+        Some(seconds) => Ok(ChoiceTimeout { seconds, default }),
+        None => Err(DialogParseError::new(
+            timeout,
+            "a timed header needs a timeout: `[timeout=5s, default=0]`",
+        )),
+    }
+}
+
+/// Splits a choice into its text, condition and effects:
+/// on every (non-escaped) `|` which is not part of a `||`.
+fn split_slots(chars: &[DialogChar]) -> Vec<&[DialogChar]> {
+    let is_bar = |i: usize| chars.get(i).is_some_and(|c| is_special(c, '|'));
+
+    let mut slots = Vec::new();
+    let mut start = 0;
+    for i in 0..chars.len() {
+        if is_bar(i) && !(i > 0 && is_bar(i - 1)) && !is_bar(i + 1) {
+            slots.push(&chars[start..i]);
+            start = i + 1;
+        }
+    }
+    slots.push(&chars[start..]);
+
+    slots
+}
+
+/// # Return
+///
+/// true if this clause of a condition holds an operator (`&&`, `!`, `>=`, ...),
+/// and so is a boolean expression: `karma >= 10 && !event: FirstKill`
+fn has_operator(clause: &[DialogChar]) -> bool {
+    clause
+        .iter()
+        .any(|dialog_char| !dialog_char.escaped && "&|!()<>=".contains(dialog_char.c))
+}
+
+/// Reads a boolean expression, see `ui::dialog_condition` for its grammar.
+///
+/// `||` binds less than `&&`, which binds less than `!`.
+struct ExpressionParser<'a> {
+    chars: &'a [DialogChar],
+    position: usize,
+}
+
+impl<'a> ExpressionParser<'a> {
+    /// Parses the whole `chars`, which must be trimmed and not empty.
+    fn parse(chars: &'a [DialogChar]) -> Result<ConditionExpr, DialogParseError> {
+        let mut parser = ExpressionParser { chars, position: 0 };
+        let expression = parser.parse_or()?;
+
+        parser.skip_blanks();
+        if parser.position < chars.len() {
+            return Err(DialogParseError::new(
+                &chars[parser.position..],
+                "expected `&&`, `||` or the end of the expression",
+            ));
+        }
+
+        Ok(expression)
+    }
+
+    fn skip_blanks(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|dialog_char| is_special(dialog_char, ' '))
         {
-            // remove the space on the first position
-            while save.starts_with(" ") {
-                save.remove(0);
+            self.position += 1;
+        }
+    }
+
+    /// Moves past `token` if it comes next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_blanks();
+        let rest = &self.chars[self.position..];
+        let matches = rest.len() >= token.chars().count()
+            && token
+                .chars()
+                .zip(rest)
+                .all(|(c, dialog_char)| is_special(dialog_char, c));
+        if matches {
+            self.position += token.chars().count();
+        }
+        matches
+    }
+
+    /// What is left, or the whole expression when nothing is: to point an error
+    fn rest(&self) -> &'a [DialogChar] {
+        if self.position < self.chars.len() {
+            &self.chars[self.position..]
+        } else {
+            self.chars
+        }
+    }
+
+    /// Reads the chars while `accept` holds.
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'a [DialogChar] {
+        self.skip_blanks();
+        let start = self.position;
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|dialog_char| accept(dialog_char.c))
+        {
+            self.position += 1;
+        }
+        &self.chars[start..self.position]
+    }
+
+    fn parse_or(&mut self) -> Result<ConditionExpr, DialogParseError> {
+        let mut expression = self.parse_and()?;
+        while self.eat("||") {
+            let right = self.parse_and()?;
+            expression = ConditionExpr::Or(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<ConditionExpr, DialogParseError> {
+        let mut expression = self.parse_not()?;
+        while self.eat("&&") {
+            let right = self.parse_not()?;
+            expression = ConditionExpr::And(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<ConditionExpr, DialogParseError> {
+        if self.eat("!") {
+            Ok(ConditionExpr::Not(Box::new(self.parse_not()?)))
+        } else if self.eat("(") {
+            let expression = self.parse_or()?;
+            if !self.eat(")") {
+                return Err(DialogParseError::new(
+                    self.rest(),
+                    "a parenthesis is not closed",
+                ));
             }
+            Ok(expression)
+        } else {
+            self.parse_atom()
+        }
+    }
 
-            // remove the space on the last position
-            while save.ends_with(" ") {
-                save.remove(save.len() - 1);
+    /// The argument of `party.has(Name)` or `item(potion)`
+    fn parse_argument(&mut self) -> Result<String, DialogParseError> {
+        if !self.eat("(") {
+            return Err(DialogParseError::new(self.rest(), "expected `(`"));
+        }
+        self.skip_blanks();
+        let start = self.position;
+        // an escaped `)` is part of the argument: `item(salt /(pepper/))`
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|dialog_char| !is_special(dialog_char, ')'))
+        {
+            self.position += 1;
+        }
+        let argument = trim(&self.chars[start..self.position]);
+        if argument.is_empty() || !self.eat(")") {
+            return Err(DialogParseError::new(
+                self.rest(),
+                "expected an argument then `)`: `party.has(Hugo)`",
+            ));
+        }
+        Ok(to_string(argument))
+    }
+
+    fn parse_atom(&mut self) -> Result<ConditionExpr, DialogParseError> {
+        let word = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+
+        let operand = match to_string(word).as_str() {
+            "event" => {
+                if !self.eat(":") {
+                    return Err(DialogParseError::new(
+                        self.rest(),
+                        "expected an event: `event: FirstKill`",
+                    ));
+                }
+                let event = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                return match to_string(event).parse::<GameEvent>() {
+                    Ok(game_event) => Ok(ConditionExpr::Event(game_event)),
+                    Err(()) => Err(DialogParseError::new(
+                        if event.is_empty() { self.rest() } else { event },
+                        "unknown game event",
+                    )),
+                };
+            }
+            "party.has" => return Ok(ConditionExpr::PartyHas(self.parse_argument()?)),
+            "hesitated" => return Ok(ConditionExpr::Hesitated),
+            "karma" => Operand::Karma,
+            "hp" => Operand::Hp,
+            "party.size" => Operand::PartySize,
+            "item" => Operand::Item(self.parse_argument()?),
+            _ => {
+                return Err(DialogParseError::new(
+                    if word.is_empty() { self.rest() } else { word },
+                    "unknown condition, expected `event:`, `party.has()`, `hesitated`, `karma`, `hp`, `party.size` or `item()`",
+                ))
+            }
+        };
+
+        let comparison = match Comparison::ALL
+            .iter()
+            .find(|comparison| self.eat(comparison.symbol()))
+        {
+            Some(comparison) => *comparison,
+            None => {
+                return Err(DialogParseError::new(
+                    self.rest(),
+                    "expected a comparison: `>=`, `<=`, `>`, `<`, `==` or `!=`",
+                ))
+            }
+        };
+
+        let number = self.take_while(|c| c.is_ascii_digit() || c == '.' || c == '-' || c == '+');
+        match to_string(number).parse::<f32>() {
+            Ok(number) => Ok(ConditionExpr::Compare(operand, comparison, number)),
+            Err(_) => Err(DialogParseError::new(
+                if number.is_empty() {
+                    self.rest()
+                } else {
+                    number
+                },
+                "expected a number: `karma >= 10`",
+            )),
+        }
+    }
+}
+
+/// Parses the condition of a choice (everything after the `|`).
+///
+/// # Return
+///
+/// The condition, None if the choice is always enabled (`None` or nothing at all),
+/// then the priority and the weight of the choice (`priority: 2; weight: 3;`)
+fn parse_condition(
+    chars: &[DialogChar],
+) -> Result<(Option<DialogCondition>, i32, u32), DialogParseError> {
+    let chars = trim(chars);
+    if chars.is_empty() || to_string(chars) == "None" {
+        return Ok((None, 0, 1));
+    }
+
+    let mut condition = DialogCondition::new();
+    let mut priority = 0;
+    let mut weight = 1;
+
+    for clause in split(chars, ';') {
+        let clause = trim(clause);
+        if clause.is_empty() {
+            continue;
+        }
+
+        let colon = match find(clause, ':') {
+            Some(colon) if !has_operator(clause) => colon,
+            // `karma >= 10`, `party.has(Hugo)`, `!event: FirstKill`
+            _ => {
+                let expression = ExpressionParser::parse(clause)?;
+                // the expressions of several clauses must all hold
+                condition.expression = Some(match condition.expression.take() {
+                    Some(previous) => ConditionExpr::And(Box::new(previous), Box::new(expression)),
+                    None => expression,
+                });
+                continue;
+            }
+        };
+        let key = trim(&clause[..colon]);
+        let values = &clause[colon + 1..];
+
+        match to_string(key).as_str() {
+            "k" | "karma" => {
+                let bounds = split(values, ',');
+                if bounds.len() != 2 {
+                    return Err(DialogParseError::new(
+                        clause,
+                        "a karma threshold needs two bounds: `karma: min,max;`",
+                    ));
+                }
+                let first = parse_karma(bounds[0], clause)?;
+                let second = parse_karma(bounds[1], clause)?;
+
+                // No matter in the order
+                condition.karma_threshold = Some((first.min(second), first.max(second)));
+            }
+            "e" | "event" => {
+                let mut events = condition.event.unwrap_or_default();
+                for event in split(values, ',') {
+                    let event = trim(event);
+                    if event.is_empty() {
+                        return Err(DialogParseError::new(clause, "an event is missing"));
+                    }
+
+                    match to_string(event).parse::<GameEvent>() {
+                        Ok(game_event) => events.push(game_event),
+                        Err(()) => {
+                            return Err(DialogParseError::new(event, "unknown game event"));
+                        }
+                    }
+                }
+                condition.event = Some(events);
+            }
+            "p" | "priority" => {
+                priority = match to_string(trim(values)).parse::<i32>() {
+                    Ok(priority) => priority,
+                    Err(_) => {
+                        return Err(DialogParseError::new(
+                            clause,
+                            "a priority must be a number: `priority: 2;`",
+                        ))
+                    }
+                };
+            }
+            "w" | "weight" => {
+                weight = match to_string(trim(values)).parse::<u32>() {
+                    Ok(weight) if weight > 0 => weight,
+                    _ => {
+                        return Err(DialogParseError::new(
+                            clause,
+                            "a weight must be a strictly positive number: `weight: 3;`",
+                        ))
+                    }
+                };
+            }
+            _ => {
+                // `key` can be empty: `: smth;`
+                return Err(DialogParseError::new(
+                    clause,
+                    "unknown condition, expected `karma`, `event`, `priority` or `weight`",
+                ));
             }
+        }
+    }
+
+    if condition == DialogCondition::new() {
+        Ok((None, priority, weight))
+    } else {
+        Ok((Some(condition), priority, weight))
+    }
+}
+
+/// # Argument
+///
+/// * `s` - A string that holds a DialogTree
+///
+/// # Errors
+///
+/// Returns a DialogParseError, pointing to the first offending token,
+/// if `s` is not in a valid DialogTree format.
+///
+/// # Conventions
+///
+/// ## Dialog Creation
+///
+/// A Dialog can involve as many characters as you like.
+/// A Dialog's Node is composed of:
+///
+/// - the author
+/// - the content
+///   - A serie of text;
+///   To create a monologue.
+///   - A serie of choice.
+///
+///     If the author of this serie is
+///     - a NPC:
+///     The choice will be selected by a priority system,
+///     Important Event dialog before random dialog.
+///     - the Main Character:
+///     The choices to be made will be indicated to the player.
+///     In The player scroll at the bottom the UI Wall.
+/// - the event the Node will launch when it's exited.
+///
+/// ## Rules
+///
+/// - Each line is either
+///   - a header: `# Author`, the number of `#` gives the depth of the node
+///   - the lines said by nobody are written under `# Narator`
+///   - a child header is exactly one `#` deeper than its parent: `#` then `##`, never `###`
+///   - a text: `- Text`
+///   - a choice: `- Text | Condition`, or with its effects: `- Text | Condition | Effects`
+///   - the events to trigger: `-> Event1, Event2`
+///   - a jump: `=> label`, leading to the node anchored `{#label}`
+/// - A header can end with an anchor: `## Olf {#ask_again}`
+///   - a label is made of letters, digits, `_` and `-`, and anchors a single header
+/// - A header of the player's choices can end with a timeout: `## Morgan {#ask} [timeout=5s, default=2]`
+///   - when the time is up, the `default` choice is picked (from 0, the first one if omitted)
+/// - The jumps of a node lead to its first children, before the headers written under it
+///   - a header holding only a jump stands for the anchored node:
+///   to make a choice lead to it
+/// - A choice is made of
+///   - a text
+///   - condition; Each must end by `;` if != None
+///     - karma threshold; (x,y) with x, y ∈ N.
+///     The player's karma must be within this certain range
+///     - event;
+///     All followed events must be triggered to enable this choice.
+///     - expression; `karma >= 10 && (event: BeatTheGame || party.has(Hugo));`
+///     A boolean expression over the karma, the events, the party, the hp and the items,
+///     see `ui::dialog_condition`.
+///     - priority; (only for a npc's choice)
+///     The verified choices with the highest priority take over the others (0 by default).
+///     - weight; (only for a npc's choice)
+///     The odds of a choice among the ones with the same priority (1 by default).
+/// - The effects of a choice are applied to the player when picked, separated by `;`
+///   - `karma: -10;`, `hp: +5;` or `mana: -5;`
+///   - the stat is kept within its bounds (`KARMA_MIN..KARMA_MAX`, `0..max_hp`)
+/// - A text can have only one child
+/// - A dialog node cannot have more than one type of dialog_type
+///   - for example
+///   within a same DialogNode, having a text and a choice in the dialog_type field
+///   is refused.
+///
+/// ***The end marker of a line is `\n`.***
+///
+/// ## Tips
+///
+/// - You can type
+///   - `k: x,y;` instead of `karma: x,y;`
+///   - `e: Event1, Event2;` instead of `event: Event1, Event2;`
+///   - `p: 2;` instead of `priority: 2;` and `w: 3;` instead of `weight: 3;`
+/// - A text can refer to some variables of the game, filled when the text is shown:
+/// `{player.name}`, `{karma}`, `{party.size}` and `{npc.name}`
+///   - `/{` and `/}` write simple braces
+/// - A text can be paced and emphasised by some markup, read by the DialogBox:
+/// `[color=red]`, `[b]`, `[shake]`, `[speed=2]` and `[wait=0.5]` (see `ui::dialog_markup`)
+/// - Two choices can lead to the same follow-up, and a dialog can loop,
+/// by jumping to an anchored header instead of copying it
+/// - You can use `MAX`/`MIN` to pick the highest/lowest karma threshold possible
+/// - Prefere not typing anything if it's something like this: `k: MIN,MAX;`
+/// - No matter in the order: `karma: 50,-50;` will result by `karma_threshold: Some((-50,50))`
+/// - To use '-', '\n', '|', '#' or '>' prefere use the char '/' just before these chars
+/// to prevent unwanted phase transition (from content phase to whatever phase)
+///
+/// # Examples
+///
+/// The MainCharacter's catchphrase is followed
+/// by two possible outcomes/choices for the interlocutor.
+///
+/// - a generic one
+///   - random chill dialog,
+///   a simple Text("I have to tell something")
+/// - a huge text to cheer the fact that Olf's reign is over
+///   - only enable when the event `Olf's takedown` occurs
+///   - a first simple Text with a second simple Text as child
+///
+/// ```rust
+/// # // ignore the extra '#' on the example (code block )
+/// # main() -> Result<(), DialogParseError> {
+///
+/// let graph: DialogGraph = parse_dialog(
+/// "# Morgan
+///
+/// - Hello
+///
+/// ## Fabien lae Random
+///
+/// - I have to tell something | None
+/// - You beat Olf ! | event: BeatTheGame;
+///
+/// ### Fabien lae Random
+///
+/// - Something
+///
+/// ### Fabien lae Random
+///
+/// - Now you can chill at the hospis
+///
+/// -> HasFriend\n"
+/// )?;
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// To create a long monologue,
+/// avoid using `\n`, prefere using `-`
+/// to seperated paragraph
+///
+/// ```rust
+/// # main() -> Result<(), DialogParseError> {
+///
+/// let graph: DialogGraph = parse_dialog(
+/// "# Olf
+///
+/// - Hello
+/// - Do you mind giving me your belongings ?
+/// - Or maybe...
+/// - You want to fight me ?
+///
+/// ## Morgan
+///
+/// - Here my money | e: HasCharisma;
+/// - You will feel my guitar | None
+/// - Call Homie | k: 10,MAX;
+///
+/// ### Olf
+///
+/// - Thank you very much
+///
+/// ### Olf
+///
+/// - Nice
+/// -> FightEvent
+///
+/// ### Olf
+///
+/// - Not Nice
+/// -> FightEvent\n"
+/// )?;
+///
+/// #     Ok(())
+/// # }
+/// ```
+pub fn parse_dialog(s: &str) -> Result<DialogGraph, DialogParseError> {
+    let mut graph = DialogGraph::default();
+
+    // All the headers from the root to the current node: (header_numbers, node)
+    let mut headers: Vec<(usize, NodeId)> = Vec::new();
+    // The node anchored by each label: `{#label}`
+    let mut labels: HashMap<String, NodeId> = HashMap::new();
+    // Resolved once every label is known: (node, label, line)
+    let mut jumps: Vec<(NodeId, String, Vec<DialogChar>)> = Vec::new();
+
+    for line in split_lines(s) {
+        let line = trim(&line);
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // Header: `## Author`
+        if is_special(&line[0], '#') {
+            let header_numbers = line
+                .iter()
+                .take_while(|dialog_char| is_special(dialog_char, '#'))
+                .count();
+
+            let (header, timeout) = split_timeout(trim(&line[header_numbers..]))?;
+            let (author, anchor) = split_anchor(header)?;
+            if author.is_empty() {
+                return Err(DialogParseError::new(
+                    line,
+                    "a header must be followed by its author",
+                ));
+            }
+
+            let node = if headers.is_empty() {
+                NodeId::ROOT
+            } else {
+                // go up until the parent of the incomming node
+                while let Some((parent_header_numbers, _)) = headers.last() {
+                    if *parent_header_numbers >= header_numbers {
+                        headers.pop();
+                    } else {
+                        break;
+                    }
+                }
+
+                let parent = match headers.last() {
+                    Some((parent_header_numbers, _))
+                        if header_numbers > parent_header_numbers + 1 =>
+                    {
+                        return Err(DialogParseError::new(
+                            &line[..header_numbers],
+                            "this header skips a level: it must be one `#` deeper than its parent",
+                        ))
+                    }
+                    Some((_, parent)) => *parent,
+                    None => {
+                        return Err(DialogParseError::new(
+                            &line[..header_numbers],
+                            "this header is not deeper than the root's one: it has no parent",
+                        ))
+                    }
+                };
+
+                graph.add_child(parent, DialogNode::new())
+            };
+
+            // resolved against the Name of the entities while talking
+            let author = to_string(author);
+            graph[node].character = if author == NARATOR {
+                None
+            } else {
+                Some(author)
+            };
+
+            if let Some(anchor) = anchor {
+                let label = parse_label(&anchor[2..anchor.len() - 1], anchor)?;
+                if labels.insert(label.clone(), node).is_some() {
+                    return Err(DialogParseError::new(
+                        anchor,
+                        "this label is already anchored to another header",
+                    ));
+                }
+                graph[node].label = Some(label);
+            }
+            if let Some(timeout) = timeout {
+                graph[node].timeout = Some(parse_timeout(timeout)?);
+            }
+
+            headers.push((header_numbers, node));
+            continue;
+        }
+
+        let current = match headers.last() {
+            Some((_, current)) => *current,
+            None => {
+                return Err(DialogParseError::new(
+                    line,
+                    "a dialog must start with a header: `# Author`",
+                ))
+            }
+        };
+
+        // Jump: `=> label`
+        if line.len() > 1 && is_special(&line[0], '=') && is_special(&line[1], '>') {
+            let label = parse_label(&line[2..], line)?;
+            jumps.push((current, label, line.to_vec()));
+        }
+        // Trigger Events: `-> Event1, Event2(arg1, arg2)`
+        else if line.len() > 1 && is_special(&line[0], '-') && is_special(&line[1], '>') {
+            for event in split_arguments(&line[2..])? {
+                let event = trim(event);
+                if event.is_empty() {
+                    return Err(DialogParseError::new(line, "a trigger event is missing"));
+                }
+
+                // add the triggered event to the vector of the current DialogNode
+                let throwable_event = parse_trigger(event)?;
+                graph[current].trigger_event.push(throwable_event);
+            }
+        }
+        // Content: `- Text` or `- Choice | Condition | Effects`
+        else if is_special(&line[0], '-') {
+            let content = &line[1..];
+
+            let dialog = match split_slots(content)[..] {
+                [text] => DialogType::Text(to_text(trim(text))?),
+                [text, condition] => {
+                    let text = to_text(trim(text))?;
+                    let (condition, priority, weight) = parse_condition(condition)?;
+                    DialogType::Choice {
+                        text,
+                        condition,
+                        effects: vec![],
+                        priority,
+                        weight,
+                    }
+                }
+                [text, condition, effects] => {
+                    let text = to_text(trim(text))?;
+                    let (condition, priority, weight) = parse_condition(condition)?;
+                    DialogType::Choice {
+                        text,
+                        condition,
+                        effects: parse_effects(effects)?,
+                        priority,
+                        weight,
+                    }
+                }
+                _ => {
+                    return Err(DialogParseError::new(
+                        line,
+                        "a choice is made of a text, a condition and its effects: `- Text | Condition | Effects`",
+                    ))
+                }
+            };
+
+            let is_empty = match &dialog {
+                DialogType::Text(text) | DialogType::Choice { text, .. } => text.is_empty(),
+            };
+            if is_empty {
+                return Err(DialogParseError::new(line, "this dialog has no text"));
+            }
+
+            let node = &mut graph[current];
+            if node.is_choice() != dialog.is_choice() && !node.dialog_type.is_empty() {
+                return Err(DialogParseError::new(
+                    line,
+                    "a dialog node cannot mix texts and choices",
+                ));
+            }
+            node.dialog_type.push(dialog);
+        } else {
+            let token_end = line
+                .iter()
+                .position(|dialog_char| dialog_char.c == ' ' && !dialog_char.escaped)
+                .unwrap_or(line.len());
+
+            return Err(DialogParseError::new(
+                &line[..token_end],
+                "expected a header `#`, a dialog `-`, trigger events `->` or a jump `=>`",
+            ));
+        }
+    }
+
+    if headers.is_empty() {
+        return Err(DialogParseError {
+            line: 1,
+            column: 1,
+            token: String::new(),
+            message: "a dialog must start with a header: `# Author`".to_string(),
+        });
+    }
+
+    resolve_jumps(&mut graph, &labels, jumps)?;
+
+    Ok(graph)
+}
+
+/// Links each jump to its anchored node.
+///
+/// The jumps of a node go before its children:
+/// they are written before any child header.
+/// A header holding only a jump (no text, no trigger, no anchor) stands for its target.
+fn resolve_jumps(
+    graph: &mut DialogGraph,
+    labels: &HashMap<String, NodeId>,
+    jumps: Vec<(NodeId, String, Vec<DialogChar>)>,
+) -> Result<(), DialogParseError> {
+    // the number of jumps already linked to each node
+    let mut linked: HashMap<NodeId, usize> = HashMap::new();
+    for (node, label, line) in jumps {
+        let target = match labels.get(&label) {
+            Some(target) => *target,
+            None => {
+                return Err(DialogParseError::new(
+                    &line,
+                    "unknown label: no header is anchored `{#label}`",
+                ))
+            }
+        };
+
+        // the jumps of a node are linked before any written child
+        let position = linked.entry(node).or_default();
+        graph[node].children.insert(*position, target);
+        graph[node].jumps.push(*position);
+        *position += 1;
+    }
+
+    let mut only_jumps = Vec::new();
+    for (node, count) in linked {
+        let jump_node = &graph[node];
+        if count == 1
+            && jump_node.children.len() == 1
+            && jump_node.dialog_type.is_empty()
+            && jump_node.trigger_event.is_empty()
+            && jump_node.label.is_none()
+        {
+            if let Some(parent) = jump_node.parent {
+                let target = jump_node.children[0];
+                let parent_node = &mut graph[parent];
+                for (child_index, child) in parent_node.children.iter_mut().enumerate() {
+                    if *child == node {
+                        *child = target;
+                        parent_node.jumps.push(child_index);
+                    }
+                }
+                parent_node.jumps.sort_unstable();
+                only_jumps.push(node);
+            }
+        }
+    }
+    graph.remove(&only_jumps);
+
+    Ok(())
+}
+
+/// # Argument
+///
+/// * `s` - A string that holds a DialogTree
+///
+/// # Panics
+///
+/// The creation will panic
+/// if any argument to the process is not valid DialogTree format.
+/// Prefere parse_dialog() which returns the error instead.
+///
+/// See parse_dialog() for the conventions.
+pub fn init_tree_file(s: String) -> DialogGraph {
+    match parse_dialog(&s) {
+        Ok(graph) => graph,
+        Err(e) => panic!("Invalid DialogTree: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[allow(deprecated)]
+    mod flat {
+        use crate::ui::dialog_system::*;
+
+        #[test]
+        fn test_print_flat_child() {
+            let mut dialog = DialogNode::new();
+            dialog.dialog_type = vec![DialogType::Text(String::from("Hello"))];
+
+            let mut answers = DialogNode::new();
+            answers.dialog_type = vec![
+                DialogType::Choice {
+                    text: String::from("Hey"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+                DialogType::Choice {
+                    text: String::from("No Hello"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+                DialogType::Choice {
+                    text: String::from("Want to share a flat ?"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+            ];
+
+            let mut graph = DialogGraph::new(dialog);
+            graph.add_child(NodeId::ROOT, answers);
+
+            assert_eq!(
+                graph.print_flat(),
+                "[Hello]->[[Hey, No Hello, Want to share a flat ?]]".to_string()
+            );
+        }
+
+        #[test]
+        fn test_print_flat_children() {
+            let mut dialog = DialogNode::new();
+            dialog.dialog_type = vec![DialogType::Choice {
+                text: String::from("Hello"),
+                condition: None,
+                effects: vec![],
+                priority: 0,
+                weight: 1,
+            }];
+            // The Player
+            dialog.character = Some("Morgan".to_string());
+
+            let mut random_dialog = DialogNode::new();
+            random_dialog.dialog_type =
+                vec![DialogType::Text(String::from("I have to tell something"))];
+            // The npc
+            random_dialog.character = Some("Fabien".to_string());
+
+            let mut olf_no_longer_a_dj = DialogNode::new();
+            olf_no_longer_a_dj.dialog_type = vec![
+                DialogType::Text(String::from("You beat Olf !")),
+                DialogType::Text(String::from("Now you can chill at the hospis")),
+            ];
+            // The npc
+            olf_no_longer_a_dj.character = Some("Fabien".to_string());
+
+            let mut graph = DialogGraph::new(dialog);
+            graph.add_child(NodeId::ROOT, random_dialog);
+            graph.add_child(NodeId::ROOT, olf_no_longer_a_dj);
+
+            assert_eq!(
+        graph.print_flat(),
+        "[Hello]->[[I have to tell something]; [You beat Olf !, Now you can chill at the hospis]]"
+            .to_string()
+    );
+        }
+
+        #[test]
+        fn test_print_flat_complex() {
+            let mut dialog = DialogNode::new();
+            dialog.dialog_type = vec![DialogType::Text(String::from("Hello"))];
+
+            let mut answers = DialogNode::new();
+            answers.dialog_type = vec![
+                DialogType::Choice {
+                    text: String::from("Hey"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+                DialogType::Choice {
+                    text: String::from("No Hello"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+                DialogType::Choice {
+                    text: String::from("Want to share a flat ?"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+            ];
+
+            let mut dialog_2 = DialogNode::new();
+            dialog_2.dialog_type = vec![DialogType::Text(String::from(":)"))];
+
+            let mut dialog_3 = DialogNode::new();
+            dialog_3.dialog_type = vec![DialogType::Text(String::from(":O"))];
+
+            let mut dialog_4 = DialogNode::new();
+            dialog_4.dialog_type = vec![DialogType::Text(String::from("Sure"))];
+
+            let mut graph = DialogGraph::new(dialog);
+            let answers = graph.add_child(NodeId::ROOT, answers);
+
+            graph.add_child(answers, dialog_2);
+            graph.add_child(answers, dialog_3);
+            graph.add_child(answers, dialog_4);
+
+            assert_eq!(
+                graph.print_flat(),
+                "[Hello]->[[Hey, No Hello, Want to share a flat ?]->[[:)]; [:O]; [Sure]]]"
+                    .to_string()
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_simple_text_1() {
+            let graph = init_tree_flat(String::from("[Hello]"));
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("Hello".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_simple_text_2() {
+            let graph = init_tree_flat(String::from("[I want to talk]"));
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("I want to talk".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_simple_choice_1() {
+            // carefull with
+            let graph = init_tree_flat(String::from("[Let's talk,I don't wanna talk]"));
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Let's talk".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "I don't wanna talk".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_simple_choice_spaced() {
+            // carefull with
+            let graph = init_tree_flat(String::from("[Let's talk, I don't wanna talk]"));
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Let's talk".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "I don't wanna talk".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_famiglia_figlio_unico() {
+            // TODO: find out what the warning referred to
+            // carefull with ???
+            let graph = init_tree_flat(String::from(
+                "[Catchphrase]->[[I love you, Give me your wallet]]",
+            ));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("Catchphrase".to_string())]
+            );
+
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "I love you".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "Give me your wallet".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_famiglia() {
+            let graph = init_tree_flat(String::from(
+            "[Catchphrase]->[[I love you, Give me your wallet]->[[Me Too]; [Here all my chicken sandwich]]]",
+        ));
+
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: String::from("I love you"),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: String::from("Give me your wallet"),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+
+            assert_eq!(
+                graph[graph[graph[NodeId::ROOT].children[0]].children[0]].dialog_type,
+                vec![DialogType::Text("Me Too".to_string())]
+            );
+
+            assert_eq!(
+                graph[graph[graph[NodeId::ROOT].children[0]].children[1]].dialog_type,
+                vec![DialogType::Text("Here all my chicken sandwich".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_flat_complex_famiglia() {
+            // helped me see that accent wasn't include in the ascii char
+
+            let graph = init_tree_flat(String::from(
+            "[Il faut absolument sauver les Fabien du Chien Géant]->[[Il me faut donc obtenir le trône...]->[[...,et de l'argent]->[[Et de l'argent]->[[C'est essentiel]]];[C'est essentiel]]]",
+        ));
+
+            // root
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text(String::from(
+                    "Il faut absolument sauver les Fabien du Chien Géant"
+                ))]
+            );
+
+            // first and only child of root
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![DialogType::Text(String::from(
+                    "Il me faut donc obtenir le trône..."
+                ))]
+            );
+
+            // choose of the player
+            assert_eq!(
+                graph[graph[graph[NodeId::ROOT].children[0]].children[0]].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "...".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "et de l'argent".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+
+            // two possible outcome from the player's answer
+
+            // first (when the player don't say anything == "...")
+            assert_eq!(
+                graph[graph[graph[graph[NodeId::ROOT].children[0]].children[0]].children[0]]
+                    .dialog_type,
+                vec![DialogType::Text("Et de l'argent".to_string())]
+            );
+            // just after the npc said "Et de l'argent"
+            assert_eq!(
+                graph[graph[graph[graph[graph[NodeId::ROOT].children[0]].children[0]].children[0]]
+                    .children[0]]
+                    .dialog_type,
+                vec![DialogType::Text("C'est essentiel".to_string())]
+            );
+
+            // println!("{}", graph.print_flat());
+
+            // second
+            assert_eq!(
+                graph[graph[graph[NodeId::ROOT].children[0]].children[1]].dialog_type,
+                vec![DialogType::Text("C'est essentiel".to_string())]
+            );
+
+            // test print
+            assert_eq!(
+            graph.print_flat(),
+            "[Il faut absolument sauver les Fabien du Chien Géant]->[[Il me faut donc obtenir le trône...]->[[..., et de l'argent]->[[Et de l'argent]->[[C'est essentiel]]]; [C'est essentiel]]]".to_string()
+        );
+        }
+    }
+
+    mod from_file {
+        use crate::ui::dialog_system::*;
+
+        #[test]
+        fn test_print_from_file() {
+            let fabien = Some(String::from("Fabien"));
+            let morgan = Some(String::from("Morgan"));
+
+            let mut dialog = DialogNode::new();
+            dialog.dialog_type = vec![DialogType::Text(String::from("Hello"))];
+
+            dialog.character = fabien.clone();
+
+            let mut answers = DialogNode::new();
+            answers.dialog_type = vec![
+                DialogType::Choice {
+                    text: String::from("Hey"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+                DialogType::Choice {
+                    text: String::from("No Hello"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+                DialogType::Choice {
+                    text: String::from("Want to share a flat ?"),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 1,
+                },
+            ];
+
+            answers.character = morgan;
+
+            let mut dialog_2 = DialogNode::new();
+            dialog_2.dialog_type = vec![DialogType::Text(String::from(":)"))];
+            dialog_2.character = fabien.clone();
+
+            let mut dialog_3 = DialogNode::new();
+            dialog_3.dialog_type = vec![DialogType::Text(String::from(":O"))];
+            dialog_3.character = fabien.clone();
+
+            let mut dialog_4 = DialogNode::new();
+            dialog_4.dialog_type = vec![DialogType::Text(String::from("Sure"))];
+            dialog_4.character = fabien.clone();
+
+            let mut graph = DialogGraph::new(dialog);
+            let answers = graph.add_child(NodeId::ROOT, answers);
+
+            graph.add_child(answers, dialog_2);
+            graph.add_child(answers, dialog_3);
+            graph.add_child(answers, dialog_4);
+
+            // println!("{}", graph.print_flat());
+
+            assert_eq!(
+                graph.print_file(),
+                "# Fabien
+
+- Hello
+
+## Morgan
+
+- Hey | None
+- No Hello | None
+- Want to share a flat ? | None
+
+### Fabien
+
+- :)
+
+### Fabien
+
+- :O
+
+### Fabien
+
+- Sure\n"
+                    .to_string()
+            );
+        }
+
+        #[test]
+        fn test_print_from_file_monologue() {
+            let graph = init_tree_file(String::from(
+                "# Olf\n\n- Hello\n- Did you just\n- Call me ?\n- Or was it my imagination\n",
+            ));
+
+            assert_eq!(
+                graph.print_file(),
+                "# Olf\n\n- Hello\n- Did you just\n- Call me ?\n- Or was it my imagination\n"
+                    .to_string()
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_simple_text_1() {
+            let graph = init_tree_file(String::from("# Olf\n\n- Hello\n"));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Olf")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("Hello".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_space_overdose_1() {
+            let graph = init_tree_file(String::from("#            Olf\n\n-      Hello\n"));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Olf")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("Hello".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_space_overdose_2() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello         |   None\n- No Hello    | None\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "No Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_space_deficiency_1() {
+            let graph = init_tree_file(String::from("#Olf\n\n-Hello\n"));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Olf")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("Hello".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_space_deficiency_2() {
+            let graph = init_tree_file(String::from("# Morgan\n\n- Hello|None\n- No Hello|None\n"));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "No Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_monologue_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello\n- I was wondering\n-Alone...\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Text("Hello".to_string()),
+                    DialogType::Text("I was wondering".to_string()),
+                    DialogType::Text("Alone...".to_string())
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_simple_choice_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello | None\n- No Hello | None\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "No Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_complex_choice_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello | None\n- No Hello | k: -10,0;\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "No Hello".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: Some((-10, 0)),
+                            event: None,
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_complex_choice_2() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello | None\n- Mary me Hugo. | e: HasCharisma;\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "Mary me Hugo.".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: None,
+                            event: Some(vec![GameEvent::HasCharisma]),
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        // allow to explicit/implicit karma/k; event/e
+        // extend the tolerance
+
+        #[test]
+        fn test_init_tree_from_file_complex_choice_3() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello | k: -50,100;\n- No Hello | karma : -100,0;\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: Some((-50, 100)),
+                            event: None,
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "No Hello".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: Some((-100, 0)),
+                            event: None,
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_complex_choice_4() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello my Friend | e: HasFriend;\n- You droped this (*crown*) | event: HasCharisma;\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello my Friend".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: None,
+                            event: Some(vec![GameEvent::HasFriend]),
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "You droped this (*crown*)".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: None,
+                            event: Some(vec![GameEvent::HasCharisma]),
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        // allow to type MAX or MIN to select
+
+        #[test]
+        fn test_init_tree_from_file_complex_choice_karma_max_min() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello | k: -10,MAX;\n- No Hello | k: MIN,0;\n",
+            ));
+
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: Some((-10, KARMA_MAX)),
+                            event: None,
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "No Hello".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: Some((KARMA_MIN, 0)),
+                            event: None,
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn test_init_tree_from_file_simple_kinship_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello\n## Hugo\n- Hey! How are you ?\n",
+            ));
 
-            // println!("text inserted: {}", save);
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].character,
+                Some(String::from("Hugo"))
+            );
 
-            let dialog = DialogType::Text(save.clone());
-            current.borrow_mut().dialog_type.push(dialog);
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text("Hello".to_string())]
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![DialogType::Text("Hey! How are you ?".to_string())]
+            );
+        }
 
-            save.clear();
+        #[test]
+        fn test_init_tree_from_file_monologue_2() {
+            let graph = init_tree_file(String::from("# Morgan\n\n- Hello\n- I was wondering\n\n## Morgan\n\n- With Friends ! | event: HasFriend;\n- Alone... | None\n"));
 
-            content_phase = false;
-        } else if (*c == ',' || *c == '\n') && trigger_phase {
-            // println!("event : {}", event);
-            let e = event.parse::<ThrowableEvent>().unwrap();
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
 
-            // add the triggered event to the vector of the current DialogNode
-            current.borrow_mut().trigger_event.push(e);
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Text("Hello".to_string()),
+                    DialogType::Text("I was wondering".to_string())
+                ]
+            );
 
-            // println!("event cleared");
-            event.clear();
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].character,
+                Some(String::from("Morgan"))
+            );
 
-            if *c == '\n' {
-                trigger_phase = false;
-            }
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "With Friends !".to_string(),
+                        condition: Some(DialogCondition {
+                            karma_threshold: None,
+                            event: Some(vec![GameEvent::HasFriend]),
+                            expression: None
+                        }),
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "Alone...".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
         }
-        // read text or condition
-        else if c.is_ascii() || c.is_alphanumeric() {
-            // the except char is /
-            if *c == '/' {
-                info!("except");
-                except = true;
-            }
-            // `;` or `\n` put an end to the selection of condtion
-            else if condition_phase {
-                if *c == 'k' {
-                    karma_phase = true;
-                    post_colon = true;
-                } else if *c == 'e' && !event_phase {
-                    event_phase = true;
-                    post_colon = true;
-                } else if *c == ':' && (event_phase || karma_phase) {
-                    post_colon = false;
-                }
-                // c.is_numeric() ||
-                // authorize MAX or MIN input
-                else if karma_phase && !post_colon && *c != ' '
-                // || *c == '-'
-                {
-                    // negative symbol -
-                    karma.push(*c);
-
-                    println!("k: {}", karma);
-                } else if event_phase && *c != ' ' && !post_colon {
-                    event.push(*c);
-
-                    // println!("e: {}", event);
-                }
-            } else if trigger_phase && *c != ' ' {
-                event.push(*c);
-            }
-            // ignore the new line: "\n"
-            // the \n is a marker to end some phase
-            else if *c == '\n' && !except {
-                // new_line = true;
-                // println!("skip");
 
-                // full reset
+        #[test]
+        fn test_init_tree_from_file_complex_kinship_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Hello | None\n- Do you want to work with me ? | None\n\n## Hugo\n\n- Hey! How are you ?\n\n## Hugo\n\n- I'm sure you'll do just fine without me.\n",
+            ));
 
-                dialog_type = DialogType::new_text();
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Hello".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "Do you want to work with me ?".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
+            );
 
-                author_phase = false;
-                content_phase = false;
-                // useless protection cause by if condition_phase just above
-                // condition_phase = false;
-            } else if author_phase && *c != '#' {
-                author.push(*c);
-            }
-            // if !is_special_char_file(*c) || (is_special_char_file(*c) && except)
-            else {
-                save.push(*c);
-                except = false;
+            println!("{}", graph.print_file());
 
-                // println!("add {} ", *c);
-            }
-        }
-    }
+            // By choosing the n-eme choice, you will get the result of the n-eme child.
 
-    return root;
-}
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].character,
+                Some(String::from("Hugo"))
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![DialogType::Text("Hey! How are you ?".to_string())]
+            );
 
-#[cfg(test)]
-mod tests {
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[1]].character,
+                Some(String::from("Hugo"))
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[1]].dialog_type,
+                vec![DialogType::Text(
+                    "I'm sure you'll do just fine without me.".to_string()
+                )]
+            );
+        }
 
-    #[allow(deprecated)]
-    mod flat {
-        use crate::ui::dialog_system::*;
+        #[test]
+        fn test_init_tree_from_file_throwable_events() {
+            let graph = init_tree_file(String::from(
+                "# Hugo\n\n- Let's go together\n\n-> HasFriend, FightEvent\n",
+            ));
+
+            assert_eq!(
+                graph[NodeId::ROOT].trigger_event,
+                vec![ThrowableEvent::HasFriend, ThrowableEvent::FightEvent]
+            );
+        }
 
         #[test]
-        fn test_print_flat_child() {
-            let dialog = Rc::new(RefCell::new(DialogNode::new()));
-            dialog.borrow_mut().dialog_type = vec![DialogType::Text(String::from("Hello"))];
+        fn test_init_tree_from_file_throwable_events_with_arguments() {
+            let graph = init_tree_file(String::from(
+                "# Olf\n\n- Take it\n\n-> Karma(+5), GiveItem(potion, 2), JoinTeam(TEAM_MC), FightEvent\n",
+            ));
 
-            let answers = Rc::new(RefCell::new(DialogNode::new()));
-            answers.borrow_mut().dialog_type = vec![
-                DialogType::Choice {
-                    text: String::from("Hey"),
-                    condition: None,
-                },
-                DialogType::Choice {
-                    text: String::from("No Hello"),
-                    condition: None,
+            assert_eq!(
+                graph[NodeId::ROOT].trigger_event,
+                vec![
+                    ThrowableEvent::Karma(5),
+                    ThrowableEvent::GiveItem {
+                        item: "potion".to_string(),
+                        amount: 2
+                    },
+                    ThrowableEvent::JoinTeam(TEAM_MC),
+                    ThrowableEvent::FightEvent,
+                ]
+            );
+        }
+
+        #[test]
+        fn test_throwable_event_display_from_str() {
+            for event in [
+                ThrowableEvent::HasFriend,
+                ThrowableEvent::Karma(-10),
+                ThrowableEvent::Karma(3),
+                ThrowableEvent::GiveItem {
+                    item: "coin".to_string(),
+                    amount: 10,
                 },
-                DialogType::Choice {
-                    text: String::from("Want to share a flat ?"),
-                    condition: None,
+                ThrowableEvent::GiveItem {
+                    item: "salt, (pepper)".to_string(),
+                    amount: 1,
                 },
-            ];
+                ThrowableEvent::JoinTeam(TEAM_OLF),
+                ThrowableEvent::JoinTeam(42),
+            ] {
+                assert_eq!(event.to_string().parse::<ThrowableEvent>(), Ok(event));
+            }
+        }
 
-            dialog.borrow_mut().add_child(answers);
+        #[test]
+        fn test_init_tree_from_file_throwable_event_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Let's Talk | None\n- Let's Fight | None\n\n## Hugo\n\n- :)\n\n-> HasFriend\n\n## Hugo\n\n- :(\n\n-> FightEvent\n",
+            ));
 
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
             assert_eq!(
-                dialog.borrow().print_flat(),
-                "[Hello]->[[Hey, No Hello, Want to share a flat ?]]".to_string()
+                graph[NodeId::ROOT].dialog_type,
+                vec![
+                    DialogType::Choice {
+                        text: "Let's Talk".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    },
+                    DialogType::Choice {
+                        text: "Let's Fight".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 1
+                    }
+                ]
             );
-        }
 
-        #[test]
-        fn test_print_flat_children() {
-            let dialog = Rc::new(RefCell::new(DialogNode::new()));
-            dialog.borrow_mut().dialog_type = vec![DialogType::Choice {
-                text: String::from("Hello"),
-                condition: None,
-            }];
-            // The Player
-            dialog.borrow_mut().character = Some((0b0000001u32, "Morgan".to_string()));
+            println!("{}", graph.print_file());
 
-            let random_dialog = Rc::new(RefCell::new(DialogNode::new()));
-            random_dialog.borrow_mut().dialog_type =
-                vec![DialogType::Text(String::from("I have to tell something"))];
-            // The npc
-            random_dialog.borrow_mut().character = Some((0b0000010u32, "Fabien".to_string()));
-            dialog.borrow_mut().add_child(random_dialog);
+            // By choosing the n-eme choice, you will get the result of the n-eme child.
 
-            let olf_no_longer_a_dj = Rc::new(RefCell::new(DialogNode::new()));
-            olf_no_longer_a_dj.borrow_mut().dialog_type = vec![
-                DialogType::Text(String::from("You beat Olf !")),
-                DialogType::Text(String::from("Now you can chill at the hospis")),
-            ];
-            // The npc
-            olf_no_longer_a_dj.borrow_mut().character = Some((0b0000010u32, "Fabien".to_string()));
-            dialog.borrow_mut().add_child(olf_no_longer_a_dj);
+            // first child
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].character,
+                Some(String::from("Hugo"))
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].dialog_type,
+                vec![DialogType::Text(":)".to_string())]
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[0]].trigger_event,
+                vec![ThrowableEvent::HasFriend]
+            );
 
+            // second child
             assert_eq!(
-        dialog.borrow().print_flat(),
-        "[Hello]->[[I have to tell something]; [You beat Olf !, Now you can chill at the hospis]]"
-            .to_string()
-    );
+                graph[graph[NodeId::ROOT].children[1]].character,
+                Some(String::from("Hugo"))
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[1]].dialog_type,
+                vec![DialogType::Text(":(".to_string())]
+            );
+            assert_eq!(
+                graph[graph[NodeId::ROOT].children[1]].trigger_event,
+                vec![ThrowableEvent::FightEvent]
+            );
         }
 
         #[test]
-        fn test_print_flat_complex() {
-            let dialog = Rc::new(RefCell::new(DialogNode::new()));
-            dialog.borrow_mut().dialog_type = vec![DialogType::Text(String::from("Hello"))];
+        fn test_init_tree_from_file_except_1() {
+            let graph = init_tree_file(String::from(
+                "# Morgan\n\n- Bonjour Florian. /\nComment vas/-tu :/# ? /\nJ'ai faim. /<3 /</|3\n",
+            ));
 
-            let answers = Rc::new(RefCell::new(DialogNode::new()));
-            answers.borrow_mut().dialog_type = vec![
-                DialogType::Choice {
-                    text: String::from("Hey"),
-                    condition: None,
-                },
-                DialogType::Choice {
-                    text: String::from("No Hello"),
-                    condition: None,
-                },
-                DialogType::Choice {
-                    text: String::from("Want to share a flat ?"),
-                    condition: None,
-                },
-            ];
+            assert_eq!(graph[NodeId::ROOT].character, Some(String::from("Morgan")));
+
+            assert_eq!(
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text(
+                    "Bonjour Florian. \nComment vas-tu :# ? \nJ'ai faim. <3 <|3".to_string()
+                )]
+            );
+        }
+    }
 
-            let dialog_2 = Rc::new(RefCell::new(DialogNode::new()));
-            dialog_2.borrow_mut().dialog_type = vec![DialogType::Text(String::from(":)"))];
+    mod graph {
+        use crate::ui::dialog_system::*;
 
-            let dialog_3 = Rc::new(RefCell::new(DialogNode::new()));
-            dialog_3.borrow_mut().dialog_type = vec![DialogType::Text(String::from(":O"))];
+        const FABIEN: &str = "# Fabien\n\n- Hello\n\n## Morgan\n\n- Hey | None\n- No Hello | None\n\n### Fabien\n\n- :)\n\n### Fabien\n\n- :O\n";
 
-            let dialog_4 = Rc::new(RefCell::new(DialogNode::new()));
-            dialog_4.borrow_mut().dialog_type = vec![DialogType::Text(String::from("Sure"))];
+        #[test]
+        fn test_add_child_links() {
+            let mut graph = DialogGraph::default();
+            let child = graph.add_child(NodeId::ROOT, DialogNode::new());
 
-            answers.borrow_mut().add_child(dialog_2);
-            answers.borrow_mut().add_child(dialog_3);
-            answers.borrow_mut().add_child(dialog_4);
+            assert_eq!(graph[NodeId::ROOT].children, vec![child]);
+            assert_eq!(graph[child].parent, Some(NodeId::ROOT));
+        }
 
-            dialog.borrow_mut().add_child(answers);
+        #[test]
+        fn test_subgraph() {
+            let graph = init_tree_file(String::from(FABIEN));
+            let answers = graph[NodeId::ROOT].children[0];
 
             assert_eq!(
-                dialog.borrow().print_flat(),
-                "[Hello]->[[Hey, No Hello, Want to share a flat ?]->[[:)]; [:O]; [Sure]]]"
+                graph.subgraph(answers).print_file(),
+                "# Morgan\n\n- Hey | None\n- No Hello | None\n\n## Fabien\n\n- :)\n\n## Fabien\n\n- :O\n"
                     .to_string()
             );
+            assert_eq!(graph.subgraph(NodeId::ROOT), graph);
         }
 
         #[test]
-        fn test_init_tree_flat_simple_text_1() {
-            let root = init_tree_flat(String::from("[Hello]"));
+        fn test_cursor_dive() {
+            let graph = init_tree_file(String::from(FABIEN));
+            let mut cursor = DialogCursor::new();
+
+            let answers = cursor.dive(&graph, 0);
+            assert_eq!(answers, Some(graph[NodeId::ROOT].children[0]));
+
+            let sad_face = cursor.dive(&graph, 1).unwrap();
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("Hello".to_string())]
+                cursor.current(&graph).unwrap().dialog_type,
+                vec![DialogType::Text(":O".to_string())]
             );
+            assert!(graph[sad_face].is_end_node());
+
+            // a leaf has no child: the dialog is ended
+            assert_eq!(cursor.dive(&graph, 0), None);
+            assert!(cursor.is_ended());
+            // but the branch taken is kept
+            assert_eq!(cursor.path, vec![0, 1]);
         }
 
         #[test]
-        fn test_init_tree_flat_simple_text_2() {
-            let root = init_tree_flat(String::from("[I want to talk]"));
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("I want to talk".to_string())]
-            );
+        fn test_cursor_dive_missing_child() {
+            let graph = init_tree_file(String::from(FABIEN));
+            let mut cursor = DialogCursor::new();
+
+            assert_eq!(cursor.dive(&graph, 3), None);
+            assert_eq!(cursor, DialogCursor::ended());
         }
 
         #[test]
-        fn test_init_tree_flat_simple_choice_1() {
-            // carefull with
-            let root = init_tree_flat(String::from("[Let's talk,I don't wanna talk]"));
+        fn test_cursor_rewind() {
+            let graph = init_tree_file(String::from(FABIEN));
+            let mut cursor = DialogCursor::new();
+            let answers = graph[NodeId::ROOT].children[0];
+
+            cursor.dive(&graph, 0);
+            let smile = cursor.dive(&graph, 0);
+            assert_eq!(smile, Some(graph[answers].children[0]));
+
+            // an ended cursor goes back to the last node reached
+            cursor.end();
+            assert_eq!(cursor.rewind(&graph), smile);
+
+            assert_eq!(cursor.rewind(&graph), Some(answers));
+            assert_eq!(cursor.path, vec![0]);
+
+            assert_eq!(cursor.rewind(&graph), Some(NodeId::ROOT));
+            // nothing before the root
+            assert_eq!(cursor.rewind(&graph), Some(NodeId::ROOT));
+            assert_eq!(cursor, DialogCursor::new());
+        }
+
+        #[test]
+        fn test_cursor_restart() {
+            let graph = init_tree_file(String::from(FABIEN));
+            let mut cursor = DialogCursor::new();
+
+            cursor.dive(&graph, 0);
+            cursor.dive(&graph, 1);
+            cursor.restart();
+
+            assert_eq!(cursor, DialogCursor::new());
+            assert_eq!(cursor.current(&graph), Some(&graph[NodeId::ROOT]));
+        }
+
+        #[test]
+        fn test_cursor_is_valid() {
+            let graph = init_tree_file(String::from(FABIEN));
+            let mut cursor = DialogCursor::new();
+            cursor.dive(&graph, 0);
+            cursor.dive(&graph, 1);
+            assert!(cursor.is_valid(&graph));
+
+            // the second answer of Fabien has been removed from the file
+            let modified = init_tree_file(String::from(
+                "# Fabien\n\n- Hello\n\n## Morgan\n\n- Hey | None\n\n### Fabien\n\n- :)\n",
+            ));
+            assert!(!cursor.is_valid(&modified));
+            assert_eq!(DialogCursor::follow(&modified, &cursor.path), None);
+        }
+    }
+
+    mod conditions {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        use crate::{ui::dialog_system::*, world_flags::WorldFlags};
+
+        #[test]
+        fn test_is_verified_karma() {
+            let condition = DialogCondition {
+                karma_threshold: Some((-10, 0)),
+                event: None,
+                expression: None,
+            };
+
+            assert!(condition.is_verified(&ConditionContext::new(-10, &WorldFlags::default())));
+            assert!(!condition.is_verified(&ConditionContext::new(1, &WorldFlags::default())));
+        }
+
+        #[test]
+        fn test_is_verified_events() {
+            let condition = DialogCondition {
+                karma_threshold: None,
+                event: Some(vec![GameEvent::HasFriend, GameEvent::FirstKill]),
+                expression: None,
+            };
+            let mut world_flags = WorldFlags::default();
+
+            assert!(!condition.is_verified(&ConditionContext::new(0, &world_flags)));
+
+            world_flags.raise(GameEvent::HasFriend);
+            assert!(!condition.is_verified(&ConditionContext::new(0, &world_flags)));
+
+            world_flags.raise(GameEvent::FirstKill);
+            assert!(condition.is_verified(&ConditionContext::new(0, &world_flags)));
+        }
+
+        #[test]
+        fn test_is_verified_karma_and_events() {
+            let condition = DialogCondition {
+                karma_threshold: Some((10, KARMA_MAX)),
+                event: Some(vec![GameEvent::HasCharisma]),
+                expression: None,
+            };
+            let mut world_flags = WorldFlags::default();
+            world_flags.raise(GameEvent::HasCharisma);
+
+            assert!(condition.is_verified(&ConditionContext::new(10, &world_flags)));
+            assert!(!condition.is_verified(&ConditionContext::new(0, &world_flags)));
+        }
+
+        #[test]
+        fn test_parse_priority_and_weight() {
+            let graph = parse_dialog("# Olf\n\n- Hey | p: 2; w: 3;\n- Hi | weight: 5;\n").unwrap();
+
             assert_eq!(
-                root.borrow().dialog_type,
+                graph[NodeId::ROOT].dialog_type,
                 vec![
                     DialogType::Choice {
-                        text: "Let's talk".to_string(),
-                        condition: None
+                        text: "Hey".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 2,
+                        weight: 3
                     },
                     DialogType::Choice {
-                        text: "I don't wanna talk".to_string(),
-                        condition: None
+                        text: "Hi".to_string(),
+                        condition: None,
+                        effects: vec![],
+                        priority: 0,
+                        weight: 5
                     }
                 ]
             );
         }
 
         #[test]
-        fn test_init_tree_flat_simple_choice_spaced() {
-            // carefull with
-            let root = init_tree_flat(String::from("[Let's talk, I don't wanna talk]"));
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Let's talk".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "I don't wanna talk".to_string(),
-                        condition: None
-                    }
-                ]
-            );
+        fn test_pick_choice_priority() {
+            let graph = parse_dialog(
+                "# Olf\n\n- Hello | None\n- You killed someone! | e: FirstKill; p: 10;\n- Nice weather | w: 50;\n",
+            )
+            .unwrap();
+            let mut rng = StdRng::seed_from_u64(42);
+            let mut world_flags = WorldFlags::default();
+
+            for _ in 0..20 {
+                let pick = graph[NodeId::ROOT]
+                    .pick_choice(&ConditionContext::new(0, &world_flags), &mut rng);
+                assert!(pick == Some(0) || pick == Some(2));
+            }
+
+            // the important event takes over the ambiance
+            world_flags.raise(GameEvent::FirstKill);
+            for _ in 0..20 {
+                assert_eq!(
+                    graph[NodeId::ROOT]
+                        .pick_choice(&ConditionContext::new(0, &world_flags), &mut rng),
+                    Some(1)
+                );
+            }
+        }
+
+        #[test]
+        fn test_pick_choice_weight() {
+            let graph = parse_dialog("# Olf\n\n- Rare | w: 1;\n- Common | w: 99;\n").unwrap();
+            let mut rng = StdRng::seed_from_u64(7);
+
+            let picks: Vec<Option<usize>> = (0..100)
+                .map(|_| {
+                    graph[NodeId::ROOT]
+                        .pick_choice(&ConditionContext::new(0, &WorldFlags::default()), &mut rng)
+                })
+                .collect();
+            let common = picks.iter().filter(|pick| **pick == Some(1)).count();
+            assert!(common > 80);
+            assert!(picks.iter().all(|pick| pick.is_some()));
+
+            // seedable
+            let mut first_rng = StdRng::seed_from_u64(1234);
+            let mut second_rng = StdRng::seed_from_u64(1234);
+            for _ in 0..20 {
+                assert_eq!(
+                    graph[NodeId::ROOT].pick_choice(
+                        &ConditionContext::new(0, &WorldFlags::default()),
+                        &mut first_rng
+                    ),
+                    graph[NodeId::ROOT].pick_choice(
+                        &ConditionContext::new(0, &WorldFlags::default()),
+                        &mut second_rng
+                    )
+                );
+            }
+        }
+
+        #[test]
+        fn test_pick_choice_none_verified() {
+            let graph = parse_dialog("# Olf\n\n- Hey | k: 50,MAX;\n").unwrap();
+            let mut rng = StdRng::seed_from_u64(0);
+
+            assert_eq!(
+                graph[NodeId::ROOT]
+                    .pick_choice(&ConditionContext::new(0, &WorldFlags::default()), &mut rng),
+                None
+            );
+        }
+
+        #[test]
+        fn test_pick_choice_huge_weights() {
+            let graph = parse_dialog(&format!(
+                "# Olf\n\n- Hey | w: {max};\n- Hi | w: {max};\n",
+                max = u32::MAX
+            ))
+            .unwrap();
+            let mut rng = StdRng::seed_from_u64(3);
+
+            for _ in 0..20 {
+                assert!(graph[NodeId::ROOT]
+                    .pick_choice(&ConditionContext::new(0, &WorldFlags::default()), &mut rng)
+                    .is_some());
+            }
         }
 
         #[test]
-        fn test_init_tree_flat_famiglia_figlio_unico() {
-            // TODO: find out what the warning referred to
-            // carefull with ???
-            let root = init_tree_flat(String::from(
-                "[Catchphrase]->[[I love you, Give me your wallet]]",
-            ));
+        fn test_pick_choice_without_weight() {
+            let mut node = DialogNode::new();
+            node.dialog_type = ["Hey", "Hi"]
+                .iter()
+                .map(|text| DialogType::Choice {
+                    text: text.to_string(),
+                    condition: None,
+                    effects: vec![],
+                    priority: 0,
+                    weight: 0,
+                })
+                .collect();
+            let mut rng = StdRng::seed_from_u64(3);
 
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("Catchphrase".to_string())]
+                node.pick_choice(&ConditionContext::new(0, &WorldFlags::default()), &mut rng),
+                Some(0)
             );
+        }
 
-            assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "I love you".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "Give me your wallet".to_string(),
-                        condition: None
-                    }
-                ]
-            );
+        #[test]
+        fn test_parse_invalid_weight() {
+            let error = parse_dialog("# Olf\n\n- Hey | w: 0;\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (3, 9));
+        }
+    }
+
+    mod interpolation {
+        use crate::ui::dialog_system::*;
+
+        fn context() -> DialogContext {
+            DialogContext {
+                player_name: "Morgan".to_string(),
+                karma: -5,
+                party_size: 3,
+                npc_name: "Olf".to_string(),
+            }
         }
 
         #[test]
-        fn test_init_tree_flat_famiglia() {
-            let root = init_tree_flat(String::from(
-            "[Catchphrase]->[[I love you, Give me your wallet]->[[Me Too]; [Here all my chicken sandwich]]]",
-        ));
+        fn test_interpolate_variables() {
+            let graph = parse_dialog(
+                "# Olf\n\n- Hello {player.name}, I am {npc.name}\n- Your karma: {karma}, your party: {party.size}\n",
+            )
+            .unwrap();
+
+            let texts: Vec<String> = graph[NodeId::ROOT]
+                .dialog_type
+                .iter()
+                .map(|dialog| match dialog {
+                    DialogType::Text(text) => interpolate(text, &context()),
+                    DialogType::Choice { text, .. } => interpolate(text, &context()),
+                })
+                .collect();
 
             assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
+                texts,
                 vec![
-                    DialogType::Choice {
-                        text: String::from("I love you"),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: String::from("Give me your wallet"),
-                        condition: None
-                    }
+                    "Hello Morgan, I am Olf".to_string(),
+                    "Your karma: -5, your party: 3".to_string()
                 ]
             );
+        }
+
+        #[test]
+        fn test_interpolate_escaped_braces() {
+            let graph = parse_dialog("# Olf\n\n- /{name/} is {player.name} /{{karma}/}\n").unwrap();
 
             assert_eq!(
-                root.borrow().children[0].borrow().children[0]
-                    .borrow()
-                    .dialog_type,
-                vec![DialogType::Text("Me Too".to_string())]
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Text(
+                    "{name} is {player.name} {{karma}}".to_string()
+                )]
             );
-
+            match &graph[NodeId::ROOT].dialog_type[0] {
+                DialogType::Text(text) => {
+                    assert_eq!(interpolate(text, &context()), "{name} is Morgan {-5}")
+                }
+                _ => unreachable!(),
+            }
             assert_eq!(
-                root.borrow().children[0].borrow().children[1]
-                    .borrow()
-                    .dialog_type,
-                vec![DialogType::Text("Here all my chicken sandwich".to_string())]
+                graph.print_file(),
+                "# Olf\n\n- /{name/} is {player.name} /{{karma}/}\n"
             );
         }
 
         #[test]
-        fn test_init_tree_flat_complex_famiglia() {
-            // helped me see that accent wasn't include in the ascii char
+        fn test_escaped_variable() {
+            let error = parse_dialog("# Olf\n\n- I am /{player.name/}\n").unwrap_err();
 
-            let root = init_tree_flat(String::from(
-            "[Il faut absolument sauver les Fabien du Chien Géant]->[[Il me faut donc obtenir le trône...]->[[...,et de l'argent]->[[Et de l'argent]->[[C'est essentiel]]];[C'est essentiel]]]",
-        ));
+            assert_eq!((error.line, error.column), (3, 8));
+            assert_eq!(error.token, "{player.name}".to_string());
+        }
 
-            // root
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text(String::from(
-                    "Il faut absolument sauver les Fabien du Chien Géant"
-                ))]
-            );
+        #[test]
+        fn test_interpolate_choice() {
+            let graph = parse_dialog("# Morgan\n\n- I'm {player.name} | None\n").unwrap();
 
-            // first and only child of root
-            assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
-                vec![DialogType::Text(String::from(
-                    "Il me faut donc obtenir le trône..."
-                ))]
-            );
+            match &graph[NodeId::ROOT].dialog_type[0] {
+                DialogType::Choice { text, .. } => {
+                    assert_eq!(interpolate(text, &context()), "I'm Morgan")
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
 
-            // choose of the player
+    mod effects {
+        use crate::ui::dialog_system::*;
+
+        const INSULT: &str = "# Olf\n\n- Give me your money\n\n## Morgan\n\n- Insult him | None | karma: -10;\n- Pay | k: 10,MAX; | karma: +5; mana: -5;\n- Fight | None\n";
+
+        #[test]
+        fn test_parse_effects() {
+            let graph = parse_dialog(INSULT).unwrap();
+            let answers = &graph[graph[NodeId::ROOT].children[0]];
+
+            let effects: Vec<Vec<ChoiceEffect>> = answers
+                .dialog_type
+                .iter()
+                .map(|dialog| match dialog {
+                    DialogType::Choice { effects, .. } => effects.clone(),
+                    DialogType::Text(_) => vec![],
+                })
+                .collect();
             assert_eq!(
-                root.borrow().children[0].borrow().children[0]
-                    .borrow()
-                    .dialog_type,
+                effects,
                 vec![
-                    DialogType::Choice {
-                        text: "...".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "et de l'argent".to_string(),
-                        condition: None
-                    }
+                    vec![ChoiceEffect::Karma(-10)],
+                    vec![ChoiceEffect::Karma(5), ChoiceEffect::Mana(-5)],
+                    vec![],
                 ]
             );
+        }
 
-            // two possible outcome from the player's answer
+        #[test]
+        fn test_parse_effect_without_semicolon() {
+            let graph = parse_dialog("# Morgan\n\n- Insult him | None | hp: -1\n").unwrap();
 
-            // first (when the player don't say anything == "...")
-            assert_eq!(
-                root.borrow().children[0].borrow().children[0]
-                    .borrow()
-                    .children[0]
-                    .borrow()
-                    .dialog_type,
-                vec![DialogType::Text("Et de l'argent".to_string())]
-            );
-            // just after the npc said "Et de l'argent"
             assert_eq!(
-                root.borrow().children[0].borrow().children[0]
-                    .borrow()
-                    .children[0]
-                    .borrow()
-                    .children[0]
-                    .borrow()
-                    .dialog_type,
-                vec![DialogType::Text("C'est essentiel".to_string())]
+                graph[NodeId::ROOT].dialog_type,
+                vec![DialogType::Choice {
+                    text: "Insult him".to_string(),
+                    condition: None,
+                    effects: vec![ChoiceEffect::Hp(-1)],
+                    priority: 0,
+                    weight: 1,
+                }]
             );
+        }
 
-            // println!("{}", root.borrow().print_flat());
+        #[test]
+        fn test_print_file_effects() {
+            let graph = parse_dialog(INSULT).unwrap();
 
-            // second
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
+            assert!(graph
+                .print_file()
+                .contains("- Insult him | None | karma: -10;\n"));
+        }
+
+        #[test]
+        fn test_parse_invalid_effects() {
+            let error =
+                parse_dialog("# Morgan\n\n- Insult him | None | charisma: -10;\n").unwrap_err();
+            assert_eq!((error.line, error.column), (3, 23));
             assert_eq!(
-                root.borrow().children[0].borrow().children[1]
-                    .borrow()
-                    .dialog_type,
-                vec![DialogType::Text("C'est essentiel".to_string())]
+                error.message,
+                "unknown effect, expected `karma`, `hp` or `mana`".to_string()
             );
 
-            // test print
-            assert_eq!(
-            root.borrow().print_flat(),
-            "[Il faut absolument sauver les Fabien du Chien Géant]->[[Il me faut donc obtenir le trône...]->[[..., et de l'argent]->[[Et de l'argent]->[[C'est essentiel]]]; [C'est essentiel]]]".to_string()
-        );
+            let error =
+                parse_dialog("# Morgan\n\n- Insult him | None | karma: a lot;\n").unwrap_err();
+            assert_eq!(error.token, "karma: a lot".to_string());
+
+            let error = parse_dialog("# Morgan\n\n- Insult him | None | karma: -10; | hp: -1;\n")
+                .unwrap_err();
+            assert_eq!((error.line, error.column), (3, 1));
         }
     }
 
-    mod from_file {
-        use crate::ui::dialog_system::*;
+    mod expressions {
+        use crate::{
+            ui::{
+                dialog_condition::{Comparison, ConditionContext, ConditionExpr, Operand},
+                dialog_system::*,
+            },
+            world_flags::{GameEvent, WorldFlags},
+        };
 
-        #[test]
-        fn test_print_from_file() {
-            let fabien = Some((0, String::from("Fabien")));
-            let morgan = Some((1, String::from("Morgan")));
+        const FIGHT: &str = "# Morgan\n\n- Fight | karma >= 10 && (event: BeatTheGame || party.has(Hugo)) && !event: FirstKill;\n- Flee | hp < 0.5; priority: 1;\n";
 
-            let dialog = Rc::new(RefCell::new(DialogNode::new()));
-            dialog.borrow_mut().dialog_type = vec![DialogType::Text(String::from("Hello"))];
+        fn first_condition(graph: &DialogGraph) -> DialogCondition {
+            match &graph[NodeId::ROOT].dialog_type[0] {
+                DialogType::Choice {
+                    condition: Some(condition),
+                    ..
+                } => condition.clone(),
+                _ => panic!("expected a choice with a condition"),
+            }
+        }
 
-            dialog.borrow_mut().character = fabien.clone();
+        #[test]
+        fn test_parse_expression() {
+            let graph = parse_dialog(FIGHT).unwrap();
 
-            let answers = Rc::new(RefCell::new(DialogNode::new()));
-            answers.borrow_mut().dialog_type = vec![
-                DialogType::Choice {
-                    text: String::from("Hey"),
-                    condition: None,
-                },
-                DialogType::Choice {
-                    text: String::from("No Hello"),
-                    condition: None,
-                },
+            assert_eq!(
+                first_condition(&graph).expression(),
+                Some(&ConditionExpr::And(
+                    Box::new(ConditionExpr::And(
+                        Box::new(ConditionExpr::Compare(
+                            Operand::Karma,
+                            Comparison::GreaterOrEqual,
+                            10.
+                        )),
+                        Box::new(ConditionExpr::Or(
+                            Box::new(ConditionExpr::Event(GameEvent::BeatTheGame)),
+                            Box::new(ConditionExpr::PartyHas(String::from("Hugo"))),
+                        )),
+                    )),
+                    Box::new(ConditionExpr::Not(Box::new(ConditionExpr::Event(
+                        GameEvent::FirstKill
+                    )))),
+                ))
+            );
+
+            // the `||` does not split the choice
+            assert_eq!(graph[NodeId::ROOT].dialog_type.len(), 2);
+            match &graph[NodeId::ROOT].dialog_type[1] {
                 DialogType::Choice {
-                    text: String::from("Want to share a flat ?"),
-                    condition: None,
-                },
-            ];
+                    text,
+                    condition: Some(condition),
+                    priority,
+                    ..
+                } => {
+                    assert_eq!(text, "Flee");
+                    assert_eq!(*priority, 1);
+                    assert!(condition.expression().is_some());
+                }
+                _ => panic!("expected a choice with a condition"),
+            }
+        }
+
+        #[test]
+        fn test_expression_is_verified() {
+            let condition = first_condition(&parse_dialog(FIGHT).unwrap());
+            let world_flags = WorldFlags::default();
+
+            assert!(!condition.is_verified(&ConditionContext::new(10, &world_flags)));
+            assert!(condition.is_verified(&ConditionContext {
+                party: vec![String::from("Hugo")],
+                ..ConditionContext::new(10, &world_flags)
+            }));
+            assert!(!condition.is_verified(&ConditionContext {
+                party: vec![String::from("Hugo")],
+                ..ConditionContext::new(9, &world_flags)
+            }));
+        }
 
-            answers.borrow_mut().character = morgan;
+        #[test]
+        fn test_expression_clauses_and_items() {
+            let graph = parse_dialog(
+                "# Morgan\n\n- Pay | event: HasFriend; item(coin) >= 3; party.size > 1;\n",
+            )
+            .unwrap();
+            let condition = first_condition(&graph);
+
+            assert_eq!(condition.event, Some(vec![GameEvent::HasFriend]));
+            assert_eq!(
+                condition
+                    .expression()
+                    .map(|expression| expression.to_string()),
+                Some(String::from("item(coin) >= 3 && party.size > 1"))
+            );
+        }
 
-            let dialog_2 = Rc::new(RefCell::new(DialogNode::new()));
-            dialog_2.borrow_mut().dialog_type = vec![DialogType::Text(String::from(":)"))];
-            dialog_2.borrow_mut().character = fabien.clone();
+        #[test]
+        fn test_print_file_expression() {
+            let graph = parse_dialog(FIGHT).unwrap();
 
-            let dialog_3 = Rc::new(RefCell::new(DialogNode::new()));
-            dialog_3.borrow_mut().dialog_type = vec![DialogType::Text(String::from(":O"))];
-            dialog_3.borrow_mut().character = fabien.clone();
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
+            assert!(graph.print_file().contains(
+                "- Fight | karma >= 10 && (event: BeatTheGame || party.has(Hugo)) && !event: FirstKill;\n"
+            ));
+        }
 
-            let dialog_4 = Rc::new(RefCell::new(DialogNode::new()));
-            dialog_4.borrow_mut().dialog_type = vec![DialogType::Text(String::from("Sure"))];
-            dialog_4.borrow_mut().character = fabien.clone();
+        #[test]
+        fn test_escaped_arguments() {
+            let graph = parse_dialog(
+                "# Morgan\n\n- Pay | party.has(Fabicurion /(2/)) && item(a/)b/;c) > 0;\n",
+            )
+            .unwrap();
+            let expression = ConditionExpr::And(
+                Box::new(ConditionExpr::PartyHas(String::from("Fabicurion (2)"))),
+                Box::new(ConditionExpr::Compare(
+                    Operand::Item(String::from("a)b;c")),
+                    Comparison::Greater,
+                    0.,
+                )),
+            );
 
-            answers.borrow_mut().add_child(dialog_2);
-            answers.borrow_mut().add_child(dialog_3);
-            answers.borrow_mut().add_child(dialog_4);
+            assert_eq!(first_condition(&graph).expression(), Some(&expression));
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
+        }
 
-            dialog.borrow_mut().add_child(answers);
+        #[test]
+        fn test_parse_hesitated() {
+            let graph = parse_dialog("# Morgan\n\n- Sorry? | hesitated && karma < 0;\n").unwrap();
+            let expression = ConditionExpr::And(
+                Box::new(ConditionExpr::Hesitated),
+                Box::new(ConditionExpr::Compare(Operand::Karma, Comparison::Less, 0.)),
+            );
 
-            // println!("{}", dialog.borrow().print_flat());
+            assert_eq!(first_condition(&graph).expression(), Some(&expression));
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
+        }
 
-            assert_eq!(
-                dialog.borrow().print_file(),
-                "# Fabien
+        #[test]
+        fn test_parse_invalid_expressions() {
+            let error = parse_dialog("# Morgan\n\n- Fight | karma >= 10 && event: OlfBeaten;\n")
+                .unwrap_err();
+            assert_eq!((error.line, error.column), (3, 33));
+            assert_eq!(error.token, "OlfBeaten".to_string());
+            assert_eq!(error.message, "unknown game event".to_string());
 
-- Hello
+            let error =
+                parse_dialog("# Morgan\n\n- Fight | (karma >= 10 || hp < 1;\n").unwrap_err();
+            assert_eq!(error.message, "a parenthesis is not closed".to_string());
 
-## Morgan
+            let error = parse_dialog("# Morgan\n\n- Fight | karma >= lots;\n").unwrap_err();
+            assert_eq!((error.line, error.column), (3, 20));
 
-- Hey | None
-- No Hello | None
-- Want to share a flat ? | None
+            let error = parse_dialog("# Morgan\n\n- Fight | mana > 2;\n").unwrap_err();
+            assert_eq!(error.token, "mana".to_string());
 
-### Fabien
+            let error = parse_dialog("# Morgan\n\n- Fight | party.has(Hugo) Olf;\n").unwrap_err();
+            assert_eq!(error.token, "Olf".to_string());
+        }
+    }
 
-- :)
+    mod jumps {
+        use crate::ui::dialog_system::*;
 
-### Fabien
+        const CONVERGE: &str = "# Olf\n\n- So I have to get the throne\n\n## Morgan\n\n- ... | None\n- and some $ | None\n\n### Olf\n\n- And some $\n=> essential\n\n### Olf {#essential}\n\n- It's essential\n";
 
-- :O
+        const SIBLING: &str = "# Morgan\n\n- Fight | None\n- Flee | None\n\n## Olf\n\n=> too_late\n\n## Olf {#too_late}\n\n- Too late\n";
 
-### Fabien
+        const LOOP: &str = "# Olf {#ask}\n\n- Do you want it ?\n\n## Morgan\n\n- Yes | None\n- Ask me again | None\n\n### Olf\n\n- Here\n\n### Olf\n\n=> ask\n";
 
-- Sure\n"
-                    .to_string()
-            );
+        #[test]
+        fn test_parse_converge() {
+            let graph = parse_dialog(CONVERGE).unwrap();
+            let answers = graph[NodeId::ROOT].children[0];
+            let essential = graph.find_label("essential").unwrap();
+            let some_money = graph[answers].children[0];
+
+            assert_eq!(graph[answers].children[1], essential);
+            assert_eq!(graph[some_money].children, vec![essential]);
+            assert!(graph.is_jump(some_money, 0));
+            assert!(!graph.is_jump(answers, 1));
         }
 
         #[test]
-        fn test_print_from_file_monologue() {
-            let root = init_tree_file(String::from(
-                "# Olf\n\n- Hello\n- Did you just\n- Call me ?\n- Or was it my imagination\n",
-            ));
+        fn test_parse_jump_to_sibling() {
+            let graph = parse_dialog(SIBLING).unwrap();
+            let too_late = graph.find_label("too_late").unwrap();
+
+            // both choices lead to the same node, written only once
+            assert_eq!(graph[NodeId::ROOT].children, vec![too_late, too_late]);
+            assert!(graph.is_jump(NodeId::ROOT, 0));
+            assert!(!graph.is_jump(NodeId::ROOT, 1));
+            assert_eq!(graph.subgraph(NodeId::ROOT), graph);
+            assert_eq!(graph.print_file(), SIBLING.to_string());
+        }
 
-            assert_eq!(
-                root.borrow().print_file(),
-                "# Olf\n\n- Hello\n- Did you just\n- Call me ?\n- Or was it my imagination\n"
-                    .to_string()
-            );
+        #[test]
+        fn test_parse_loop() {
+            let graph = parse_dialog(LOOP).unwrap();
+            let answers = graph[NodeId::ROOT].children[0];
+
+            // the header holding only a jump stands for the root
+            assert_eq!(graph[answers].children[1], NodeId::ROOT);
+            assert_eq!(graph.subgraph(NodeId::ROOT), graph);
+
+            let mut cursor = DialogCursor::new();
+            cursor.dive(&graph, 0);
+            assert_eq!(cursor.dive(&graph, 1), Some(NodeId::ROOT));
+            cursor.dive(&graph, 0);
+            assert_eq!(cursor.path, vec![0, 1, 0]);
+            assert!(cursor.is_valid(&graph));
         }
 
         #[test]
-        fn test_init_tree_from_file_simple_text_1() {
-            let root = init_tree_file(String::from("# Olf\n\n- Hello\n"));
+        fn test_print_file_jumps() {
+            for dialog in [CONVERGE, SIBLING, LOOP] {
+                let graph = parse_dialog(dialog).unwrap();
+                assert_eq!(graph.print_file(), dialog.to_string());
+            }
+        }
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Olf"))));
+        #[test]
+        fn test_add_jump() {
+            let mut graph = parse_dialog(LOOP).unwrap();
+            let here = graph[graph[NodeId::ROOT].children[0]].children[0];
+            graph.add_jump(here, NodeId::ROOT);
 
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("Hello".to_string())]
+                graph.print_flat(),
+                "[Do you want it ?]->[[Yes, Ask me again]->[[Here]->[[=> ask]]; [=> ask]]]"
             );
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
         }
 
         #[test]
-        fn test_init_tree_from_file_space_overdose_1() {
-            let root = init_tree_file(String::from("#            Olf\n\n-      Hello\n"));
+        fn test_parse_unknown_label() {
+            let error = parse_dialog("# Olf\n\n- Hello\n=> goodbye\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Olf"))));
+            assert_eq!((error.line, error.column), (4, 1));
+            assert_eq!(error.token, "=> goodbye".to_string());
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("Hello".to_string())]
-            );
+        #[test]
+        fn test_parse_invalid_anchor() {
+            let error = parse_dialog("# Olf {#hello}\n\n- Hello\n\n## Olf {#hello}\n\n- Again\n")
+                .unwrap_err();
+            assert_eq!((error.line, error.column), (5, 8));
+            assert_eq!(error.token, "{#hello}".to_string());
+
+            let error = parse_dialog("# Olf {#hello world}\n\n- Hello\n").unwrap_err();
+            assert_eq!((error.line, error.column), (1, 7));
         }
+    }
+
+    mod timeouts {
+        use crate::ui::dialog_system::*;
+
+        const CORNERED: &str = "# Olf\n\n- You're cornered\n\n## Morgan {#cornered} [timeout=5s, default=2]\n\n- Fight | None\n- Flee | None\n- ... | None\n";
 
         #[test]
-        fn test_init_tree_from_file_space_overdose_2() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello         |   None\n- No Hello    | None\n",
-            ));
+        fn test_parse_timeout() {
+            let graph = parse_dialog(CORNERED).unwrap();
+            let answers = &graph[graph[NodeId::ROOT].children[0]];
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!(
+                answers.timeout,
+                Some(ChoiceTimeout {
+                    seconds: 5.,
+                    default: 2,
+                })
+            );
+            assert_eq!(answers.character, Some(String::from("Morgan")));
+            assert_eq!(answers.label, Some(String::from("cornered")));
+            assert_eq!(graph[NodeId::ROOT].timeout, None);
 
+            // the default is the first choice
+            let graph = parse_dialog("# Morgan [ timeout = 0.5 ]\n\n- Hey | None\n").unwrap();
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "No Hello".to_string(),
-                        condition: None
-                    }
-                ]
+                graph[NodeId::ROOT].timeout,
+                Some(ChoiceTimeout {
+                    seconds: 0.5,
+                    default: 0,
+                })
             );
         }
 
         #[test]
-        fn test_init_tree_from_file_space_deficiency_1() {
-            let root = init_tree_file(String::from("#Olf\n\n-Hello\n"));
+        fn test_print_file_timeout() {
+            let graph = parse_dialog(CORNERED).unwrap();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Olf"))));
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
+            assert!(graph
+                .print_file()
+                .contains("## Morgan {#cornered} [timeout=5s, default=2]\n"));
 
+            // an author ending by `]` is not a timeout
+            let graph = parse_dialog("# Hugo [the Great/]\n\n- Hey\n").unwrap();
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("Hello".to_string())]
+                graph[NodeId::ROOT].character,
+                Some(String::from("Hugo [the Great]"))
             );
+            assert_eq!(parse_dialog(&graph.print_file()).unwrap(), graph);
         }
 
         #[test]
-        fn test_init_tree_from_file_space_deficiency_2() {
-            let root = init_tree_file(String::from("# Morgan\n\n- Hello|None\n- No Hello|None\n"));
-
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
-
+        fn test_parse_invalid_timeout() {
+            let error = parse_dialog("# Morgan [timeout=soon]\n\n- Hey | None\n").unwrap_err();
+            assert_eq!((error.line, error.column), (1, 11));
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "No Hello".to_string(),
-                        condition: None
-                    }
-                ]
+                error.message,
+                "a timeout is a positive duration: `timeout=5s`".to_string()
             );
+
+            let error = parse_dialog("# Morgan [default=1]\n\n- Hey | None\n").unwrap_err();
+            assert_eq!(error.token, "[default=1]".to_string());
+
+            let error =
+                parse_dialog("# Morgan [timeout=5s, patience=2]\n\n- Hey | None\n").unwrap_err();
+            assert_eq!(error.token, "patience=2".to_string());
+
+            let error = parse_dialog("# Morgan timeout=5s]\n\n- Hey | None\n").unwrap_err();
+            assert_eq!((error.line, error.column), (1, 3));
         }
+    }
+
+    mod errors {
+        use crate::ui::dialog_system::*;
 
         #[test]
-        fn test_init_tree_from_file_monologue_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello\n- I was wondering\n-Alone...\n",
-            ));
+        fn test_parse_dialog_unknown_variable() {
+            let error = parse_dialog("# Olf\n\n- Hello {player.age}\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (3, 9));
+            assert_eq!(error.token, "{player.age}".to_string());
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Text("Hello".to_string()),
-                    DialogType::Text("I was wondering".to_string()),
-                    DialogType::Text("Alone...".to_string())
-                ]
-            );
+        #[test]
+        fn test_parse_dialog_unclosed_variable() {
+            let error = parse_dialog("# Olf\n\n- Hello {karma\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (3, 9));
         }
 
         #[test]
-        fn test_init_tree_from_file_simple_choice_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello | None\n- No Hello | None\n",
-            ));
+        fn test_parse_dialog_invalid_karma() {
+            let error = parse_dialog("# Morgan\n\n- Hello | k: -10,abc;\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (3, 18));
+            assert_eq!(error.token, "abc".to_string());
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "No Hello".to_string(),
-                        condition: None
-                    }
-                ]
-            );
+        #[test]
+        fn test_parse_dialog_missing_karma_bound() {
+            let error = parse_dialog("# Morgan\n\n- Hello | k: 10;\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (3, 11));
+            assert_eq!(error.token, "k: 10".to_string());
         }
 
         #[test]
-        fn test_init_tree_from_file_complex_choice_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello | None\n- No Hello | k: -10,0;\n",
-            ));
+        fn test_parse_dialog_unknown_game_event() {
+            let error = parse_dialog("# Morgan\n\n- Hello | e: OlfIsGone;\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (3, 14));
+            assert_eq!(error.token, "OlfIsGone".to_string());
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "No Hello".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: Some((-10, 0)),
-                            event: None
-                        })
-                    }
-                ]
-            );
+        #[test]
+        fn test_parse_dialog_unknown_trigger_event() {
+            let error = parse_dialog("# Olf\n\n- Hello\n\n-> HasFriend, Dance\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (5, 15));
+            assert_eq!(error.token, "Dance".to_string());
         }
 
         #[test]
-        fn test_init_tree_from_file_complex_choice_2() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello | None\n- Mary me Hugo. | e: HasCharisma;\n",
-            ));
+        fn test_parse_dialog_invalid_trigger_argument() {
+            let error = parse_dialog("# Olf\n\n- Hello\n\n-> Karma(+5), GiveItem(potion, lots)\n")
+                .unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (5, 32));
+            assert_eq!(error.token, "lots".to_string());
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "Mary me Hugo.".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: None,
-                            event: Some(vec![GameEvent::HasCharisma])
-                        })
-                    }
-                ]
-            );
+        #[test]
+        fn test_parse_dialog_wrong_trigger_arguments_number() {
+            let error = parse_dialog("# Olf\n\n- Hello\n\n-> Karma(5, 2)\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (5, 4));
+            assert_eq!(error.message, "this trigger event expects `Karma(delta)`");
         }
 
-        // allow to explicit/implicit karma/k; event/e
-        // extend the tolerance
+        #[test]
+        fn test_parse_dialog_unclosed_trigger_arguments() {
+            let error = parse_dialog("# Olf\n\n- Hello\n\n-> GiveItem(potion, 2\n").unwrap_err();
+
+            assert_eq!(error.message, "a parenthesis is not closed");
+        }
 
         #[test]
-        fn test_init_tree_from_file_complex_choice_3() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello | k: -50,100;\n- No Hello | karma : -100,0;\n",
-            ));
+        fn test_parse_dialog_mixed_node() {
+            let error = parse_dialog("# Olf\n\n- Hello\n- Fight me | None\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (4, 1));
+            assert_eq!(error.message, "a dialog node cannot mix texts and choices");
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: Some((-50, 100)),
-                            event: None
-                        })
-                    },
-                    DialogType::Choice {
-                        text: "No Hello".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: Some((-100, 0)),
-                            event: None
-                        })
-                    }
-                ]
-            );
+        #[test]
+        fn test_parse_dialog_orphan_header() {
+            let error = parse_dialog("## Olf\n\n- Hello\n\n# Morgan\n\n- Hey\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (5, 1));
+            assert_eq!(error.token, "#".to_string());
         }
 
         #[test]
-        fn test_init_tree_from_file_complex_choice_4() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello my Friend | e: HasFriend;\n- You droped this (*crown*) | event: HasCharisma;\n",
-            ));
+        fn test_parse_dialog_skipped_header_level() {
+            let error = parse_dialog("# Olf\n\n- Hello\n\n### Morgan\n\n- Hey\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (5, 1));
+            assert_eq!(error.token, "###".to_string());
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello my Friend".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: None,
-                            event: Some(vec![GameEvent::HasFriend])
-                        })
-                    },
-                    DialogType::Choice {
-                        text: "You droped this (*crown*)".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: None,
-                            event: Some(vec![GameEvent::HasCharisma])
-                        })
-                    }
-                ]
-            );
+        #[test]
+        fn test_parse_dialog_no_header() {
+            let error = parse_dialog("- Hello\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (1, 1));
         }
 
-        // allow to type MAX or MIN to select
+        #[test]
+        fn test_parse_dialog_unexpected_line() {
+            let error = parse_dialog("# Olf\n\n- Hello\nGoodbye my friend\n").unwrap_err();
+
+            assert_eq!((error.line, error.column), (4, 1));
+            assert_eq!(error.token, "Goodbye".to_string());
+        }
 
         #[test]
-        fn test_init_tree_from_file_complex_choice_karma_max_min() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello | k: -10,MAX;\n- No Hello | k: MIN,0;\n",
-            ));
+        fn test_parse_dialog_escaped_position() {
+            // the escaped `\n` does not end the line
+            let error = parse_dialog("# Olf\n\n- Hello /\nfriend | e: Olf;\n").unwrap_err();
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+            assert_eq!((error.line, error.column), (4, 13));
+        }
+    }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: Some((-10, KARMA_MAX)),
-                            event: None
-                        })
-                    },
-                    DialogType::Choice {
-                        text: "No Hello".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: Some((KARMA_MIN, 0)),
-                            event: None
-                        })
-                    }
-                ]
-            );
+    /// Random valid dialogs must be written and read back unchanged:
+    /// `init_tree_file(print_file(graph)) == graph`
+    mod round_trip {
+        use proptest::prelude::*;
+
+        use crate::{
+            constants::character::{KARMA_MAX, KARMA_MIN},
+            ui::{
+                dialog_condition::{Comparison, ConditionExpr, Operand},
+                dialog_system::*,
+            },
+            world_flags::GameEvent,
+        };
+
+        /// A DialogNode and the nodes written under it
+        #[derive(Clone, Debug)]
+        struct Tree {
+            node: DialogNode,
+            children: Vec<Tree>,
         }
 
-        #[test]
-        fn test_init_tree_from_file_simple_kinship_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello\n## Hugo\n- Hey! How are you ?\n",
-            ));
+        fn add_children(graph: &mut DialogGraph, parent: NodeId, children: Vec<Tree>) {
+            for child in children {
+                let node = graph.add_child(parent, child.node);
+                add_children(graph, node, child.children);
+            }
+        }
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
-            assert_eq!(
-                root.borrow().children[0].borrow().character,
-                Some((0, String::from("Hugo")))
-            );
+        /// The nodes are added in the order of the file, as the parser does.
+        fn to_graph(tree: Tree) -> DialogGraph {
+            let mut graph = DialogGraph::new(tree.node);
+            add_children(&mut graph, NodeId::ROOT, tree.children);
+            graph
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text("Hello".to_string())]
-            );
-            assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
-                vec![DialogType::Text("Hey! How are you ?".to_string())]
-            );
+        /// A name, or None for the Narator
+        fn author() -> impl Strategy<Value = Option<String>> {
+            prop::option::weighted(
+                0.8,
+                prop::sample::select(vec![
+                    "Olf",
+                    "Morgan",
+                    "Fabien lae Random",
+                    "Hélène",
+                    "L'Amiral",
+                    "Hugo/Olf",
+                    "Hugo [the Great]",
+                ])
+                .prop_map(String::from),
+            )
         }
 
-        #[test]
-        fn test_init_tree_from_file_monologue_2() {
-            let root = init_tree_file(String::from("# Morgan\n\n- Hello\n- I was wondering\n\n## Morgan\n\n- With Friends ! | event: HasFriend;\n- Alone... | None\n"));
+        /// Words, accented chars, the special chars of the format,
+        /// simple braces (`{`) and variables (`{karma}`)
+        fn text() -> impl Strategy<Value = String> {
+            prop::collection::vec(
+                prop_oneof![
+                    "[a-zA-Z0-9 ,.!?']{1,8}",
+                    "[éèàçùôÉßñ…]{1,3}",
+                    prop::sample::select(vec![
+                        "-", "->", "=>", "#", "{#", "|", "/", ";", ":", "\n", " ", "{", "}",
+                    ])
+                    .prop_map(String::from),
+                    prop::sample::select(DIALOG_VARIABLES.to_vec())
+                        .prop_map(|variable| format!("{{{}}}", variable)),
+                ],
+                1..6,
+            )
+            .prop_map(|parts| parts.concat())
+        }
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+        fn game_event() -> impl Strategy<Value = GameEvent> {
+            prop::sample::select(vec![
+                GameEvent::BeatTheGame,
+                GameEvent::FirstKill,
+                GameEvent::AreaCleared,
+                GameEvent::HasCharisma,
+                GameEvent::HasFriend,
+            ])
+        }
 
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Text("Hello".to_string()),
-                    DialogType::Text("I was wondering".to_string())
-                ]
-            );
+        fn operand() -> impl Strategy<Value = Operand> {
+            prop_oneof![
+                Just(Operand::Karma),
+                Just(Operand::Hp),
+                Just(Operand::PartySize),
+                // The special chars of the format and of the expressions are escaped
+                "[a-z_,;()|/]{1,8}".prop_map(Operand::Item),
+            ]
+        }
 
-            assert_eq!(
-                root.borrow().children[0].borrow().character,
-                Some((0, String::from("Morgan")))
-            );
+        fn expression() -> impl Strategy<Value = ConditionExpr> {
+            let leaf = prop_oneof![
+                game_event().prop_map(ConditionExpr::Event),
+                Just(ConditionExpr::Hesitated),
+                "[A-Za-z]{1,6}( [a-z0-9,;()|/]{1,4})?".prop_map(ConditionExpr::PartyHas),
+                (
+                    operand(),
+                    prop::sample::select(Comparison::ALL.to_vec()),
+                    // n/4 is written exactly
+                    (-400..400_i32).prop_map(|n| n as f32 / 4.)
+                )
+                    .prop_map(|(operand, comparison, number)| {
+                        ConditionExpr::Compare(operand, comparison, number)
+                    }),
+            ];
 
-            assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "With Friends !".to_string(),
-                        condition: Some(DialogCondition {
-                            karma_threshold: None,
-                            event: Some(vec![GameEvent::HasFriend])
-                        })
-                    },
-                    DialogType::Choice {
-                        text: "Alone...".to_string(),
-                        condition: None
-                    }
+            leaf.prop_recursive(3, 12, 2, |inner| {
+                prop_oneof![
+                    (inner.clone(), inner.clone()).prop_map(|(left, right)| {
+                        ConditionExpr::And(Box::new(left), Box::new(right))
+                    }),
+                    (inner.clone(), inner.clone()).prop_map(|(left, right)| {
+                        ConditionExpr::Or(Box::new(left), Box::new(right))
+                    }),
+                    inner.prop_map(|expression| ConditionExpr::Not(Box::new(expression))),
                 ]
-            );
+            })
         }
 
-        #[test]
-        fn test_init_tree_from_file_complex_kinship_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Hello | None\n- Do you want to work with me ? | None\n\n## Hugo\n\n- Hey! How are you ?\n\n## Hugo\n\n- I'm sure you'll do just fine without me.\n",
-            ));
-
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Hello".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "Do you want to work with me ?".to_string(),
-                        condition: None
+        fn condition() -> impl Strategy<Value = Option<DialogCondition>> {
+            (
+                prop::option::of((KARMA_MIN..=KARMA_MAX, KARMA_MIN..=KARMA_MAX)),
+                prop::option::of(prop::collection::vec(game_event(), 1..3)),
+                prop::option::of(expression()),
+            )
+                .prop_map(|(karma_threshold, event, expression)| {
+                    let condition = DialogCondition {
+                        // sorted by the parser
+                        karma_threshold: karma_threshold.map(|(a, b)| (a.min(b), a.max(b))),
+                        event,
+                        expression,
+                    };
+                    // a condition without any clause is written `None`
+                    if condition == DialogCondition::new() {
+                        None
+                    } else {
+                        Some(condition)
                     }
-                ]
-            );
+                })
+        }
 
-            println!("{}", root.borrow().print_file());
+        fn effect() -> impl Strategy<Value = ChoiceEffect> {
+            prop_oneof![
+                any::<i32>().prop_map(ChoiceEffect::Karma),
+                any::<i32>().prop_map(ChoiceEffect::Hp),
+                any::<i32>().prop_map(ChoiceEffect::Mana),
+            ]
+        }
 
-            // By choosing the n-eme choice, you will get the result of the n-eme child.
+        fn trigger() -> impl Strategy<Value = ThrowableEvent> {
+            prop_oneof![
+                Just(ThrowableEvent::FightEvent),
+                Just(ThrowableEvent::HasFriend),
+                any::<i32>().prop_map(ThrowableEvent::Karma),
+                // the separators of the arguments are escaped
+                ("[a-z_,()]{1,4}( [a-z_,()]{1,4})?", any::<u32>())
+                    .prop_map(|(item, amount)| ThrowableEvent::GiveItem { item, amount }),
+                any::<i32>().prop_map(ThrowableEvent::JoinTeam),
+            ]
+        }
 
-            assert_eq!(
-                root.borrow().children[0].borrow().character,
-                Some((0, String::from("Hugo")))
-            );
-            assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
-                vec![DialogType::Text("Hey! How are you ?".to_string())]
+        /// A node of texts or of choices, never both
+        fn node() -> impl Strategy<Value = DialogNode> {
+            let texts = prop::collection::vec(text().prop_map(DialogType::Text), 1..4);
+            let choices = prop::collection::vec(
+                (
+                    text(),
+                    condition(),
+                    prop::collection::vec(effect(), 0..3),
+                    -3..3_i32,
+                    1..4_u32,
+                )
+                    .prop_map(|(text, condition, effects, priority, weight)| {
+                        DialogType::Choice {
+                            text,
+                            condition,
+                            effects,
+                            priority,
+                            weight,
+                        }
+                    }),
+                1..4,
             );
 
-            assert_eq!(
-                root.borrow().children[1].borrow().character,
-                Some((0, String::from("Hugo")))
-            );
-            assert_eq!(
-                root.borrow().children[1].borrow().dialog_type,
-                vec![DialogType::Text(
-                    "I'm sure you'll do just fine without me.".to_string()
-                )]
-            );
-        }
+            let timeout =
+                prop::option::of((1..80_u32, 0..4_usize).prop_map(|(quarters, default)| {
+                    ChoiceTimeout {
+                        seconds: quarters as f32 / 4.,
+                        default,
+                    }
+                }));
 
-        // TODO: add test for multiple throwable event `-> HasFriend, FightEvent\n`
+            (
+                author(),
+                prop_oneof![texts, choices],
+                prop::collection::vec(trigger(), 0..3),
+                timeout,
+            )
+                .prop_map(|(author, dialog_type, trigger_event, timeout)| DialogNode {
+                    dialog_type,
+                    character: author,
+                    trigger_event,
+                    timeout,
+                    ..DialogNode::new()
+                })
+        }
 
-        #[test]
-        fn test_init_tree_from_file_throwable_event_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Let's Talk | None\n- Let's Fight | None\n\n## Hugo\n\n- :)\n\n-> HasFriend\n\n## Hugo\n\n- :(\n\n-> FightEvent\n",
-            ));
+        fn tree() -> impl Strategy<Value = DialogGraph> {
+            let leaf = node().prop_map(|node| Tree {
+                node,
+                children: vec![],
+            });
+
+            leaf.prop_recursive(4, 24, 3, |inner| {
+                (node(), prop::collection::vec(inner, 0..4))
+                    .prop_map(|(node, children)| Tree { node, children })
+            })
+            .prop_map(to_graph)
+        }
 
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
-            assert_eq!(
-                root.borrow().dialog_type,
-                vec![
-                    DialogType::Choice {
-                        text: "Let's Talk".to_string(),
-                        condition: None
-                    },
-                    DialogType::Choice {
-                        text: "Let's Fight".to_string(),
-                        condition: None
+        /// A tree with some labelled nodes, and some jumps:
+        /// to any node (loops, converging branches) or to a node already written under it
+        fn graph() -> impl Strategy<Value = DialogGraph> {
+            tree()
+                .prop_flat_map(|graph| {
+                    let len = graph.nodes.len();
+                    (
+                        Just(graph),
+                        prop::collection::vec(prop::option::of("[a-z][a-z0-9_-]{0,5}"), len),
+                        prop::collection::vec((0..len, 0..len, any::<bool>()), 0..4),
+                    )
+                })
+                .prop_map(|(mut graph, labels, jumps)| {
+                    for (id, label) in labels.into_iter().enumerate() {
+                        // a label anchors a single header
+                        graph[NodeId(id)].label = label.map(|label| format!("{}_{}", label, id));
                     }
-                ]
-            );
 
-            println!("{}", root.borrow().print_file());
-
-            // By choosing the n-eme choice, you will get the result of the n-eme child.
+                    for (from, to, to_child) in jumps {
+                        let from = NodeId(from);
+                        let children = &graph[from].children;
+                        let to = if to_child && !children.is_empty() {
+                            children[to % children.len()]
+                        } else {
+                            NodeId(to)
+                        };
+                        // the anchor printed for an unlabelled node would be parsed as its label
+                        if graph[to].label.is_none() {
+                            graph[to].label = Some(format!("target_{}", to.0));
+                        }
+                        graph.add_jump(from, to);
+                    }
+                    graph
+                })
+        }
 
-            // first child
-            assert_eq!(
-                root.borrow().children[0].borrow().character,
-                Some((0, String::from("Hugo")))
-            );
-            assert_eq!(
-                root.borrow().children[0].borrow().dialog_type,
-                vec![DialogType::Text(":)".to_string())]
-            );
-            assert_eq!(
-                root.borrow().children[0].borrow().trigger_event,
-                vec![ThrowableEvent::HasFriend]
-            );
+        proptest! {
+            #[test]
+            fn test_print_then_parse(graph in graph()) {
+                prop_assert_eq!(init_tree_file(graph.print_file()), graph);
+            }
 
-            // second child
-            assert_eq!(
-                root.borrow().children[1].borrow().character,
-                Some((0, String::from("Hugo")))
-            );
-            assert_eq!(
-                root.borrow().children[1].borrow().dialog_type,
-                vec![DialogType::Text(":(".to_string())]
-            );
-            assert_eq!(
-                root.borrow().children[1].borrow().trigger_event,
-                vec![ThrowableEvent::FightEvent]
-            );
+            #[test]
+            fn test_print_is_stable(graph in graph()) {
+                let file = graph.print_file();
+                prop_assert_eq!(init_tree_file(file.clone()).print_file(), file);
+            }
         }
 
         #[test]
-        fn test_init_tree_from_file_except_1() {
-            let root = init_tree_file(String::from(
-                "# Morgan\n\n- Bonjour Florian. /\nComment vas/-tu :/# ? /\nJ'ai faim. /<3 /</|3\n",
-            ));
-
-            assert_eq!(root.borrow().character, Some((0, String::from("Morgan"))));
+        fn test_print_escaped_text() {
+            let mut node = DialogNode::new();
+            node.character = Some(String::from(" Olf/Hugo"));
+            node.dialog_type = vec![DialogType::Choice {
+                text: String::from(" admin | {name} is {karma}} "),
+                condition: None,
+                effects: vec![],
+                priority: 0,
+                weight: 1,
+            }];
+            node.trigger_event = vec![ThrowableEvent::FightEvent, ThrowableEvent::Karma(5)];
+            let graph = DialogGraph::new(node);
 
             assert_eq!(
-                root.borrow().dialog_type,
-                vec![DialogType::Text(
-                    "Bonjour Florian. \nComment vas-tu :# ? \nJ'ai faim. <3 <|3".to_string()
-                )]
+                graph.print_file(),
+                "# / Olf//Hugo\n\n- / admin /| /{name/} is {karma}/}/  | None\n-> FightEvent, Karma(+5)\n"
             );
+            assert_eq!(init_tree_file(graph.print_file()), graph);
         }
     }
 
     // #[test]
     // fn test_add_child() {
-    //     let root = init_tree_flat(String::from("[0,1,[3,4,5,[7,8]],2]"));
+    //     let graph = init_tree_flat(String::from("[0,1,[3,4,5,[7,8]],2]"));
     //     let new_node = Rc::new(RefCell::new(TreeNode::new()));
     //     new_node.borrow_mut().value = Some(9);
-    //     let child = &root.borrow().children[2];
+    //     let child = &graph[NodeId::ROOT].children[2];
     //     child.borrow_mut().add_child(new_node);
-    //     assert_eq!(root.borrow().print_flat(), "[0,1,[3,4,5,[7,8],9],2]");
+    //     assert_eq!(graph.print_flat(), "[0,1,[3,4,5,[7,8],9],2]");
     // }
 }
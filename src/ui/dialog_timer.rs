@@ -0,0 +1,131 @@
+//! Timed Choices
+//!
+//! A header can give the player a limited time to answer: `## Morgan [timeout=5s, default=2]`
+//!
+//! - a ChoiceCountdown is started on the UI Wall when the player's choices of a timed node are shown
+//!   - the ChoiceTimerBar drains over the player scroll
+//! - when the time is up
+//!   - the `default` choice is picked (a DialogDiveEvent),
+//!     or the first shown one if the default is hidden by its condition
+//!   - the player has hesitated (`DialogCursor::hesitated`):
+//!     the next choices can require it (`hesitated`), until the player picks one
+//! - the countdown stops as soon as the node is left
+
+use bevy::prelude::*;
+
+use crate::{
+    constants::ui::dialogs::CHOICE_TIMER_BAR_WIDTH,
+    ui::{
+        dialog_panel::{is_said_by_mc, DialogPanel},
+        dialog_player::DialogDiveEvent,
+        dialog_scroll::{PlayerScroll, Scroll},
+        dialog_system::DialogCursor,
+    },
+};
+
+/// The time left to the player to pick a choice, on the UI Wall.
+#[derive(Component)]
+pub struct ChoiceCountdown {
+    timer: Timer,
+    /// The index of the choice picked when the time is up
+    default: usize,
+    /// The visit of the timed node: a dialog can loop back to it
+    cursor: DialogCursor,
+}
+
+/// The bar draining while a ChoiceCountdown runs
+#[derive(Component)]
+pub struct ChoiceTimerBar;
+
+/// Starts a ChoiceCountdown when the current node holds timed choices of the player,
+/// stops it when the node is left.
+pub fn start_choice_countdown(
+    mut commands: Commands,
+    panel_query: Query<(Entity, &DialogPanel, Option<&ChoiceCountdown>), Changed<DialogPanel>>,
+) {
+    for (ui_wall, panel, countdown) in panel_query.iter() {
+        let timeout = panel
+            .cursor
+            .current(&panel.dialog_tree)
+            .filter(|current| current.is_choice() && is_said_by_mc(current))
+            .and_then(|current| current.timeout);
+
+        match (timeout, countdown) {
+            // still the same visit
+            (Some(_), Some(countdown)) if countdown.cursor == panel.cursor => {}
+            (Some(timeout), _) => {
+                commands.entity(ui_wall).insert(ChoiceCountdown {
+                    timer: Timer::from_seconds(timeout.seconds, TimerMode::Once),
+                    default: timeout.default,
+                    cursor: panel.cursor.clone(),
+                });
+            }
+            (None, Some(_)) => {
+                commands.entity(ui_wall).remove::<ChoiceCountdown>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Drains the ChoiceTimerBar, and picks the default choice when the time is up.
+pub fn tick_choice_countdown(
+    mut commands: Commands,
+    time: Res<Time>,
+
+    mut countdown_query: Query<(Entity, &mut ChoiceCountdown)>,
+    player_scroll_query: Query<&PlayerScroll, With<Scroll>>,
+    mut bar_query: Query<(&mut Style, &mut Visibility), With<ChoiceTimerBar>>,
+
+    mut dialog_dive_event: EventWriter<DialogDiveEvent>,
+) {
+    let countdown = countdown_query.get_single_mut().ok();
+
+    for (mut style, mut visibility) in bar_query.iter_mut() {
+        match &countdown {
+            Some((_, countdown)) => {
+                style.width = Val::Px(CHOICE_TIMER_BAR_WIDTH * countdown.timer.percent_left());
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    let (ui_wall, mut countdown) = match countdown {
+        Some(countdown) => countdown,
+        None => return,
+    };
+    if !countdown.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // some choices can be hidden: the default one may not be shown
+    let shown: Vec<usize> = player_scroll_query
+        .get_single()
+        .map_or(Vec::new(), |player_scroll| {
+            player_scroll
+                .choices
+                .iter()
+                .map(|(index, _)| *index)
+                .collect()
+        });
+    let child_index = if shown.contains(&countdown.default) {
+        Some(countdown.default)
+    } else {
+        shown.first().copied()
+    };
+
+    match child_index {
+        Some(child_index) => {
+            info!("The time is up: the choice n°{} is picked", child_index);
+            dialog_dive_event.send(DialogDiveEvent {
+                child_index,
+                skip: false,
+                timed_out: true,
+            });
+        }
+        None => warn!("The time is up but no choice is shown"),
+    }
+
+    commands.entity(ui_wall).remove::<ChoiceCountdown>();
+}
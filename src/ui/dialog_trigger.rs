@@ -0,0 +1,104 @@
+//! Trigger Events
+//!
+//! A dialog node can throw some ThrowableEvent when it is left (`-> FightEvent`).
+//!
+//! - Each kind of ThrowableEvent is bound to a handler in the TriggerRegistry
+//!   - any plugin can bind its own with `app.register_trigger(ThrowableEvent::KARMA, handler)`
+//! - throw_trigger_event runs the handler of every trigger thrown by a TriggerEvent
+//!
+//! The ui never has to know what a trigger does.
+
+use bevy::{ecs::event::ManualEventReader, prelude::*, utils::HashMap};
+
+use crate::ui::dialog_system::{ThrowableEvent, TriggerEvent};
+
+/// Runs the effect of a ThrowableEvent
+/// with the interlocutor of the dialog which threw it.
+pub type TriggerHandler = Box<dyn Fn(&mut World, Entity, &ThrowableEvent) + Send + Sync>;
+
+/// Binds each kind of ThrowableEvent (`ThrowableEvent::kind()`) to its handler.
+#[derive(Resource, Default)]
+pub struct TriggerRegistry {
+    handlers: HashMap<&'static str, TriggerHandler>,
+}
+
+impl TriggerRegistry {
+    /// Replaces (with a warning) any handler already bound to this `kind`.
+    pub fn register(&mut self, kind: &'static str, handler: TriggerHandler) {
+        if self.handlers.insert(kind, handler).is_some() {
+            warn!("The trigger {} had already a handler, it is replaced", kind);
+        }
+    }
+
+    /// # Return
+    ///
+    /// false if no handler is bound to the kind of this `trigger`
+    pub fn dispatch(
+        &self,
+        world: &mut World,
+        interlocutor: Entity,
+        trigger: &ThrowableEvent,
+    ) -> bool {
+        match self.handlers.get(trigger.kind()) {
+            Some(handler) => {
+                handler(world, interlocutor, trigger);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Lets any plugin bind a handler to a kind of ThrowableEvent.
+pub trait TriggerAppExt {
+    fn register_trigger(
+        &mut self,
+        kind: &'static str,
+        handler: impl Fn(&mut World, Entity, &ThrowableEvent) + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl TriggerAppExt for App {
+    fn register_trigger(
+        &mut self,
+        kind: &'static str,
+        handler: impl Fn(&mut World, Entity, &ThrowableEvent) + Send + Sync + 'static,
+    ) -> &mut Self {
+        // the UiPlugin can be added after the plugin which registers the trigger
+        self.world
+            .get_resource_or_insert_with(TriggerRegistry::default)
+            .register(kind, Box::new(handler));
+        self
+    }
+}
+
+/// Runs the handler of every trigger thrown by a TriggerEvent.
+///
+/// # Note
+///
+/// Exclusive system: a handler can modify anything in the World
+/// (insert components, send events, change the state, etc).
+pub fn throw_trigger_event(
+    world: &mut World,
+    mut trigger_event: Local<ManualEventReader<TriggerEvent>>,
+) {
+    let thrown: Vec<(Entity, Vec<ThrowableEvent>)> = trigger_event
+        .iter(world.resource::<Events<TriggerEvent>>())
+        .map(|event| (event.interlocutor, event.triggers.clone()))
+        .collect();
+
+    if thrown.is_empty() {
+        return;
+    }
+
+    world.resource_scope(|world, registry: Mut<TriggerRegistry>| {
+        for (interlocutor, triggers) in thrown {
+            for trigger in triggers.iter() {
+                info!("Trigger Event: {}", trigger);
+                if !registry.dispatch(world, interlocutor, trigger) {
+                    warn!("The trigger {} has no handler registered", trigger);
+                }
+            }
+        }
+    });
+}
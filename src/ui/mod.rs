@@ -1,10 +1,23 @@
 use bevy::{prelude::*, winit::WinitSettings};
 
+pub mod dialog_advance;
+pub mod dialog_asset;
 mod dialog_box;
+pub mod dialog_cast;
+pub mod dialog_condition;
+pub mod dialog_export;
+pub mod dialog_focus;
+pub mod dialog_history;
+pub mod dialog_lint;
+pub mod dialog_locale;
+pub mod dialog_markup;
 pub mod dialog_panel;
 mod dialog_player;
 pub mod dialog_scroll;
+pub mod dialog_speaker;
 pub mod dialog_system;
+pub mod dialog_timer;
+pub mod dialog_trigger;
 
 pub struct UiPlugin;
 
@@ -14,6 +27,8 @@ impl Plugin for UiPlugin {
         app
             // OPTIMIZE: Only run the app when there is user input. This will significantly reduce CPU/GPU use.
             .insert_resource(WinitSettings::game())
+            .add_asset::<dialog_asset::DialogAsset>()
+            .init_asset_loader::<dialog_asset::DialogLoader>()
             .add_event::<dialog_panel::CreateDialogPanelEvent>()
             .add_event::<dialog_panel::CloseDialogPanelEvent>()
             .add_event::<dialog_panel::EndNodeDialogEvent>()
@@ -22,18 +37,28 @@ impl Plugin for UiPlugin {
             .add_event::<dialog_player::DropFirstTextUpperScroll>()
             .add_event::<dialog_box::ResetDialogBoxEvent>()
             // Trigger Event
-            // .add_event::<dialog_system::FightEvent>()
-            // .add_event::<dialog_system::TriggerEvent>()
+            .init_resource::<dialog_trigger::TriggerRegistry>()
+            .init_resource::<dialog_panel::DialogRng>()
+            .init_resource::<dialog_locale::Locale>()
+            .init_resource::<dialog_history::DialogHistory>()
+            .init_resource::<dialog_advance::AdvanceMode>()
+            .init_resource::<dialog_focus::ChoiceFocus>()
+            .add_event::<dialog_system::TriggerEvent>()
+            .add_event::<dialog_system::ChoiceEffectEvent>()
             .add_systems(Startup, dialog_panel::load_textures)
             // OPTIMIZE: System Ordering
             .add_systems(
                 Update,
                 (
+                    dialog_asset::update_dialog_on_asset_event,
                     dialog_panel::create_dialog_panel_on_key_press,
                     dialog_panel::create_dialog_panel_on_combat_event,
                     dialog_panel::create_dialog_panel,
+                    dialog_panel::pick_npc_choice.before(dialog_panel::update_dialog_panel),
                     dialog_panel::update_dialog_panel,
                     dialog_panel::update_dialog_tree,
+                    dialog_speaker::update_speaker,
+                    dialog_cast::leave_cast_dialog,
                     dialog_scroll::animate_scroll,
                     dialog_scroll::update_upper_scroll,
                     dialog_scroll::update_player_scroll,
@@ -44,10 +69,70 @@ impl Plugin for UiPlugin {
                     dialog_player::skip_forward_dialog,
                     dialog_player::dialog_dive,
                     dialog_player::drop_first_text_upper_scroll,
-                    // crash when in this big tuple: (but not when in a simple `.add_systems()`)
-                    // dialog_player::throw_trigger_event.after(dialog_player::dialog_dive),
                 ),
             )
+            .add_systems(
+                Update,
+                (
+                    dialog_locale::switch_locale_on_key_press,
+                    dialog_locale::translate_dialogs
+                        .after(dialog_locale::switch_locale_on_key_press),
+                    dialog_locale::fallback_missing_translations,
+                ),
+            )
+            .add_systems(
+                Update,
+                dialog_box::shake_dialog_box.after(dialog_box::update_dialog_box),
+            )
+            .add_systems(
+                Update,
+                (
+                    dialog_timer::start_choice_countdown,
+                    dialog_timer::tick_choice_countdown
+                        .after(dialog_timer::start_choice_countdown)
+                        .before(dialog_player::dialog_dive),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    dialog_advance::toggle_advance_mode,
+                    dialog_advance::update_advance_mode_button,
+                    dialog_advance::advance_dialog.before(dialog_player::dialog_dive),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    dialog_focus::reset_choice_focus,
+                    dialog_focus::navigate_choices
+                        .after(dialog_focus::reset_choice_focus)
+                        .before(dialog_player::dialog_dive),
+                    dialog_focus::highlight_focused_choice
+                        .after(dialog_focus::navigate_choices)
+                        .after(dialog_player::button_system),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    dialog_scroll::turn_choice_page,
+                    dialog_scroll::update_choice_page_buttons,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    dialog_history::toggle_history_panel_on_key_press,
+                    dialog_history::update_history_panel,
+                    dialog_history::scroll_history_panel,
+                ),
+            )
+            // exclusive system: the handlers can modify the whole World
+            .add_systems(
+                Update,
+                dialog_trigger::throw_trigger_event.after(dialog_player::dialog_dive),
+            )
             // crash when in this big tuple: (but not when in a simple `.add_systems()`)
             .add_systems(Update, dialog_panel::end_node_dialog)
             .add_systems(Update, dialog_panel::close_dialog_panel)
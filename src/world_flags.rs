@@ -0,0 +1,107 @@
+//! World Flags
+//!
+//! Remembers every GameEvent which has already happened in the game.
+//!
+//! - Any system can raise a GameEvent
+//!   - directly, with `ResMut<WorldFlags>`
+//!   - or by sending a RaiseFlagEvent
+//! - The DialogCondition of a choice reads these flags
+
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct WorldFlagsPlugin;
+
+impl Plugin for WorldFlagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldFlags>()
+            .add_event::<RaiseFlagEvent>()
+            .add_systems(Update, raise_flags);
+    }
+}
+
+/// An event which can occurs only once in the game,
+/// and can be required by a DialogCondition.
+#[derive(Reflect, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum GameEvent {
+    BeatTheGame,
+    FirstKill,
+    AreaCleared,
+    HasCharisma,
+    HasFriend,
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameEvent::BeatTheGame => write!(f, "BeatTheGame"),
+            GameEvent::FirstKill => write!(f, "FirstKill"),
+            GameEvent::AreaCleared => write!(f, "AreaCleared"),
+            GameEvent::HasCharisma => write!(f, "HasCharisma"),
+            GameEvent::HasFriend => write!(f, "HasFriend"),
+        }
+    }
+}
+
+impl FromStr for GameEvent {
+    type Err = (); // ParseIntError;
+
+    fn from_str(input: &str) -> Result<GameEvent, Self::Err> {
+        match input {
+            "BeatTheGame" => Ok(GameEvent::BeatTheGame),
+            "FirstKill" => Ok(GameEvent::FirstKill),
+            "AreaCleared" => Ok(GameEvent::AreaCleared),
+            "HasCharisma" => Ok(GameEvent::HasCharisma),
+            "HasFriend" => Ok(GameEvent::HasFriend),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Holds all the GameEvent which have already happened.
+#[derive(Resource, Serialize, Deserialize, Default, PartialEq, Clone, Debug)]
+pub struct WorldFlags {
+    raised: HashSet<GameEvent>,
+}
+
+impl WorldFlags {
+    /// # Return
+    ///
+    /// true if the `event` had not happened yet
+    pub fn raise(&mut self, event: GameEvent) -> bool {
+        self.raised.insert(event)
+    }
+
+    pub fn has(&self, event: GameEvent) -> bool {
+        self.raised.contains(&event)
+    }
+
+    /// # Return
+    ///
+    /// true if every one of these `events` has already happened
+    pub fn has_all(&self, events: &[GameEvent]) -> bool {
+        events.iter().all(|event| self.has(*event))
+    }
+}
+
+/// Happens when
+///   - any system
+///     - a GameEvent occurs
+/// Read in
+///   - world_flags::raise_flags
+///     - records the GameEvent in the WorldFlags
+#[derive(Event)]
+pub struct RaiseFlagEvent(pub GameEvent);
+
+pub fn raise_flags(
+    mut raise_flag_events: EventReader<RaiseFlagEvent>,
+    mut world_flags: ResMut<WorldFlags>,
+) {
+    for RaiseFlagEvent(event) in raise_flag_events.iter() {
+        if world_flags.raise(*event) {
+            info!("GameEvent raised: {}", event);
+        }
+    }
+}